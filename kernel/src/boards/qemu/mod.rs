@@ -1,15 +1,113 @@
 pub use exit_handle::*;
 
-use crate::entry::KERNEL_ADDR_OFFSET;
+use crate::{config::SV39_PAGE_SIZE, dtb, entry::KERNEL_ADDR_OFFSET};
+use alloc::vec::Vec;
 
-pub const CLOCK_FREQ: usize = 12500000;
+/// RAM below it holds the kernel layout, see `config::mm`
 pub const MEMORY_END: usize = 0x88000000;
-pub const MMIO: &[(usize, usize)] = &[
-    // (addr, len)
-    (VIRT_TEST as usize, 0x2000),
-    (VIRT_IO, 0x1000),
-];
+const MEMORY_START: usize = 0x80000000;
+const DEFAULT_CLOCK_FREQ: usize = 12500000;
 
 mod exit_handle;
 
-pub const VIRT_IO: usize = 0x10001000 + KERNEL_ADDR_OFFSET;
+pub fn init(dtb_pa: usize) {
+    dtb::init(dtb_pa);
+}
+
+/// End of the RAM the kernel was loaded into, in the kernel address space.
+/// A smaller machine ends below `MEMORY_END` and the layout is cut there.
+pub fn memory_end() -> usize {
+    dtb::get_machine_info()
+        .memory()
+        .iter()
+        .find(|&&(addr, size)| addr <= MEMORY_START && MEMORY_START < addr + size)
+        .map_or(MEMORY_END, |&(addr, size)| (addr + size).min(MEMORY_END))
+        + KERNEL_ADDR_OFFSET
+}
+
+pub fn clock_freq() -> usize {
+    dtb::get_machine_info()
+        .clock_freq()
+        .unwrap_or(DEFAULT_CLOCK_FREQ)
+}
+
+pub fn has_sstc() -> bool {
+    dtb::get_machine_info().has_sstc()
+}
+
+/// RAM beyond `MEMORY_END` in the kernel address space, (start, end)
+pub fn extra_memory() -> Vec<(usize, usize)> {
+    dtb::get_machine_info()
+        .memory()
+        .iter()
+        .filter_map(|&(addr, size)| {
+            let start = addr.max(MEMORY_END);
+            let end = addr + size;
+            if start >= end {
+                return None;
+            }
+            // RAM beyond the top of the address space is not reachable
+            let start = start.checked_add(KERNEL_ADDR_OFFSET)?;
+            let end = end
+                .checked_add(KERNEL_ADDR_OFFSET)
+                .unwrap_or(usize::MAX & !(SV39_PAGE_SIZE - 1));
+            Some((start, end))
+        })
+        .filter(|&(start, end)| start < end)
+        .collect()
+}
+
+/// Initrd loaded by QEMU `-initrd` in the kernel address space, (start, end)
+pub fn initrd() -> Option<(usize, usize)> {
+    dtb::get_machine_info()
+        .initrd()
+        .map(|(start, end)| (start + KERNEL_ADDR_OFFSET, end + KERNEL_ADDR_OFFSET))
+}
+
+/// Every MMIO region in the kernel address space, (addr, len)
+pub fn mmio() -> Vec<(usize, usize)> {
+    let machine_info = dtb::get_machine_info();
+    let mut mmio: Vec<(usize, usize)> = [(VIRT_TEST as usize - KERNEL_ADDR_OFFSET, 0x1000)]
+        .iter()
+        .chain(machine_info.virtio())
+        .chain(machine_info.uart())
+        .chain(machine_info.plic())
+        .chain(machine_info.rtc())
+        .map(|&(addr, len)| {
+            let start = addr & !(SV39_PAGE_SIZE - 1);
+            let end = (addr + len + SV39_PAGE_SIZE - 1) & !(SV39_PAGE_SIZE - 1);
+            (start, end)
+        })
+        .collect();
+
+    // devices may share a page, merge them to avoid mapping twice
+    mmio.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in mmio {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(start, end)| (start + KERNEL_ADDR_OFFSET, end - start))
+        .collect()
+}
+
+/// Base addresses of virtio-mmio slots in the kernel address space
+pub fn virtio_mmio() -> Vec<usize> {
+    dtb::get_machine_info()
+        .virtio()
+        .iter()
+        .map(|&(addr, _)| addr + KERNEL_ADDR_OFFSET)
+        .collect()
+}
+
+/// Base address of the goldfish RTC in the kernel address space
+pub fn virt_rtc() -> Option<usize> {
+    dtb::get_machine_info()
+        .rtc()
+        .first()
+        .map(|&(addr, _)| addr + KERNEL_ADDR_OFFSET)
+}
@@ -1,3 +1,14 @@
+use super::*;
+
 pub const ROOT_DIR: &str = "/";
 pub const CURRENT_DIR: &str = ".";
 pub const DIR_SEPARATOR: &str = "/";
+pub const DISK_MOUNT_POINT: &str = "/mnt";
+
+pub const PIPE_DEFAULT_SIZE: usize = 16 * SV39_PAGE_SIZE; // 64 KB
+
+// like /proc/sys/fs/pipe-max-size
+pub const PIPE_MAX_SIZE: usize = 256 * SV39_PAGE_SIZE; // 1 MB
+
+// the frames all pipe buffers may hold together
+pub const PIPE_TOTAL_MAX_SIZE: usize = 4096 * SV39_PAGE_SIZE; // 16 MB
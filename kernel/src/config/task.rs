@@ -2,3 +2,13 @@ use super::*;
 
 pub const USER_STACK_SIZE: usize = 0x200000; // 2 MB
 pub const KERNEL_STACK_SIZE: usize = SV39_PAGE_SIZE;
+// POSIX timers a process may hold, the _POSIX_TIMER_MAX minimum
+pub const POSIX_TIMER_MAX: usize = 32;
+
+// per-task kernel stack in stackful mode
+#[cfg(feature = "stackful")]
+pub const TASK_KERNEL_STACK_SIZE: usize = 4 * SV39_PAGE_SIZE; // 16 KB
+
+// load addresses of ET_DYN executables and of the ELF interpreter
+pub const ELF_DYN_BASE: usize = 0x1000_0000;
+pub const ELF_INTERP_BASE: usize = 0x4000_0000;
@@ -1,3 +1,5 @@
+pub use rtc::*;
 pub use virtio::*;
 
+mod rtc;
 mod virtio;
@@ -0,0 +1,28 @@
+use core::ptr::read_volatile;
+
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+
+// region GoldfishRtc begin
+/// Goldfish RTC of the QEMU virt machine, counting nanoseconds since the unix epoch
+pub struct GoldfishRtc {
+    base: usize,
+}
+
+impl GoldfishRtc {
+    pub const fn new(base: usize) -> Self {
+        Self { base }
+    }
+
+    pub fn read_time(&self) -> usize {
+        // reading TIME_LOW latches TIME_HIGH
+        let low = self.read_reg(TIME_LOW) as usize;
+        let high = self.read_reg(TIME_HIGH) as usize;
+        (high << 32) | low
+    }
+
+    fn read_reg(&self, offset: usize) -> u32 {
+        unsafe { read_volatile((self.base + offset) as *const u32) }
+    }
+}
+// region GoldfishRtc end
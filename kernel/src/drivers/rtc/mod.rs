@@ -0,0 +1,3 @@
+pub use goldfish::*;
+
+mod goldfish;
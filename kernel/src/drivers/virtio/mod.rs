@@ -1,9 +1,29 @@
 use crate::{
+    board,
     config::KERNEL_ADDR_OFFSET,
     mm::{self, PhysAddr, PpnOffset},
 };
 use core::{mem::forget, ptr::NonNull};
-use virtio_drivers::Hal;
+use virtio_drivers::{
+    transport::{
+        mmio::{MmioTransport, VirtIOHeader},
+        DeviceType, Transport,
+    },
+    Hal,
+};
+
+/// Find the `index`-th virtio-mmio device of `device_type`, ordered by address
+pub fn probe_virtio_device(device_type: DeviceType, index: usize) -> Option<MmioTransport> {
+    board::virtio_mmio()
+        .into_iter()
+        .filter_map(|addr| {
+            let header = NonNull::new(addr as *mut VirtIOHeader).unwrap();
+            // empty slots are rejected by the transport
+            let transport = unsafe { MmioTransport::new(header) }.ok()?;
+            (transport.device_type() == device_type).then_some(transport)
+        })
+        .nth(index)
+}
 
 // region VirtIOHal begin
 pub struct VirtIOHal;
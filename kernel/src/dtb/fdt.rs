@@ -0,0 +1,236 @@
+use alloc::vec::Vec;
+use core::{slice, str};
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_HEADER_SIZE: usize = 40;
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+
+// region Fdt begin
+/// Flattened device tree, all fields are big-endian
+pub struct Fdt<'a> {
+    root: FdtNode<'a>,
+}
+
+impl<'a> Fdt<'a> {
+    /// # Safety
+    ///
+    /// `ptr` must point to a readable device tree blob which outlives `'a`.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Option<Self> {
+        let header = slice::from_raw_parts(ptr, FDT_HEADER_SIZE);
+        if read_u32(header, 0)? != FDT_MAGIC {
+            return None;
+        }
+        let total_size = read_u32(header, 4)? as usize;
+        if total_size < FDT_HEADER_SIZE {
+            return None;
+        }
+        Self::from_bytes(slice::from_raw_parts(ptr, total_size))
+    }
+
+    pub fn from_bytes(blob: &'a [u8]) -> Option<Self> {
+        if read_u32(blob, 0)? != FDT_MAGIC {
+            return None;
+        }
+        let read_header = |offset: usize| read_u32(blob, offset).map(|v| v as usize);
+        let struct_begin = read_header(8)?;
+        let strings_begin = read_header(12)?;
+        let strings_size = read_header(32)?;
+        let struct_size = read_header(36)?;
+
+        let mut parser = FdtParser {
+            structs: blob.get(struct_begin..struct_begin + struct_size)?,
+            strings: blob.get(strings_begin..strings_begin + strings_size)?,
+            offset: 0,
+        };
+        // skip leading NOPs
+        while parser.peek_token()? == FDT_NOP {
+            parser.offset += 4;
+        }
+        let root = parser.parse_node()?;
+        Some(Self { root })
+    }
+
+    pub fn root(&self) -> &FdtNode<'a> {
+        &self.root
+    }
+}
+// region Fdt end
+
+// region FdtNode begin
+pub struct FdtNode<'a> {
+    name: &'a str,
+    props: Vec<FdtProp<'a>>,
+    children: Vec<FdtNode<'a>>,
+}
+
+impl<'a> FdtNode<'a> {
+    /// Node name without the unit address
+    pub fn base_name(&self) -> &'a str {
+        self.name.split('@').next().unwrap_or(self.name)
+    }
+
+    pub fn children(&self) -> &[FdtNode<'a>] {
+        &self.children
+    }
+
+    pub fn child(&self, name: &str) -> Option<&FdtNode<'a>> {
+        self.children
+            .iter()
+            .find(|node| node.name == name || node.base_name() == name)
+    }
+
+    pub fn prop(&self, name: &str) -> Option<&'a [u8]> {
+        self.props
+            .iter()
+            .find(|prop| prop.name == name)
+            .map(|prop| prop.value)
+    }
+
+    pub fn prop_u32(&self, name: &str) -> Option<u32> {
+        read_u32(self.prop(name)?, 0)
+    }
+
+    /// A u32 or u64 cell, such as timebase-frequency
+    pub fn prop_usize(&self, name: &str) -> Option<usize> {
+        let value = self.prop(name)?;
+        read_cells(value, value.len() / 4)
+    }
+
+    pub fn prop_str(&self, name: &str) -> Option<&'a str> {
+        self.prop_str_list(name)?.next()
+    }
+
+    pub fn prop_str_list(&self, name: &str) -> Option<impl Iterator<Item = &'a str>> {
+        let value = self.prop(name)?;
+        Some(
+            value
+                .split(|&b| b == 0)
+                .filter(|s| !s.is_empty())
+                .filter_map(|s| str::from_utf8(s).ok()),
+        )
+    }
+
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.prop_str_list("compatible")
+            .map(|mut list| list.any(|c| c == compatible))
+            .unwrap_or(false)
+    }
+
+    /// `#address-cells` and `#size-cells` this node defines for its children
+    pub fn cells(&self) -> (usize, usize) {
+        let address_cells = self.prop_u32("#address-cells").unwrap_or(2) as usize;
+        let size_cells = self.prop_u32("#size-cells").unwrap_or(1) as usize;
+        (address_cells, size_cells)
+    }
+
+    /// Decode `reg` with the cell sizes of the parent node
+    pub fn reg(&self, cells: (usize, usize)) -> Vec<(usize, usize)> {
+        let (address_cells, size_cells) = cells;
+        let entry_size = (address_cells + size_cells) * 4;
+        let value = match self.prop("reg") {
+            Some(value) if entry_size > 0 => value,
+            _ => return Vec::new(),
+        };
+        value
+            .chunks_exact(entry_size)
+            .filter_map(|entry| {
+                let addr = read_cells(entry, address_cells)?;
+                let size = read_cells(&entry[address_cells * 4..], size_cells)?;
+                Some((addr, size))
+            })
+            .collect()
+    }
+}
+// region FdtNode end
+
+// region FdtProp begin
+struct FdtProp<'a> {
+    name: &'a str,
+    value: &'a [u8],
+}
+// region FdtProp end
+
+// region FdtParser begin
+struct FdtParser<'a> {
+    structs: &'a [u8],
+    strings: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> FdtParser<'a> {
+    fn peek_token(&self) -> Option<u32> {
+        read_u32(self.structs, self.offset)
+    }
+
+    fn next_token(&mut self) -> Option<u32> {
+        let token = self.peek_token()?;
+        self.offset += 4;
+        Some(token)
+    }
+
+    fn parse_node(&mut self) -> Option<FdtNode<'a>> {
+        if self.next_token()? != FDT_BEGIN_NODE {
+            return None;
+        }
+        let name = self.read_str()?;
+        let mut node = FdtNode {
+            name,
+            props: Vec::new(),
+            children: Vec::new(),
+        };
+
+        loop {
+            match self.peek_token()? {
+                FDT_BEGIN_NODE => node.children.push(self.parse_node()?),
+                FDT_PROP => {
+                    self.offset += 4;
+                    let len = self.next_token()? as usize;
+                    let name_offset = self.next_token()? as usize;
+                    let value = self.structs.get(self.offset..self.offset + len)?;
+                    self.offset = align_up(self.offset + len);
+                    let name = read_str(self.strings, name_offset)?;
+                    node.props.push(FdtProp { name, value });
+                }
+                FDT_NOP => self.offset += 4,
+                FDT_END_NODE => {
+                    self.offset += 4;
+                    return Some(node);
+                }
+                // FDT_END before the node is closed
+                _ => return None,
+            }
+        }
+    }
+
+    fn read_str(&mut self) -> Option<&'a str> {
+        let s = read_str(self.structs, self.offset)?;
+        self.offset = align_up(self.offset + s.len() + 1);
+        Some(s)
+    }
+}
+// region FdtParser end
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_cells(bytes: &[u8], cells: usize) -> Option<usize> {
+    (0..cells).try_fold(0usize, |value, i| {
+        let cell = read_u32(bytes, i * 4)? as usize;
+        Some((value << 32) | cell)
+    })
+}
+
+fn read_str(bytes: &[u8], offset: usize) -> Option<&str> {
+    let bytes = bytes.get(offset..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    str::from_utf8(&bytes[..len]).ok()
+}
+
+const fn align_up(offset: usize) -> usize {
+    (offset + 3) & !3
+}
@@ -0,0 +1,136 @@
+use crate::dtb::FdtNode;
+use alloc::{string::String, vec::Vec};
+
+// region MachineInfo begin
+/// Platform description collected from the device tree, addresses are physical
+pub struct MachineInfo {
+    memory: Vec<(usize, usize)>,
+    clock_freq: Option<usize>,
+    sstc: bool,
+    bootargs: Option<String>,
+    initrd: Option<(usize, usize)>,
+    virtio: Vec<(usize, usize)>,
+    uart: Vec<(usize, usize)>,
+    plic: Vec<(usize, usize)>,
+    rtc: Vec<(usize, usize)>,
+}
+
+impl MachineInfo {
+    pub fn from_fdt(root: &FdtNode) -> Self {
+        let mut info = Self {
+            memory: Vec::new(),
+            clock_freq: None,
+            sstc: false,
+            bootargs: None,
+            initrd: None,
+            virtio: Vec::new(),
+            uart: Vec::new(),
+            plic: Vec::new(),
+            rtc: Vec::new(),
+        };
+
+        if let Some(cpus) = root.child("cpus") {
+            info.clock_freq = cpus.prop_usize("timebase-frequency").or_else(|| {
+                cpus.children()
+                    .iter()
+                    .find_map(|cpu| cpu.prop_usize("timebase-frequency"))
+            });
+
+            // every hart must have it, QEMU lists it in both properties
+            let has_sstc = |cpu: &FdtNode| {
+                cpu.prop_str_list("riscv,isa-extensions")
+                    .is_some_and(|mut exts| exts.any(|ext| ext == "sstc"))
+                    || cpu
+                        .prop_str("riscv,isa")
+                        .is_some_and(|isa| isa.split('_').any(|ext| ext == "sstc"))
+            };
+            let harts: Vec<&FdtNode> = cpus
+                .children()
+                .iter()
+                .filter(|cpu| cpu.prop_str("device_type") == Some("cpu"))
+                .collect();
+            info.sstc = !harts.is_empty() && harts.iter().all(|cpu| has_sstc(cpu));
+        }
+        if let Some(chosen) = root.child("chosen") {
+            info.bootargs = chosen.prop_str("bootargs").map(String::from);
+            // set by QEMU `-initrd`, (start, end)
+            info.initrd = chosen
+                .prop_usize("linux,initrd-start")
+                .zip(chosen.prop_usize("linux,initrd-end"))
+                .filter(|&(start, end)| start < end);
+        }
+        info.scan(root, root.cells());
+
+        // sort by address, so that the device order is stable
+        for list in [
+            &mut info.memory,
+            &mut info.virtio,
+            &mut info.uart,
+            &mut info.plic,
+            &mut info.rtc,
+        ] {
+            list.sort_unstable();
+        }
+        info
+    }
+
+    fn scan(&mut self, node: &FdtNode, cells: (usize, usize)) {
+        for child in node.children() {
+            let list = match child {
+                _ if child.prop_str("device_type") == Some("memory") => &mut self.memory,
+                _ if child.is_compatible("virtio,mmio") => &mut self.virtio,
+                _ if child.is_compatible("ns16550a") => &mut self.uart,
+                _ if child.is_compatible("riscv,plic0")
+                    || child.is_compatible("sifive,plic-1.0.0") =>
+                {
+                    &mut self.plic
+                }
+                _ if child.is_compatible("google,goldfish-rtc") => &mut self.rtc,
+                // buses like /soc, assume an identity `ranges`
+                _ => {
+                    self.scan(child, child.cells());
+                    continue;
+                }
+            };
+            list.extend(child.reg(cells).into_iter().filter(|&(_, size)| size != 0));
+        }
+    }
+
+    pub fn memory(&self) -> &[(usize, usize)] {
+        &self.memory
+    }
+
+    pub fn clock_freq(&self) -> Option<usize> {
+        self.clock_freq
+    }
+
+    /// Whether the harts implement the Sstc extension, i.e. `stimecmp`
+    pub fn has_sstc(&self) -> bool {
+        self.sstc
+    }
+
+    pub fn bootargs(&self) -> Option<&str> {
+        self.bootargs.as_deref()
+    }
+
+    pub fn initrd(&self) -> Option<(usize, usize)> {
+        self.initrd
+    }
+
+    pub fn virtio(&self) -> &[(usize, usize)] {
+        &self.virtio
+    }
+
+    pub fn uart(&self) -> &[(usize, usize)] {
+        &self.uart
+    }
+
+    pub fn plic(&self) -> &[(usize, usize)] {
+        &self.plic
+    }
+
+    pub fn rtc(&self) -> &[(usize, usize)] {
+        &self.rtc
+    }
+}
+// region MachineInfo end
@@ -0,0 +1,33 @@
+pub use fdt::*;
+pub use machine::*;
+
+use crate::config::KERNEL_ADDR_OFFSET;
+use log::trace;
+use spin::Once;
+
+mod fdt;
+mod machine;
+
+/// Parse the device tree passed by SBI, must be called before the frame allocator
+/// is used, since the blob lives in RAM that will be handed out later
+pub fn init(dtb_pa: usize) {
+    // the boot page table maps the first GiB of RAM at the kernel offset
+    let fdt = unsafe { Fdt::from_ptr((dtb_pa + KERNEL_ADDR_OFFSET) as *const u8) }
+        .expect("Invalid device tree");
+    let machine_info = MachineInfo::from_fdt(fdt.root());
+
+    for &(addr, size) in machine_info.memory() {
+        trace!("DeviceTree: memory [{:#x}, {:#x})", addr, addr + size);
+    }
+    trace!("DeviceTree: timebase {:?}", machine_info.clock_freq());
+    trace!("DeviceTree: bootargs {:?}", machine_info.bootargs());
+    trace!("DeviceTree: initrd {:x?}", machine_info.initrd());
+
+    MACHINE_INFO.call_once(|| machine_info);
+}
+
+pub fn get_machine_info() -> &'static MachineInfo {
+    MACHINE_INFO.get().expect("Device tree is not parsed")
+}
+
+static MACHINE_INFO: Once<MachineInfo> = Once::new();
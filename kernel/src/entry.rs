@@ -43,9 +43,10 @@ unsafe extern "C" fn _start() -> ! {
     )
 }
 
-fn rust_main() {
+// a0 = hart id, a1 = device tree blob, both kept intact by _start
+extern "C" fn rust_main(hart_id: usize, dtb_pa: usize) {
     clear_bss();
-    crate::main();
+    crate::main(hart_id, dtb_pa);
 }
 
 fn clear_bss() {
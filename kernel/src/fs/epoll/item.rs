@@ -0,0 +1,99 @@
+use crate::{
+    fs::{EpollEvent, File, PollEvents, EPOLLET, EPOLLONESHOT},
+    sync::WaitQueue,
+};
+use alloc::{
+    sync::{Arc, Weak},
+    task::Wake,
+};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Waker},
+};
+
+// region EpollItem begin
+/// A watched file, its waker is registered on the file and marks the item
+/// triggered before waking the epoll waiters
+pub struct EpollItem {
+    // closing the file removes the item, so it is not kept open by epoll
+    file: Weak<dyn File + Send + Sync>,
+    event: EpollEvent,
+    // woken by the file since last reported, for edge-triggered items
+    triggered: AtomicBool,
+    // reported once with EPOLLONESHOT, until rearmed by EPOLL_CTL_MOD
+    disabled: AtomicBool,
+    ready_wait: Weak<WaitQueue>,
+}
+
+impl EpollItem {
+    pub fn new(
+        file: &Arc<dyn File + Send + Sync>,
+        event: EpollEvent,
+        ready_wait: &Arc<WaitQueue>,
+    ) -> Self {
+        Self {
+            file: Arc::downgrade(file),
+            event,
+            // a file ready when added is reported, edge-triggered or not
+            triggered: AtomicBool::new(true),
+            disabled: AtomicBool::new(false),
+            ready_wait: Arc::downgrade(ready_wait),
+        }
+    }
+
+    pub fn get_file(&self) -> Option<Arc<dyn File + Send + Sync>> {
+        self.file.upgrade()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.file.strong_count() == 0
+    }
+
+    /// The event to report, None if not ready. `consume` takes the edge of an
+    /// edge-triggered item and disables a one-shot one.
+    pub fn poll(self: &Arc<Self>, consume: bool) -> Option<EpollEvent> {
+        let file = self.file.upgrade()?;
+        if self.disabled.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+        let events = PollEvents::from_bits_truncate(self.event.get_events() as u16);
+        let ready =
+            file.poll_events(events, &mut cx) & (events | PollEvents::ERR | PollEvents::HUP);
+        if ready.is_empty() {
+            return None;
+        }
+
+        let flags = self.event.get_events();
+        if flags & EPOLLET != 0 {
+            let triggered = if consume {
+                self.triggered.swap(false, Ordering::AcqRel)
+            } else {
+                self.triggered.load(Ordering::Acquire)
+            };
+            if !triggered {
+                return None;
+            }
+        }
+        if consume && flags & EPOLLONESHOT != 0 {
+            self.disabled.store(true, Ordering::Release);
+        }
+        Some(EpollEvent::new(ready.bits() as u32, self.event.get_data()))
+    }
+}
+
+impl Wake for EpollItem {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.triggered.store(true, Ordering::Release);
+        if let Some(ready_wait) = self.ready_wait.upgrade() {
+            ready_wait.wake_all();
+        }
+    }
+}
+// region EpollItem end
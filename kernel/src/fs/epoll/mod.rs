@@ -0,0 +1,199 @@
+pub use item::*;
+
+use crate::{
+    fs::{File, PollEvents},
+    sync::{SpinLock, WaitQueue},
+    syscall::errno::{EEXIST, ELOOP, ENOENT},
+};
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::task::{Context, Poll};
+
+mod item;
+
+pub const EPOLLONESHOT: u32 = 1 << 30;
+pub const EPOLLET: u32 = 1 << 31;
+
+// region EpollEvent begin
+/// struct epoll_event, only packed on x86_64
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EpollEvent {
+    events: u32,
+    data: u64,
+}
+
+impl EpollEvent {
+    pub fn new(events: u32, data: u64) -> Self {
+        Self { events, data }
+    }
+
+    pub fn get_events(&self) -> u32 {
+        self.events
+    }
+
+    pub fn get_data(&self) -> u64 {
+        self.data
+    }
+}
+// region EpollEvent end
+
+// region EventPoll begin
+/// An epoll instance, the file behind the fd epoll_create1 returns
+pub struct EventPoll {
+    // by fd, like Linux keys them by fd and open file
+    items: SpinLock<BTreeMap<usize, Arc<EpollItem>>>,
+    // epoll_wait callers and pollers of the epoll fd itself
+    ready_wait: Arc<WaitQueue>,
+}
+
+impl EventPoll {
+    pub fn new() -> Self {
+        Self {
+            items: SpinLock::new("EventPoll", BTreeMap::new()),
+            ready_wait: Arc::new(WaitQueue::new()),
+        }
+    }
+
+    /// EPOLL_CTL_ADD, Err carries the errno
+    pub fn add(
+        &self,
+        fd: usize,
+        file: &Arc<dyn File + Send + Sync>,
+        event: EpollEvent,
+    ) -> Result<(), isize> {
+        // polling a loop of epolls would never end
+        if file
+            .as_event_poll()
+            .is_some_and(|target| self.is_watched_by(target))
+        {
+            return Err(ELOOP);
+        }
+
+        let mut items = self.items.lock();
+        if items.get(&fd).is_some_and(|item| !item.is_closed()) {
+            return Err(EEXIST);
+        }
+        items.insert(fd, Arc::new(EpollItem::new(file, event, &self.ready_wait)));
+        drop(items);
+        // waiters look at the new item
+        self.ready_wait.wake_all();
+        Ok(())
+    }
+
+    /// EPOLL_CTL_MOD, the item is replaced and so rearmed
+    pub fn modify(
+        &self,
+        fd: usize,
+        file: &Arc<dyn File + Send + Sync>,
+        event: EpollEvent,
+    ) -> Result<(), isize> {
+        let mut items = self.items.lock();
+        match items.get_mut(&fd) {
+            Some(item) if !item.is_closed() => {
+                *item = Arc::new(EpollItem::new(file, event, &self.ready_wait));
+            }
+            _ => return Err(ENOENT),
+        }
+        drop(items);
+        self.ready_wait.wake_all();
+        Ok(())
+    }
+
+    /// EPOLL_CTL_DEL
+    pub fn delete(&self, fd: usize) -> Result<(), isize> {
+        match self.items.lock().remove(&fd) {
+            Some(item) if !item.is_closed() => Ok(()),
+            _ => Err(ENOENT),
+        }
+    }
+
+    /// Fill `events` with what is ready, the waker is registered if nothing is
+    pub fn poll_wait(&self, events: &mut [EpollEvent], cx: &mut Context<'_>) -> Poll<usize> {
+        // before looking, so a file woken during the scan is not missed
+        self.ready_wait.register(cx.waker());
+
+        let mut count = 0;
+        for item in self.get_items() {
+            if count == events.len() {
+                break;
+            }
+            if let Some(event) = item.poll(true) {
+                events[count] = event;
+                count += 1;
+            }
+        }
+
+        if count > 0 {
+            Poll::Ready(count)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    // whether `other` is this instance or watches it, directly or not
+    fn is_watched_by(&self, other: &EventPoll) -> bool {
+        core::ptr::eq(self, other)
+            || other.get_items().iter().any(|item| {
+                item.get_file().is_some_and(|file| {
+                    file.as_event_poll()
+                        .is_some_and(|ep| self.is_watched_by(ep))
+                })
+            })
+    }
+
+    // taken out of the lock, polling a file may take its sleeping lock
+    fn get_items(&self) -> Vec<Arc<EpollItem>> {
+        let mut items = self.items.lock();
+        // a closed file leaves the interest list
+        items.retain(|_, item| !item.is_closed());
+        items.values().cloned().collect()
+    }
+}
+
+impl File for EventPoll {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> usize {
+        0
+    }
+
+    fn write(&self, _buf: &[u8]) -> usize {
+        0
+    }
+
+    /// Readable while an item is ready, for an epoll fd watched by poll or epoll
+    fn poll_events(&self, events: PollEvents, cx: &mut Context<'_>) -> PollEvents {
+        if events.contains(PollEvents::IN) {
+            self.ready_wait.register(cx.waker());
+        }
+        let ready = self
+            .get_items()
+            .iter()
+            .any(|item| item.poll(false).is_some());
+        if ready {
+            PollEvents::IN
+        } else {
+            PollEvents::empty()
+        }
+    }
+
+    fn as_event_poll(&self) -> Option<&EventPoll> {
+        Some(self)
+    }
+
+    fn path(&self) -> String {
+        "anon_inode:[eventpoll]".to_string()
+    }
+}
+// region EventPoll end
@@ -0,0 +1,148 @@
+use crate::{
+    fs::{File, PollEvents},
+    sync::{SpinNoIrqLock, WaitQueue},
+    syscall::errno::{EAGAIN, EINVAL},
+};
+use alloc::string::{String, ToString};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+pub const EFD_SEMAPHORE: usize = 1;
+
+// the counter never reaches u64::MAX, a write that would blocks
+const MAX_COUNTER: u64 = u64::MAX - 1;
+
+// region EventFd begin
+/// A 64-bit counter, read takes it (or one with EFD_SEMAPHORE) and write adds
+pub struct EventFd {
+    counter: SpinNoIrqLock<u64>,
+    semaphore: bool,
+    nonblock: AtomicBool,
+    read_wait: WaitQueue,
+    write_wait: WaitQueue,
+}
+
+impl EventFd {
+    pub fn new(value: u64, semaphore: bool, nonblock: bool) -> Self {
+        Self {
+            counter: SpinNoIrqLock::new("EventFd", value),
+            semaphore,
+            nonblock: AtomicBool::new(nonblock),
+            read_wait: WaitQueue::new(),
+            write_wait: WaitQueue::new(),
+        }
+    }
+
+    fn try_read(&self, buf: &mut [u8], waker: Option<&Waker>) -> Poll<Result<usize, isize>> {
+        if buf.len() < size_of::<u64>() {
+            return Poll::Ready(Err(EINVAL));
+        }
+
+        let mut counter = self.counter.lock();
+        if *counter == 0 {
+            if self.is_nonblock() {
+                return Poll::Ready(Err(EAGAIN));
+            }
+            if let Some(waker) = waker {
+                self.read_wait.register(waker);
+            }
+            return Poll::Pending;
+        }
+
+        let value = if self.semaphore { 1 } else { *counter };
+        *counter -= value;
+        drop(counter);
+        buf[..size_of::<u64>()].copy_from_slice(&value.to_ne_bytes());
+        self.write_wait.wake_all();
+        Poll::Ready(Ok(size_of::<u64>()))
+    }
+
+    fn try_write(&self, buf: &[u8], waker: Option<&Waker>) -> Poll<Result<usize, isize>> {
+        let Some(bytes) = buf.get(..size_of::<u64>()) else {
+            return Poll::Ready(Err(EINVAL));
+        };
+        let value = u64::from_ne_bytes(bytes.try_into().unwrap());
+        if value > MAX_COUNTER {
+            return Poll::Ready(Err(EINVAL));
+        }
+
+        let mut counter = self.counter.lock();
+        if value > MAX_COUNTER - *counter {
+            if self.is_nonblock() {
+                return Poll::Ready(Err(EAGAIN));
+            }
+            if let Some(waker) = waker {
+                self.write_wait.register(waker);
+            }
+            return Poll::Pending;
+        }
+
+        *counter += value;
+        drop(counter);
+        self.read_wait.wake_all();
+        Poll::Ready(Ok(size_of::<u64>()))
+    }
+}
+
+impl File for EventFd {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn read(&self, buf: &mut [u8]) -> usize {
+        match self.try_read(buf, None) {
+            Poll::Ready(Ok(len)) => len,
+            _ => 0,
+        }
+    }
+
+    fn write(&self, buf: &[u8]) -> usize {
+        match self.try_write(buf, None) {
+            Poll::Ready(Ok(len)) => len,
+            _ => 0,
+        }
+    }
+
+    fn poll_read(&self, buf: &mut [u8], cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        self.try_read(buf, Some(cx.waker()))
+    }
+
+    fn poll_write(&self, buf: &[u8], cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        self.try_write(buf, Some(cx.waker()))
+    }
+
+    fn poll_events(&self, events: PollEvents, cx: &mut Context<'_>) -> PollEvents {
+        let counter = self.counter.lock();
+        let mut ready = PollEvents::empty();
+        ready.set(PollEvents::IN, *counter > 0);
+        ready.set(PollEvents::OUT, *counter < MAX_COUNTER);
+
+        // registered under the lock, so a change in between is not missed
+        if events.contains(PollEvents::IN) {
+            self.read_wait.register(cx.waker());
+        }
+        if events.contains(PollEvents::OUT) {
+            self.write_wait.register(cx.waker());
+        }
+        ready
+    }
+
+    fn is_nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
+
+    fn path(&self) -> String {
+        "anon_inode:[eventfd]".to_string()
+    }
+}
+// region EventFd end
@@ -1,18 +1,18 @@
 /* Refer to https://github.com/neuq-rcore/rCore */
 
-use crate::fs::BlockDevice;
-use alloc::boxed::Box;
+use crate::{fs::BlockDevice, sync::SpinLock};
+use alloc::{boxed::Box, sync::Arc};
 
 // region FatDeviceDriver begin
 pub struct FatDeviceDriver {
-    device: Box<dyn BlockDevice>,
+    device: Arc<SpinLock<Box<dyn BlockDevice>>>,
 }
 
 unsafe impl Send for FatDeviceDriver {}
 unsafe impl Sync for FatDeviceDriver {}
 
 impl FatDeviceDriver {
-    pub const fn new(device: Box<dyn BlockDevice>) -> Self {
+    pub const fn new(device: Arc<SpinLock<Box<dyn BlockDevice>>>) -> Self {
         Self { device }
     }
 }
@@ -27,7 +27,7 @@ impl FatDeviceDriver {
             len
         );
 
-        let device = &mut self.device;
+        let mut device = self.device.lock();
         let device_offset = device.get_position() % 512;
 
         // Virtio_driver can only read 512 bytes at a time
@@ -84,7 +84,7 @@ impl fatfs::Read for FatDeviceDriver {
 
 impl fatfs::Write for FatDeviceDriver {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        let device = &mut self.device;
+        let mut device = self.device.lock();
         let device_offset = device.get_position() % 512;
 
         let size_written = if device_offset != 0 || buf.len() < 512 {
@@ -107,13 +107,14 @@ impl fatfs::Write for FatDeviceDriver {
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
+        self.device.lock().flush();
         Ok(())
     }
 }
 
 impl fatfs::Seek for FatDeviceDriver {
     fn seek(&mut self, pos: fatfs::SeekFrom) -> Result<u64, Self::Error> {
-        let device = &mut self.device;
+        let mut device = self.device.lock();
         match pos {
             fatfs::SeekFrom::Start(i) => {
                 device.set_position(i as usize);
@@ -1,16 +1,15 @@
 use crate::{
     fs::{File, LinuxDirent64},
-    sync::UPSafeCell,
+    sync::{SpinLock, SpinLockGuard},
 };
 use alloc::{string::String, vec::Vec};
-use core::cell::Ref;
 
 // region FatDir begin
 pub struct FatDir {
     readable: bool,
     writable: bool,
     path: String,
-    inner: UPSafeCell<FatDirInner<'static>>,
+    inner: SpinLock<FatDirInner<'static>>,
 }
 
 impl FatDir {
@@ -19,12 +18,12 @@ impl FatDir {
             readable,
             writable,
             path,
-            inner: unsafe { UPSafeCell::new(inner) },
+            inner: SpinLock::new("FatDir", inner),
         }
     }
 
-    fn inner(&self) -> Ref<FatDirInner<'static>> {
-        self.inner.shared_access()
+    fn inner(&self) -> SpinLockGuard<FatDirInner<'static>> {
+        self.inner.lock()
     }
 }
 
@@ -70,6 +69,6 @@ impl File for FatDir {
 type FatDirInner<'a> = fatfs::Dir<
     'a,
     crate::fs::fat::FatDeviceDriver,
-    fatfs::NullTimeProvider,
+    crate::fs::fat::RtcTimeProvider,
     fatfs::LossyOemCpConverter,
 >;
@@ -1,28 +1,54 @@
-use crate::{fs::File, sync::UPSafeCell};
-use alloc::string::String;
-use core::cell::RefMut;
-use fatfs::{Read, Write};
+use crate::{
+    fs::File,
+    sync::{SpinLock, SpinLockGuard},
+};
+use alloc::{
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use fatfs::{Read, Seek, SeekFrom, Write};
+
+// the files flush_open_files writes back
+static OPEN_FILES: SpinLock<Vec<Weak<FatFile>>> = SpinLock::new("FatOpenFiles", Vec::new());
+
+/// Write the directory entries of every open file back, for sync
+pub fn flush_open_files() {
+    // a flush takes the file lock, so not under ours
+    let files: Vec<Arc<FatFile>> = OPEN_FILES.lock().iter().filter_map(Weak::upgrade).collect();
+    files.iter().for_each(|file| file.sync());
+}
 
 // region FatFile begin
 pub struct FatFile {
     readable: bool,
     writable: bool,
     path: String,
-    inner: UPSafeCell<FatFileInner<'static>>,
+    inner: SpinLock<FatFileInner<'static>>,
 }
 
 impl FatFile {
-    pub fn new(path: String, inner: FatFileInner<'static>, readable: bool, writable: bool) -> Self {
-        Self {
+    /// The file is known to flush_open_files while it is open
+    pub fn open(
+        path: String,
+        inner: FatFileInner<'static>,
+        readable: bool,
+        writable: bool,
+    ) -> Arc<Self> {
+        let file = Arc::new(Self {
             readable,
             writable,
             path,
-            inner: unsafe { UPSafeCell::new(inner) },
-        }
+            inner: SpinLock::new("FatFile", inner),
+        });
+        let mut files = OPEN_FILES.lock();
+        files.retain(|file| file.strong_count() > 0);
+        files.push(Arc::downgrade(&file));
+        file
     }
 
-    fn inner_mut(&self) -> RefMut<FatFileInner<'static>> {
-        self.inner.exclusive_access()
+    fn inner(&self) -> SpinLockGuard<FatFileInner<'static>> {
+        self.inner.lock()
     }
 }
 
@@ -40,14 +66,14 @@ impl File for FatFile {
 
     fn read(&self, buf: &mut [u8]) -> usize {
         assert!(self.readable);
-        let mut inner = self.inner_mut();
+        let mut inner = self.inner();
         inner.read_exact(buf).ok();
         buf.len()
     }
 
     fn write(&self, buf: &[u8]) -> usize {
         assert!(self.writable);
-        let mut inner = self.inner_mut();
+        let mut inner = self.inner();
         inner.write_all(buf).ok();
         inner.flush().ok();
         buf.len()
@@ -56,12 +82,77 @@ impl File for FatFile {
     fn path(&self) -> String {
         self.path.clone()
     }
+
+    fn truncate(&self, len: usize) -> bool {
+        if !self.writable {
+            return false;
+        }
+        let mut inner = self.inner();
+        let pos = match inner.seek(SeekFrom::Current(0)) {
+            Ok(pos) => pos,
+            Err(_) => return false,
+        };
+        let size = match inner.seek(SeekFrom::End(0)) {
+            Ok(size) => size as usize,
+            Err(_) => return false,
+        };
+
+        if len < size {
+            // shrink
+            if inner.seek(SeekFrom::Start(len as u64)).is_err() || inner.truncate().is_err() {
+                return false;
+            }
+        } else {
+            // grow, fill the hole with zeros
+            let zeros = [0u8; 512];
+            let mut remain = len - size;
+            while remain > 0 {
+                let amount = remain.min(zeros.len());
+                if inner.write_all(&zeros[..amount]).is_err() {
+                    return false;
+                }
+                remain -= amount;
+            }
+        }
+
+        // keep the file offset unchanged
+        inner.seek(SeekFrom::Start(pos)).ok();
+        inner.flush().is_ok()
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        assert!(self.readable);
+        let mut inner = self.inner();
+        let pos = match inner.seek(SeekFrom::Current(0)) {
+            Ok(pos) => pos,
+            Err(_) => return 0,
+        };
+
+        // short of the end of file, a read may still return less than asked
+        let mut len = 0;
+        if inner.seek(SeekFrom::Start(offset as u64)).is_ok() {
+            while len < buf.len() {
+                match inner.read(&mut buf[len..]) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => len += read,
+                }
+            }
+        }
+
+        // keep the file offset unchanged
+        inner.seek(SeekFrom::Start(pos)).ok();
+        len
+    }
+
+    fn sync(&self) {
+        self.inner().flush().ok();
+    }
 }
 // region FatFile end
 
 type FatFileInner<'a> = fatfs::File<
     'a,
     crate::fs::fat::FatDeviceDriver,
-    fatfs::NullTimeProvider,
+    crate::fs::fat::RtcTimeProvider,
     fatfs::LossyOemCpConverter,
 >;
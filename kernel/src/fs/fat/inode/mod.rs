@@ -1,11 +1,17 @@
 pub use dir::*;
 pub use file::*;
 
+use super::{calculate_date_time, calculate_sec};
 use crate::{
-    config::ROOT_DIR,
-    fs::{Inode, InodeType},
+    fs::{File, Inode, InodeType, LinuxDirent64},
+    syscall::errno::EIO,
 };
-use alloc::string::{String, ToString};
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use fatfs::Write;
 
 mod dir;
 mod file;
@@ -33,16 +39,53 @@ impl FatInode {
         }
     }
 
-    pub fn from_root(inner: FatInodeInnerRoot<'static>) -> Self {
+    pub fn from_root(path: String, inner: FatInodeInnerRoot<'static>) -> Self {
         Self {
             readable: true,
             writable: false,
-            path: ROOT_DIR.to_string(),
+            path,
             inner: FatInodeType::Root(inner),
         }
     }
 }
 
+impl FatInode {
+    fn fat_file(&self) -> Arc<FatFile> {
+        match &self.inner {
+            FatInodeType::Root(_) => panic!("Root is not a file"),
+            FatInodeType::Normal(ref inner) => {
+                assert!(inner.is_file());
+                FatFile::open(
+                    self.path.clone(),
+                    inner.to_file(),
+                    self.readable,
+                    self.writable,
+                )
+            }
+        }
+    }
+
+    fn fat_dir(&self) -> FatDir {
+        match &self.inner {
+            FatInodeType::Root(ref inner) => FatDir::new(
+                self.path.clone(),
+                (*inner).clone(),
+                self.readable,
+                self.writable,
+            ),
+            FatInodeType::Normal(ref inner) => {
+                assert!(inner.is_dir());
+                FatDir::new(
+                    self.path.clone(),
+                    inner.to_dir(),
+                    self.readable,
+                    self.writable,
+                )
+            }
+        }
+    }
+}
+
 unsafe impl Sync for FatInode {}
 unsafe impl Send for FatInode {}
 
@@ -72,39 +115,16 @@ impl Inode for FatInode {
         }
     }
 
-    fn to_file(&self) -> FatFile {
-        match &self.inner {
-            FatInodeType::Root(_) => panic!("Root is not a file"),
-            FatInodeType::Normal(ref inner) => {
-                assert!(inner.is_file());
-                FatFile::new(
-                    self.path.clone(),
-                    inner.to_file(),
-                    self.readable,
-                    self.writable,
-                )
-            }
-        }
+    fn to_file(&self) -> Arc<dyn File + Send + Sync> {
+        self.fat_file()
     }
 
-    fn to_dir(&self) -> FatDir {
-        match &self.inner {
-            FatInodeType::Root(ref inner) => FatDir::new(
-                self.path.clone(),
-                (*inner).clone(),
-                self.readable,
-                self.writable,
-            ),
-            FatInodeType::Normal(ref inner) => {
-                assert!(inner.is_dir());
-                FatDir::new(
-                    self.path.clone(),
-                    inner.to_dir(),
-                    self.readable,
-                    self.writable,
-                )
-            }
-        }
+    fn to_dir(&self) -> Arc<dyn File + Send + Sync> {
+        Arc::new(self.fat_dir())
+    }
+
+    fn get_entries(&self) -> Vec<LinuxDirent64> {
+        self.fat_dir().get_entries()
     }
 
     fn atime(&self) -> (usize, usize) {
@@ -153,20 +173,38 @@ impl Inode for FatInode {
             }
         }
     }
+
+    fn set_times(&self, atime: Option<usize>, mtime: Option<usize>) -> Result<(), isize> {
+        match self.inner {
+            // the root has no directory entry to keep them in
+            FatInodeType::Root(_) => Ok(()),
+            FatInodeType::Normal(ref inner) => {
+                // rust-fatfs only exposes timestamp setters on files, so a
+                // directory keeps its times like it does on vfat
+                if !inner.is_file() {
+                    return Ok(());
+                }
+                let mut file = inner.to_file();
+                if let Some(secs) = atime {
+                    file.set_accessed(calculate_date_time(secs).date);
+                }
+                if let Some(secs) = mtime {
+                    file.set_modified(calculate_date_time(secs));
+                }
+                file.flush().map_err(|_| EIO)
+            }
+        }
+    }
 }
 // region FatInode end
 
-type FatInodeInnerNormal<'a> = fatfs::DirEntry<
-    'a,
-    super::FatDeviceDriver,
-    fatfs::NullTimeProvider,
-    fatfs::LossyOemCpConverter,
->;
+type FatInodeInnerNormal<'a> =
+    fatfs::DirEntry<'a, super::FatDeviceDriver, super::RtcTimeProvider, fatfs::LossyOemCpConverter>;
 
 type FatInodeInnerRoot<'a> = fatfs::Dir<
     'a,
     crate::fs::fat::FatDeviceDriver,
-    fatfs::NullTimeProvider,
+    crate::fs::fat::RtcTimeProvider,
     fatfs::LossyOemCpConverter,
 >;
 
@@ -174,38 +212,3 @@ pub enum FatInodeType<'a> {
     Root(FatInodeInnerRoot<'a>),
     Normal(FatInodeInnerNormal<'a>),
 }
-
-fn calculate_sec(
-    year: usize,
-    month: usize,
-    day: usize,
-    hour: usize,
-    min: usize,
-    sec: usize,
-) -> usize {
-    const UNIX_YEAR: usize = 1970;
-    let mut days = 0;
-    for i in UNIX_YEAR..year {
-        days += 365;
-        if i % 4 == 0 {
-            days += 1;
-        }
-    }
-    for i in 1..month {
-        days += match i {
-            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
-            4 | 6 | 9 | 11 => 30,
-            2 => {
-                if year % 4 == 0 {
-                    29
-                } else {
-                    28
-                }
-            }
-            _ => 0,
-        }
-    }
-    days += day;
-
-    days * 24 * 60 * 60 + hour * 60 * 60 + min * 60 + sec
-}
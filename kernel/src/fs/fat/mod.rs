@@ -1,27 +1,33 @@
 pub use driver::*;
 pub use inode::*;
+pub use time::*;
 pub use virtio::*;
 
 use crate::{
-    board::VIRT_IO,
-    config::ROOT_DIR,
+    config::{DIR_SEPARATOR, ROOT_DIR},
     drivers::VirtIOHal,
-    fs::{FileSystem, OpenFlags, PathUtil},
+    fs::{self, BlockDevice, FileSystem, Inode, OpenFlags, PathUtil},
+    sync::SpinLock,
+    syscall::errno::{EBUSY, EEXIST, EINVAL, EIO, EISDIR, ENOENT, ENOTDIR, ENOTEMPTY},
 };
-use alloc::{boxed::Box, string::ToString};
-use core::ptr::NonNull;
-use fatfs::{FsOptions, LossyOemCpConverter, NullTimeProvider};
-use virtio_drivers::{
-    device::blk::VirtIOBlk,
-    transport::mmio::{MmioTransport, VirtIOHeader},
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
 };
+use fatfs::{FsOptions, LossyOemCpConverter};
+use virtio_drivers::{device::blk::VirtIOBlk, transport::mmio::MmioTransport};
 
 mod driver;
 mod inode;
+mod time;
 mod virtio;
 
 // region FatFileSystem begin
 pub struct FatFileSystem {
+    mount_point: String,
+    device: Arc<SpinLock<Box<dyn BlockDevice>>>,
     inner: FatFileSystemInner,
 }
 
@@ -29,7 +35,7 @@ unsafe impl Send for FatFileSystem {}
 unsafe impl Sync for FatFileSystem {}
 
 impl FileSystem for FatFileSystem {
-    fn open(&'static self, path: &str, flags: OpenFlags) -> Option<FatInode> {
+    fn open(&'static self, path: &str, flags: OpenFlags) -> Option<Arc<dyn Inode>> {
         let path = PathUtil::from_str(path);
         let parent = path.parent();
         let name = path.name();
@@ -37,9 +43,11 @@ impl FileSystem for FatFileSystem {
 
         // root
         if path == ROOT_DIR {
-            let inode = FatInode::from_root(self.inner.root_dir());
-            return Some(inode);
+            let path = self.mount_point.clone();
+            let inode = FatInode::from_root(path, self.inner.root_dir());
+            return Some(Arc::new(inode));
         }
+        let path = fs::to_absolute(&self.mount_point, &path);
 
         // open parent directory
         let dir = self.inner.root_dir();
@@ -61,7 +69,7 @@ impl FileSystem for FatFileSystem {
             let file = file.unwrap();
             let (readable, writable) = flags.read_write();
             let inode = FatInode::new_normal(path, file, readable, writable);
-            Some(inode)
+            Some(Arc::new(inode))
         } else {
             // file not found
             if flags.create() {
@@ -77,7 +85,7 @@ impl FileSystem for FatFileSystem {
                     .unwrap();
                 let (readable, writable) = flags.read_write();
                 let inode = FatInode::new_normal(path, file, readable, writable);
-                return Some(inode);
+                return Some(Arc::new(inode));
             }
             None
         }
@@ -88,6 +96,11 @@ impl FileSystem for FatFileSystem {
         self.inner.root_dir().create_dir(&path).is_ok()
     }
 
+    fn create_fifo(&'static self, _path: &str) -> Result<(), ()> {
+        // FAT has no special files, like vfat on Linux
+        Err(())
+    }
+
     fn delete(&self, path: &str) -> Result<(), ()> {
         let path = PathUtil::from_str(path);
         let parent = path.parent();
@@ -106,32 +119,137 @@ impl FileSystem for FatFileSystem {
         };
 
         // find the file in the directory
-        let entry = dir
-            .iter()
-            .find(|entry| entry.as_ref().unwrap().file_name() == name);
-        if entry.is_none() {
-            return Err(());
+        match find_entry(&dir, &name) {
+            Ok(Some(_)) => dir.remove(&name).map_err(|_| ()),
+            _ => Err(()),
+        }
+    }
+
+    fn rename(
+        &'static self,
+        old_path: &str,
+        new_path: &str,
+        no_replace: bool,
+    ) -> Result<(), isize> {
+        let old_path = PathUtil::from_str(old_path);
+        let new_path = PathUtil::from_str(new_path);
+        let old_name = old_path.name();
+        let new_name = new_path.name();
+
+        // root could not be moved
+        if old_name.is_empty() || new_name.is_empty() {
+            return Err(EBUSY);
+        }
+        // rename to itself
+        if old_path.to_string() == new_path.to_string() {
+            return Ok(());
+        }
+        // a directory could not be moved into itself
+        let old_prefix = old_path.to_string() + DIR_SEPARATOR;
+        if new_path.to_string().starts_with(&old_prefix) {
+            return Err(EINVAL);
+        }
+
+        // open parent directories
+        let old_dir = self.open_parent(&old_path.parent()).ok_or(ENOENT)?;
+        let new_dir = self.open_parent(&new_path.parent()).ok_or(ENOENT)?;
+
+        // find the source in the directory
+        let node = find_entry(&old_dir, &old_name)?.ok_or(ENOENT)?;
+
+        // rust-fatfs refuses to overwrite, so the target is moved aside and only
+        // removed once the source has taken its name
+        let mut aside = None;
+        if let Some(target) = find_entry(&new_dir, &new_name)? {
+            if no_replace {
+                return Err(EEXIST);
+            }
+            match (node.is_dir(), target.is_dir()) {
+                (true, false) => return Err(ENOTDIR),
+                (false, true) => return Err(EISDIR),
+                (true, true) if !is_empty_dir(&target.to_dir())? => return Err(ENOTEMPTY),
+                _ => {}
+            }
+            let mut name = format!(".{}.old", new_name);
+            while find_entry(&new_dir, &name)?.is_some() {
+                name.push('~');
+            }
+            new_dir
+                .rename(&new_name, &new_dir, &name)
+                .map_err(|_| EIO)?;
+            aside = Some(name);
+        }
+
+        if old_dir.rename(&old_name, &new_dir, &new_name).is_err() {
+            // put the target back, nothing is lost
+            if let Some(name) = aside {
+                let _ = new_dir.rename(&name, &new_dir, &new_name);
+            }
+            return Err(EIO);
+        }
+        match aside {
+            Some(name) => new_dir.remove(&name).map_err(|_| EIO),
+            None => Ok(()),
         }
-        dir.remove(&name).ok().unwrap();
-        Ok(())
+    }
+
+    fn sync(&'static self) {
+        // sizes and timestamps of open files sit in their directory entries,
+        // which rust-fatfs writes back on flush
+        flush_open_files();
+        self.device.lock().flush();
     }
 }
 
 impl FatFileSystem {
-    pub fn new(device_id: usize) -> Self {
-        let addr = VIRT_IO + device_id * 0x1000;
-        let header = NonNull::new(addr as *mut VirtIOHeader).unwrap();
-        let transport =
-            unsafe { MmioTransport::new(header).expect("Failed to create mmio transport") };
+    pub fn new(mount_point: &str, transport: MmioTransport) -> Self {
         let blk = VirtIOBlk::<VirtIOHal, MmioTransport>::new(transport)
             .expect("Failed to create VirtIOBlk");
-        let device = Box::new(VirtIODisk::new(blk));
-        let io = FatDeviceDriver::new(device);
-        let inner = fatfs::FileSystem::new(io, FsOptions::new()).unwrap();
+        let device: Box<dyn BlockDevice> = Box::new(VirtIODisk::new(blk));
+        let device = Arc::new(SpinLock::new("BlockDevice", device));
+        let io = FatDeviceDriver::new(device.clone());
+        let inner =
+            fatfs::FileSystem::new(io, FsOptions::new().time_provider(RtcTimeProvider)).unwrap();
 
-        Self { inner }
+        Self {
+            mount_point: mount_point.to_string(),
+            device,
+            inner,
+        }
+    }
+
+    fn open_parent(&'static self, parent: &str) -> Option<FatDirInner<'static>> {
+        let dir = self.inner.root_dir();
+        if parent == ROOT_DIR {
+            Some(dir)
+        } else {
+            dir.open_dir(parent).ok()
+        }
     }
 }
 // region FatFileSystem end
 
-type FatFileSystemInner = fatfs::FileSystem<FatDeviceDriver, NullTimeProvider, LossyOemCpConverter>;
+// the entry named `name` in `dir`, Err(EIO) if the directory could not be read
+fn find_entry<'a>(dir: &FatDirInner<'a>, name: &str) -> Result<Option<FatDirEntry<'a>>, isize> {
+    for entry in dir.iter() {
+        let entry = entry.map_err(|_| EIO)?;
+        if entry.file_name() == name {
+            return Ok(Some(entry));
+        }
+    }
+    Ok(None)
+}
+
+fn is_empty_dir(dir: &FatDirInner) -> Result<bool, isize> {
+    for entry in dir.iter() {
+        let name = entry.map_err(|_| EIO)?.file_name();
+        if name != "." && name != ".." {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+type FatFileSystemInner = fatfs::FileSystem<FatDeviceDriver, RtcTimeProvider, LossyOemCpConverter>;
+type FatDirInner<'a> = fatfs::Dir<'a, FatDeviceDriver, RtcTimeProvider, LossyOemCpConverter>;
+type FatDirEntry<'a> = fatfs::DirEntry<'a, FatDeviceDriver, RtcTimeProvider, LossyOemCpConverter>;
@@ -0,0 +1,104 @@
+use crate::timer;
+use fatfs::{Date, DateTime, Time, TimeProvider};
+
+// region RtcTimeProvider begin
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RtcTimeProvider;
+
+impl TimeProvider for RtcTimeProvider {
+    fn get_current_date(&self) -> Date {
+        self.get_current_date_time().date
+    }
+
+    fn get_current_date_time(&self) -> DateTime {
+        calculate_date_time(timer::get_wall_time().sec())
+    }
+}
+// region RtcTimeProvider end
+
+fn days_in_month(year: usize, month: usize) -> usize {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 => {
+            if is_leap_year(year) {
+                29
+            } else {
+                28
+            }
+        }
+        _ => 0,
+    }
+}
+
+fn is_leap_year(year: usize) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_year(year: usize) -> usize {
+    if is_leap_year(year) {
+        366
+    } else {
+        365
+    }
+}
+
+pub(super) fn calculate_sec(
+    year: usize,
+    month: usize,
+    day: usize,
+    hour: usize,
+    min: usize,
+    sec: usize,
+) -> usize {
+    let mut days = 0;
+    for i in UNIX_YEAR..year {
+        days += days_in_year(i);
+    }
+    for i in 1..month {
+        days += days_in_month(year, i);
+    }
+    // day of month starts from 1
+    days += day.saturating_sub(1);
+
+    days * SECS_PER_DAY + hour * 60 * 60 + min * 60 + sec
+}
+
+pub(super) fn calculate_date_time(secs: usize) -> DateTime {
+    const FAT_MIN_YEAR: usize = 1980;
+    const FAT_MAX_YEAR: usize = 2107;
+
+    let mut days = secs / SECS_PER_DAY;
+    let secs_of_day = secs % SECS_PER_DAY;
+
+    let mut year = UNIX_YEAR;
+    while days >= days_in_year(year) {
+        days -= days_in_year(year);
+        year += 1;
+    }
+    let mut month = 1;
+    while days >= days_in_month(year, month) {
+        days -= days_in_month(year, month);
+        month += 1;
+    }
+
+    // FAT could only store [1980, 2107]
+    if year < FAT_MIN_YEAR {
+        return DateTime::new(Date::new(1980, 1, 1), Time::new(0, 0, 0, 0));
+    }
+    if year > FAT_MAX_YEAR {
+        return DateTime::new(Date::new(2107, 12, 31), Time::new(23, 59, 59, 0));
+    }
+
+    let date = Date::new(year as u16, month as u16, days as u16 + 1);
+    let time = Time::new(
+        (secs_of_day / 3600) as u16,
+        (secs_of_day % 3600 / 60) as u16,
+        (secs_of_day % 60) as u16,
+        0,
+    );
+    DateTime::new(date, time)
+}
+
+const UNIX_YEAR: usize = 1970;
+const SECS_PER_DAY: usize = 24 * 60 * 60;
@@ -20,6 +20,8 @@ impl VirtIODisk {
     }
 }
 
+unsafe impl Send for VirtIODisk {}
+
 impl BlockDevice for VirtIODisk {
     fn read_blocks(&mut self, buf: &mut [u8]) {
         self.inner
@@ -45,6 +47,12 @@ impl BlockDevice for VirtIODisk {
     fn move_cursor(&mut self, amount: usize) {
         self.set_position(self.get_position() + amount)
     }
+
+    fn flush(&mut self) {
+        self.inner
+            .flush()
+            .expect("Error occurred when flushing VirtIOBlk");
+    }
 }
 // region VirtIODisk end
 
@@ -0,0 +1,114 @@
+use core::str;
+
+const MAGIC: &[u8] = b"070701";
+const HEADER_LEN: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+// region CpioReader begin
+/// Iterate over a cpio archive in the "new ASCII" (newc) format
+pub struct CpioReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> CpioReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_entry(&mut self) -> Result<CpioEntry<'a>, ()> {
+        let header = self
+            .data
+            .get(self.offset..self.offset + HEADER_LEN)
+            .ok_or(())?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(());
+        }
+        // 13 fields of 8 hex digits follow the magic
+        let field = |index: usize| -> Result<usize, ()> {
+            let start = MAGIC.len() + index * 8;
+            let digits = str::from_utf8(&header[start..start + 8]).map_err(|_| ())?;
+            usize::from_str_radix(digits, 16).map_err(|_| ())
+        };
+        let mode = field(1)? as u32;
+        let mtime = field(5)?;
+        let file_size = field(6)?;
+        let name_size = field(11)?;
+
+        // the name is NUL terminated, and padded with the header to 4 bytes
+        let name_start = self.offset + HEADER_LEN;
+        let name = self
+            .data
+            .get(name_start..name_start + name_size)
+            .ok_or(())?;
+        let name = str::from_utf8(name.strip_suffix(&[0]).ok_or(())?).map_err(|_| ())?;
+        let data_start = align4(name_start + name_size);
+        let data = self
+            .data
+            .get(data_start..data_start + file_size)
+            .ok_or(())?;
+
+        self.offset = align4(data_start + file_size);
+        Ok(CpioEntry {
+            name,
+            mode,
+            mtime,
+            data,
+        })
+    }
+}
+
+impl<'a> Iterator for CpioReader<'a> {
+    type Item = Result<CpioEntry<'a>, ()>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.data.len() {
+            return None;
+        }
+        match self.read_entry() {
+            Ok(entry) if entry.name == TRAILER => {
+                self.offset = self.data.len();
+                None
+            }
+            Ok(entry) => Some(Ok(entry)),
+            Err(()) => {
+                // stop at the first broken header
+                self.offset = self.data.len();
+                Some(Err(()))
+            }
+        }
+    }
+}
+// region CpioReader end
+
+// region CpioEntry begin
+pub struct CpioEntry<'a> {
+    pub name: &'a str,
+    pub mode: u32,
+    pub mtime: usize,
+    pub data: &'a [u8],
+}
+
+impl CpioEntry<'_> {
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+}
+// region CpioEntry end
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
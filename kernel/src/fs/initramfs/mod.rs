@@ -0,0 +1,83 @@
+use crate::{
+    board,
+    fs::tmpfs::TmpFileSystem,
+    mm::{self, PhysAddr},
+};
+use cpio::CpioReader;
+use log::{info, warn};
+
+mod cpio;
+
+#[cfg(feature = "initramfs")]
+const EMBEDDED_INITRAMFS: &[u8] = include_bytes!(env!("INITRAMFS"));
+
+/// The archive passed by QEMU `-initrd`, or the one linked in with the `initramfs` feature
+pub fn get_archive() -> Option<&'static [u8]> {
+    if let Some((start, end)) = board::initrd() {
+        // the pages are kept out of the frame allocator until `release_archive`
+        return Some(unsafe { core::slice::from_raw_parts(start as *const u8, end - start) });
+    }
+
+    #[cfg(feature = "initramfs")]
+    {
+        Some(EMBEDDED_INITRAMFS)
+    }
+
+    #[cfg(not(feature = "initramfs"))]
+    {
+        None
+    }
+}
+
+/// Give the pages of the `-initrd` archive to the frame allocator, the archive
+/// must not be used after it has been unpacked
+pub fn release_archive() {
+    if let Some((start, end)) = board::initrd() {
+        mm::add_ppn_range(PhysAddr(start), PhysAddr(end));
+        info!("Initramfs: released [{:#x}, {:#x})", start, end);
+    }
+}
+
+/// Unpack a cpio newc archive into `fs`, only directories and regular files are supported
+pub fn unpack(fs: &TmpFileSystem, archive: &[u8]) {
+    let mut count = 0;
+    for entry in CpioReader::new(archive) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(()) => {
+                warn!("Initramfs: malformed archive, stop unpacking");
+                break;
+            }
+        };
+        let path = entry.name.trim_start_matches("./");
+        if path.is_empty() || path == "." {
+            continue;
+        }
+
+        let node = match entry {
+            _ if entry.is_dir() => fs.create_dir_all(path),
+            _ if entry.is_file() => fs.create_file(path, entry.data),
+            _ if entry.is_symlink() => {
+                warn!("Initramfs: symlink {} is not supported, skip", path);
+                continue;
+            }
+            _ => {
+                warn!(
+                    "Initramfs: {} has unsupported mode {:#o}, skip",
+                    path, entry.mode
+                );
+                continue;
+            }
+        };
+        match node {
+            Some(node) => {
+                let mut inner = node.inner();
+                inner.mtime = entry.mtime;
+                inner.ctime = entry.mtime;
+                count += 1;
+            }
+            None => warn!("Initramfs: failed to create {}", path),
+        }
+    }
+    info!("Initramfs: unpacked {} entries", count);
+}
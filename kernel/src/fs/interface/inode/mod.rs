@@ -1,22 +1,34 @@
 pub use linux_dent::*;
 pub use open_flags::*;
+pub use poll_events::*;
 
-use crate::fs::fat;
-use alloc::string::String;
+use crate::fs::{EventPoll, Fifo, Pipe, SignalFd, TimerFd};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::task::{Context, Poll};
 
 mod linux_dent;
 mod open_flags;
+mod poll_events;
 
 pub trait Inode: Send + Sync {
     #[allow(unused)]
     fn name(&self) -> String;
     fn size(&self) -> usize;
     fn get_type(&self) -> InodeType;
-    fn to_file(&self) -> fat::FatFile;
-    fn to_dir(&self) -> fat::FatDir;
+    fn to_file(&self) -> Arc<dyn File + Send + Sync>;
+    fn to_dir(&self) -> Arc<dyn File + Send + Sync>;
+    fn get_entries(&self) -> Vec<LinuxDirent64>;
+
+    /// The named pipe an `InodeType::Fifo` opens into
+    fn get_fifo(&self) -> Option<Arc<Fifo>> {
+        None
+    }
+
     fn atime(&self) -> (usize, usize);
     fn mtime(&self) -> (usize, usize);
     fn ctime(&self) -> (usize, usize);
+    /// Err carries the errno
+    fn set_times(&self, atime: Option<usize>, mtime: Option<usize>) -> Result<(), isize>;
 }
 
 pub trait File: Send + Sync {
@@ -25,6 +37,61 @@ pub trait File: Send + Sync {
     fn read(&self, buf: &mut [u8]) -> usize;
     fn write(&self, buf: &[u8]) -> usize;
     fn path(&self) -> String;
+
+    /// Used by the syscalls, a file that would block returns `Pending` with the
+    /// waker registered. Others are always ready. Err carries the errno.
+    fn poll_read(&self, buf: &mut [u8], _cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        Poll::Ready(Ok(self.read(buf)))
+    }
+
+    fn poll_write(&self, buf: &[u8], _cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        Poll::Ready(Ok(self.write(buf)))
+    }
+
+    /// The events that are ready, for poll, select and epoll. The waker is
+    /// registered for `events` either way, to fire on a later change. Files
+    /// that never block are always ready.
+    fn poll_events(&self, _events: PollEvents, _cx: &mut Context<'_>) -> PollEvents {
+        let mut ready = PollEvents::empty();
+        ready.set(PollEvents::IN, self.readable());
+        ready.set(PollEvents::OUT, self.writable());
+        ready
+    }
+
+    /// O_NONBLOCK of the open file, only honoured by files that may block
+    fn is_nonblock(&self) -> bool {
+        false
+    }
+
+    fn set_nonblock(&self, _nonblock: bool) {}
+
+    fn as_pipe(&self) -> Option<&Pipe> {
+        None
+    }
+
+    fn as_event_poll(&self) -> Option<&EventPoll> {
+        None
+    }
+
+    fn as_timer_fd(&self) -> Option<&TimerFd> {
+        None
+    }
+
+    fn as_signal_fd(&self) -> Option<&SignalFd> {
+        None
+    }
+
+    fn truncate(&self, _len: usize) -> bool {
+        false
+    }
+
+    /// Read at `offset` and leave the file offset alone, for the ELF loader.
+    /// Files that cannot seek read nothing.
+    fn read_at(&self, _offset: usize, _buf: &mut [u8]) -> usize {
+        0
+    }
+
+    fn sync(&self) {}
 }
 
 // region InodeType begin
@@ -33,6 +100,7 @@ pub enum InodeType {
     Unknown,
     File,
     Dir,
+    Fifo,
     #[allow(unused)]
     CharDevice,
 }
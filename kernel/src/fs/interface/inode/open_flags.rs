@@ -9,6 +9,8 @@ bitflags! {
         const RDWR = 1 << 1; // 0x2
         const CREATE = 1 << 6; // 0x40
         const TRUNC = 1 << 10; // 0x400
+        const NONBLOCK = 1 << 11; // 0x800
+        const CLOEXEC = 1 << 19; // 0x80000
         const DIRECTORY = 1 << 21; // 0x200000
     }
 }
@@ -29,5 +31,13 @@ impl OpenFlags {
     pub const fn directory(&self) -> bool {
         self.contains(OpenFlags::DIRECTORY)
     }
+
+    pub const fn nonblock(&self) -> bool {
+        self.contains(OpenFlags::NONBLOCK)
+    }
+
+    pub const fn cloexec(&self) -> bool {
+        self.contains(OpenFlags::CLOEXEC)
+    }
 }
 // region OpenFlags end
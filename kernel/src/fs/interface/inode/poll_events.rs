@@ -0,0 +1,16 @@
+use bitflags::bitflags;
+
+// region PollEvents begin
+bitflags! {
+    /// `events` and `revents` of struct pollfd
+    #[derive(Clone, Copy)]
+    pub struct PollEvents: u16 {
+        const IN = 1 << 0; // 0x1
+        const PRI = 1 << 1; // 0x2
+        const OUT = 1 << 2; // 0x4
+        const ERR = 1 << 3; // 0x8
+        const HUP = 1 << 4; // 0x10
+        const NVAL = 1 << 5; // 0x20
+    }
+}
+// region PollEvents end
@@ -1,11 +1,19 @@
 pub use inode::*;
 pub use virtio::*;
 
+use alloc::sync::Arc;
+
 mod inode;
 mod virtio;
 
 pub trait FileSystem: Send + Sync {
-    fn open(&'static self, path: &str, flags: OpenFlags) -> Option<super::fat::FatInode>;
+    /// Paths passed to a filesystem are relative to its mount point
+    fn open(&'static self, path: &str, flags: OpenFlags) -> Option<Arc<dyn Inode>>;
     fn create_dir(&'static self, path: &str, mode: usize) -> bool;
+    fn create_fifo(&'static self, path: &str) -> Result<(), ()>;
     fn delete(&'static self, path: &str) -> Result<(), ()>;
+    /// Err carries the errno
+    fn rename(&'static self, old_path: &str, new_path: &str, no_replace: bool)
+        -> Result<(), isize>;
+    fn sync(&'static self);
 }
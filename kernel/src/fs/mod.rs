@@ -1,33 +1,142 @@
+pub use epoll::*;
+pub use eventfd::*;
 pub use interface::*;
+pub use mount::*;
 pub use path::*;
 pub use pipe::*;
+pub use signalfd::*;
 pub use stdio::*;
+pub use timerfd::*;
 
-use alloc::sync::Arc;
-use lazy_static::lazy_static;
+use crate::{
+    config::{DISK_MOUNT_POINT, ROOT_DIR},
+    drivers,
+    syscall::errno::{EBUSY, EINVAL, EISDIR, ENOENT, ENOTDIR, EXDEV},
+    util,
+};
+use alloc::{boxed::Box, sync::Arc};
+use log::info;
+use virtio_drivers::transport::DeviceType;
 
+mod epoll;
+mod eventfd;
 pub mod fat;
+mod initramfs;
 mod interface;
+mod mount;
 mod path;
 mod pipe;
+mod signalfd;
 mod stdio;
+mod timerfd;
+pub mod tmpfs;
 
-pub fn open_file(path: &str, flags: OpenFlags) -> Option<fat::FatInode> {
-    ROOT_FILESYSTEM.open(path, flags)
+/// Mount the root filesystem, an initramfs if there is one, the block device otherwise
+pub fn init() {
+    // root=/dev/vdX, defaults to the first block device
+    let index = util::get_cmdline().root_device().unwrap_or(0);
+    let transport = drivers::probe_virtio_device(DeviceType::Block, index);
+
+    let Some(archive) = initramfs::get_archive() else {
+        let transport = transport.expect("Neither initramfs nor root block device is found");
+        let root_fs: &'static _ = Box::leak(Box::new(fat::FatFileSystem::new(ROOT_DIR, transport)));
+        mount(ROOT_DIR, root_fs).unwrap();
+        return;
+    };
+
+    let root_fs: &'static _ = Box::leak(Box::new(tmpfs::TmpFileSystem::new(ROOT_DIR)));
+    initramfs::unpack(root_fs, archive);
+    initramfs::release_archive();
+    mount(ROOT_DIR, root_fs).unwrap();
+
+    // the disk is optional once the initramfs provides the root
+    match transport {
+        Some(transport) => {
+            root_fs.create_dir_all(DISK_MOUNT_POINT);
+            let disk_fs: &'static _ = Box::leak(Box::new(fat::FatFileSystem::new(
+                DISK_MOUNT_POINT,
+                transport,
+            )));
+            mount(DISK_MOUNT_POINT, disk_fs).unwrap();
+        }
+        None => info!("Filesystem: no block device, run from initramfs only"),
+    }
+}
+
+pub fn open_file(path: &str, flags: OpenFlags) -> Option<Arc<dyn Inode>> {
+    let (fs, path) = lookup(path)?;
+    fs.open(&path, flags)
+}
+
+/// Read a whole file onto the kernel heap, fine for small ones like the test manifest
+#[cfg(feature = "test")]
+pub fn read_file(path: &str) -> Option<alloc::vec::Vec<u8>> {
+    use alloc::vec;
+
+    let inode = open_file(path, OpenFlags::RDONLY)?;
+    if inode.get_type() != InodeType::File {
+        return None;
+    }
+    let mut buf = vec![0; inode.size()];
+    inode.to_file().read(&mut buf);
+    Some(buf)
+}
+
+/// Mount an empty tmpfs on the directory `path`
+pub fn mount_tmpfs(path: &str) -> Result<(), isize> {
+    match open_file(path, OpenFlags::RDONLY) {
+        Some(inode) if inode.get_type() == InodeType::Dir => {}
+        Some(_) => return Err(ENOTDIR),
+        None => return Err(ENOENT),
+    }
+    if lookup(path).is_some_and(|(_, local)| local == ROOT_DIR) {
+        return Err(EBUSY);
+    }
+    let fs: &'static _ = Box::leak(Box::new(tmpfs::TmpFileSystem::new(path)));
+    mount(path, fs).map_err(|()| EBUSY)
 }
 
 pub fn create_dir(path: &str, mode: usize) -> bool {
-    ROOT_FILESYSTEM.create_dir(path, mode)
+    match lookup(path) {
+        Some((fs, path)) => fs.create_dir(&path, mode),
+        None => false,
+    }
+}
+
+pub fn create_fifo(path: &str) -> Result<(), ()> {
+    let (fs, path) = lookup(path).ok_or(())?;
+    fs.create_fifo(&path)
 }
 
 pub fn delete(path: &str) -> Result<(), ()> {
-    ROOT_FILESYSTEM.delete(path)
+    let (fs, path) = lookup(path).ok_or(())?;
+    fs.delete(&path)
 }
 
-pub fn open_inode(path: &str) -> Option<fat::FatInode> {
+pub fn open_inode(path: &str) -> Option<Arc<dyn Inode>> {
     open_file(path, OpenFlags::RDONLY)
 }
 
-lazy_static! {
-    static ref ROOT_FILESYSTEM: Arc<dyn FileSystem> = Arc::new(fat::FatFileSystem::new(0));
+/// Err carries the errno
+pub fn rename(old_path: &str, new_path: &str, no_replace: bool) -> Result<(), isize> {
+    let (old_fs, old_path) = lookup(old_path).ok_or(ENOENT)?;
+    let (new_fs, new_path) = lookup(new_path).ok_or(ENOENT)?;
+    // moving across filesystems is not supported
+    if !core::ptr::addr_eq(old_fs, new_fs) {
+        return Err(EXDEV);
+    }
+    old_fs.rename(&old_path, &new_path, no_replace)
+}
+
+pub fn truncate(path: &str, len: usize) -> Result<(), isize> {
+    let inode = open_file(path, OpenFlags::WRONLY).ok_or(ENOENT)?;
+    match inode.get_type() {
+        InodeType::File if inode.to_file().truncate(len) => Ok(()),
+        InodeType::Dir => Err(EISDIR),
+        _ => Err(EINVAL),
+    }
+}
+
+pub fn sync() {
+    get_filesystems().into_iter().for_each(|fs| fs.sync());
 }
@@ -0,0 +1,117 @@
+use crate::{
+    config::{DIR_SEPARATOR, ROOT_DIR},
+    fs::FileSystem,
+    sync::RwLock,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use lazy_static::lazy_static;
+use log::info;
+
+pub fn mount(mount_point: &str, fs: &'static dyn FileSystem) -> Result<(), ()> {
+    MOUNT_TABLE.mount(mount_point, fs)
+}
+
+/// Find the filesystem holding `path`, and the path relative to its mount point
+pub fn lookup(path: &str) -> Option<(&'static dyn FileSystem, String)> {
+    MOUNT_TABLE.lookup(path)
+}
+
+pub fn get_filesystems() -> Vec<&'static dyn FileSystem> {
+    MOUNT_TABLE.filesystems()
+}
+
+/// Turn a path relative to `mount_point` back into an absolute one
+pub fn to_absolute(mount_point: &str, path: &str) -> String {
+    if mount_point == ROOT_DIR {
+        path.to_string()
+    } else if path == ROOT_DIR {
+        mount_point.to_string()
+    } else {
+        format!("{}{}", mount_point, path)
+    }
+}
+
+lazy_static! {
+    static ref MOUNT_TABLE: MountTable = MountTable::new();
+}
+
+// region MountTable begin
+/// Filesystems are leaked on mount, so that inodes could borrow them for `'static`
+struct MountTable {
+    inner: RwLock<Vec<MountPoint>>,
+}
+
+impl MountTable {
+    fn new() -> Self {
+        Self {
+            inner: RwLock::new("MountTable", Vec::new()),
+        }
+    }
+
+    fn mount(&self, mount_point: &str, fs: &'static dyn FileSystem) -> Result<(), ()> {
+        let path = normalize(mount_point);
+        let mut inner = self.inner.write();
+        if inner.iter().any(|mp| mp.path == path) {
+            return Err(());
+        }
+        info!("MountTable: mount {}", path);
+        inner.push(MountPoint { path, fs });
+        Ok(())
+    }
+
+    fn lookup(&self, path: &str) -> Option<(&'static dyn FileSystem, String)> {
+        let path = normalize(path);
+        let inner = self.inner.read();
+        // the longest mount point wins
+        let mp = inner
+            .iter()
+            .filter(|mp| mp.contains(&path))
+            .max_by_key(|mp| mp.path.len())?;
+        let local = if mp.path == ROOT_DIR {
+            path
+        } else {
+            let local = &path[mp.path.len()..];
+            if local.is_empty() {
+                ROOT_DIR.to_string()
+            } else {
+                local.to_string()
+            }
+        };
+        Some((mp.fs, local))
+    }
+
+    fn filesystems(&self) -> Vec<&'static dyn FileSystem> {
+        self.inner.read().iter().map(|mp| mp.fs).collect()
+    }
+}
+// region MountTable end
+
+// region MountPoint begin
+struct MountPoint {
+    path: String,
+    fs: &'static dyn FileSystem,
+}
+
+impl MountPoint {
+    fn contains(&self, path: &str) -> bool {
+        self.path == ROOT_DIR
+            || path == self.path
+            || path
+                .strip_prefix(self.path.as_str())
+                .is_some_and(|rest| rest.starts_with(DIR_SEPARATOR))
+    }
+}
+// region MountPoint end
+
+/// Absolute path without empty components or a trailing separator
+fn normalize(path: &str) -> String {
+    let split: Vec<&str> = path
+        .split(DIR_SEPARATOR)
+        .filter(|s| !s.is_empty())
+        .collect();
+    format!("{}{}", ROOT_DIR, split.join(DIR_SEPARATOR))
+}
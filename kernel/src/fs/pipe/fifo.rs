@@ -0,0 +1,86 @@
+use crate::{
+    config::PIPE_DEFAULT_SIZE,
+    fs::{OpenFlags, Pipe, PipeRingBuffer},
+    sync::{Mutex, SpinLock},
+    syscall::errno::ENXIO,
+};
+use alloc::sync::{Arc, Weak};
+use core::{future, task::Poll};
+
+// region Fifo begin
+/// The inode side of a named pipe, its buffer lives only while an end is open
+pub struct Fifo {
+    buffer: SpinLock<Weak<Mutex<PipeRingBuffer>>>,
+}
+
+impl Fifo {
+    pub fn new() -> Self {
+        Self {
+            buffer: SpinLock::new("Fifo", Weak::new()),
+        }
+    }
+
+    /// Open an end sharing the buffer with the other opens, see fifo(7). A reader
+    /// waits for a writer and the other way round, unless O_NONBLOCK or O_RDWR.
+    /// Err carries the errno.
+    pub async fn open(&self, flags: OpenFlags) -> Result<Arc<Pipe>, isize> {
+        let (readable, writable) = flags.read_write();
+        let buffer = self.get_buffer()?;
+
+        let peer_opens = |ring_buffer: &PipeRingBuffer| match readable {
+            true => ring_buffer.get_writer_opens(),
+            false => ring_buffer.get_reader_opens(),
+        };
+        let seen = {
+            let ring_buffer = buffer.lock();
+            // nobody would ever read what is written
+            if writable && !readable && flags.nonblock() && ring_buffer.all_read_ends_are_closed() {
+                return Err(ENXIO);
+            }
+            peer_opens(&ring_buffer)
+        };
+        let pipe = Arc::new(Pipe::new(
+            buffer.clone(),
+            readable,
+            writable,
+            flags.nonblock(),
+        ));
+        if (readable && writable) || flags.nonblock() {
+            return Ok(pipe);
+        }
+
+        // an end that opened and closed in the meantime counts as well
+        future::poll_fn(|cx| {
+            let ring_buffer = buffer.lock();
+            let peers = match readable {
+                true => !ring_buffer.all_write_ends_are_closed(),
+                false => !ring_buffer.all_read_ends_are_closed(),
+            };
+            if peers || peer_opens(&ring_buffer) != seen {
+                return Poll::Ready(());
+            }
+            let wait_queue = match readable {
+                true => ring_buffer.get_read_wait(),
+                false => ring_buffer.get_write_wait(),
+            };
+            wait_queue.register(cx.waker());
+            Poll::Pending
+        })
+        .await;
+        Ok(pipe)
+    }
+
+    fn get_buffer(&self) -> Result<Arc<Mutex<PipeRingBuffer>>, isize> {
+        let mut buffer = self.buffer.lock();
+        if let Some(buffer) = buffer.upgrade() {
+            return Ok(buffer);
+        }
+        let new = Arc::new(Mutex::new(
+            "PipeRingBuffer",
+            PipeRingBuffer::new(PIPE_DEFAULT_SIZE)?,
+        ));
+        *buffer = Arc::downgrade(&new);
+        Ok(new)
+    }
+}
+// region Fifo end
@@ -1,40 +1,150 @@
+pub use fifo::*;
 pub use ring_buffer::*;
 
-use crate::{fs::File, task};
+use crate::{
+    config::{PIPE_DEFAULT_SIZE, PIPE_MAX_SIZE, SV39_PAGE_SIZE},
+    fs::{File, OpenFlags, PollEvents},
+    sync::Mutex,
+    syscall::errno::{EAGAIN, EINVAL, EPIPE},
+    task,
+};
 use alloc::sync::Arc;
-use spin::Mutex;
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
 
+mod fifo;
 mod ring_buffer;
 
-pub fn make_pipe() -> (Arc<Pipe>, Arc<Pipe>) {
-    let buffer = Arc::new(Mutex::new(PipeRingBuffer::new()));
-    let read_end = Arc::new(Pipe::new_read_end(buffer.clone()));
-    let write_end = Arc::new(Pipe::new_write_end(buffer.clone()));
-    buffer.lock().set_write_end(&write_end);
-    (read_end, write_end)
+/// Both ends of a new pipe, `flags` may carry O_NONBLOCK. Err carries the errno.
+pub fn make_pipe(flags: OpenFlags) -> Result<(Arc<Pipe>, Arc<Pipe>), isize> {
+    let buffer = Arc::new(Mutex::new(
+        "PipeRingBuffer",
+        PipeRingBuffer::new(PIPE_DEFAULT_SIZE)?,
+    ));
+    let read_end = Arc::new(Pipe::new(buffer.clone(), true, false, flags.nonblock()));
+    let write_end = Arc::new(Pipe::new(buffer, false, true, flags.nonblock()));
+    Ok((read_end, write_end))
 }
 
 // region Pipe begin
 pub struct Pipe {
     readable: bool,
     writable: bool,
+    nonblock: AtomicBool,
     buffer: Arc<Mutex<PipeRingBuffer>>,
 }
 
 impl Pipe {
-    pub fn new_read_end(buffer: Arc<Mutex<PipeRingBuffer>>) -> Self {
+    /// An end of a FIFO opened with O_RDWR is both readable and writable
+    pub fn new(
+        buffer: Arc<Mutex<PipeRingBuffer>>,
+        readable: bool,
+        writable: bool,
+        nonblock: bool,
+    ) -> Self {
+        let mut ring_buffer = buffer.lock();
+        // FIFO openers wait for the other kind of end
+        if readable {
+            ring_buffer.add_reader();
+            ring_buffer.get_write_wait().wake_all();
+        }
+        if writable {
+            ring_buffer.add_writer();
+            ring_buffer.get_read_wait().wake_all();
+        }
+        drop(ring_buffer);
+
         Self {
-            readable: true,
-            writable: false,
+            readable,
+            writable,
+            nonblock: AtomicBool::new(nonblock),
             buffer,
         }
     }
 
-    pub fn new_write_end(buffer: Arc<Mutex<PipeRingBuffer>>) -> Self {
-        Self {
-            readable: false,
-            writable: true,
-            buffer,
+    pub fn get_size(&self) -> usize {
+        self.buffer.lock().get_capacity()
+    }
+
+    /// F_SETPIPE_SZ, the size is rounded up to whole pages. Err carries the errno.
+    pub fn set_size(&self, size: usize) -> Result<usize, isize> {
+        if size > PIPE_MAX_SIZE {
+            return Err(EINVAL);
+        }
+        let size = size.max(1).div_ceil(SV39_PAGE_SIZE) * SV39_PAGE_SIZE;
+
+        let mut ring_buffer = self.buffer.lock();
+        ring_buffer.set_capacity(size)?;
+        ring_buffer.get_write_wait().wake_all();
+        Ok(size)
+    }
+
+    // move what the buffer holds, `waker` is registered if it would block
+    fn try_read(&self, buf: &mut [u8], waker: Option<&Waker>) -> Poll<Result<usize, isize>> {
+        let mut ring_buffer = self.buffer.lock();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let len = ring_buffer.read(buf);
+        if len == 0 {
+            if ring_buffer.all_write_ends_are_closed() {
+                return Poll::Ready(Ok(0));
+            }
+            if self.is_nonblock() {
+                return Poll::Ready(Err(EAGAIN));
+            }
+            // registered under the lock, so a write in between is not missed
+            if let Some(waker) = waker {
+                ring_buffer.get_read_wait().register(waker);
+            }
+            return Poll::Pending;
+        }
+
+        ring_buffer.get_write_wait().wake_all();
+        Poll::Ready(Ok(len))
+    }
+
+    // fill the free space of the buffer, `waker` is registered if it would block
+    fn try_write(&self, buf: &[u8], waker: Option<&Waker>) -> Poll<Result<usize, isize>> {
+        let mut ring_buffer = self.buffer.lock();
+        if ring_buffer.all_read_ends_are_closed() {
+            return Poll::Ready(Err(EPIPE));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let len = ring_buffer.write(buf);
+        if len == 0 {
+            if self.is_nonblock() {
+                return Poll::Ready(Err(EAGAIN));
+            }
+            if let Some(waker) = waker {
+                ring_buffer.get_write_wait().register(waker);
+            }
+            return Poll::Pending;
+        }
+
+        ring_buffer.get_read_wait().wake_all();
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let mut ring_buffer = self.buffer.lock();
+        // readers see the end of file once the last write end is gone, and
+        // writers get EPIPE once the last read end is
+        if self.readable {
+            ring_buffer.remove_reader();
+            ring_buffer.get_write_wait().wake_all();
+        }
+        if self.writable {
+            ring_buffer.remove_writer();
+            ring_buffer.get_read_wait().wake_all();
         }
     }
 }
@@ -50,89 +160,64 @@ impl File for Pipe {
 
     fn read(&self, buf: &mut [u8]) -> usize {
         assert!(self.readable);
-        let mut buf_iter = buf.iter_mut();
-        let mut buf_len = 0usize;
-
-        loop {
-            let mut ring_buffer = self.buffer.lock();
-            let loop_read = ring_buffer.read_bytes();
-            if loop_read == 0 {
-                if ring_buffer.all_write_ends_are_closed() {
-                    return buf_len;
-                }
-
-                drop(ring_buffer);
-                task::get_processor()
-                    .current()
-                    .get_trap_cx_mut()
-                    .move_to_prev_ins();
-                let current_task = task::get_processor().current();
-                let task_inner = current_task.inner();
-                if let Some(child) = task_inner
-                    .get_children_ref()
-                    .iter()
-                    .find(|pcb| !pcb.is_zombie())
-                {
-                    let pid = child.get_pid();
-                    drop(task_inner);
-                    task::get_processor().wait_for_child(pid);
-                }
-                task::get_processor().schedule();
-            }
-
-            for _ in 0..loop_read {
-                if let Some(byte) = buf_iter.next() {
-                    *byte = ring_buffer.read_byte();
-                    buf_len += 1;
-                } else {
-                    return buf_len;
-                }
-            }
+        match self.try_read(buf, None) {
+            Poll::Ready(Ok(len)) => len,
+            _ => 0,
         }
     }
 
     fn write(&self, buf: &[u8]) -> usize {
         assert!(self.writable);
-        let want_to_write = buf.len();
-        let mut buf_iter = buf.iter();
-        let mut buf_len = 0usize;
-
-        loop {
-            let mut ring_buffer = self.buffer.lock();
-            let loop_write = ring_buffer.write_bytes();
-            if loop_write == 0 {
-                drop(ring_buffer);
-                task::get_processor()
-                    .current()
-                    .get_trap_cx_mut()
-                    .move_to_prev_ins();
-                let current_task = task::get_processor().current();
-                let task_inner = current_task.inner();
-                if let Some(child) = task_inner
-                    .get_children_ref()
-                    .iter()
-                    .find(|pcb| !pcb.is_zombie())
-                {
-                    let pid = child.get_pid();
-                    drop(task_inner);
-                    task::get_processor().wait_for_child(pid);
-                }
-                task::get_processor().schedule();
-            }
+        match self.try_write(buf, None) {
+            Poll::Ready(Ok(len)) => len,
+            _ => 0,
+        }
+    }
 
-            for _ in 0..loop_write {
-                if let Some(byte) = buf_iter.next() {
-                    ring_buffer.write_byte(*byte);
-                    buf_len += 1;
-
-                    if buf_len == want_to_write {
-                        return buf_len;
-                    }
-                } else {
-                    return buf_len;
-                }
-            }
+    fn poll_read(&self, buf: &mut [u8], cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        assert!(self.readable);
+        self.try_read(buf, Some(cx.waker()))
+    }
+
+    fn poll_write(&self, buf: &[u8], cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        assert!(self.writable);
+        self.try_write(buf, Some(cx.waker()))
+    }
+
+    fn poll_events(&self, events: PollEvents, cx: &mut Context<'_>) -> PollEvents {
+        let ring_buffer = self.buffer.lock();
+        let mut ready = PollEvents::empty();
+        if self.readable {
+            // the end of file reads without blocking as well
+            let hangup = ring_buffer.all_write_ends_are_closed();
+            ready.set(PollEvents::IN, ring_buffer.read_bytes() > 0 || hangup);
+            ready.set(PollEvents::HUP, hangup);
+        }
+        if self.writable {
+            ready.set(PollEvents::OUT, ring_buffer.write_bytes() > 0);
+            ready.set(PollEvents::ERR, ring_buffer.all_read_ends_are_closed());
+        }
+
+        // registered under the lock, so a change in between is not missed
+        if self.readable && events.contains(PollEvents::IN) {
+            ring_buffer.get_read_wait().register(cx.waker());
+        }
+        if self.writable && events.contains(PollEvents::OUT) {
+            ring_buffer.get_write_wait().register(cx.waker());
         }
+        ready
+    }
+
+    fn is_nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
+
+    fn as_pipe(&self) -> Option<&Pipe> {
+        Some(self)
     }
 
     fn path(&self) -> alloc::string::String {
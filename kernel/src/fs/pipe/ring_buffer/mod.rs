@@ -1,58 +1,173 @@
 pub use status::*;
 
-use crate::fs::Pipe;
-use alloc::sync::{Arc, Weak};
+use crate::{
+    config::{PIPE_TOTAL_MAX_SIZE, SV39_PAGE_SIZE},
+    mm::{self, PpnTracker},
+    sync::WaitQueue,
+    syscall::errno::{EBUSY, ENOMEM},
+};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 mod status;
 
-const RING_BUFFER_SIZE: usize = 32;
+// frames held by all pipe buffers, kept under PIPE_TOTAL_MAX_SIZE
+static PIPE_PAGES: AtomicUsize = AtomicUsize::new(0);
 
 // region PipeRingBuffer begin
+/// The data lives in frames rather than on the kernel heap, `capacity` is
+/// always a multiple of the page size
 pub struct PipeRingBuffer {
-    arr: [u8; RING_BUFFER_SIZE],
+    pages: Vec<PpnTracker>,
     head: usize,
     tail: usize,
     status: RingBufferStatus,
-    write_end: Option<Weak<Pipe>>,
+    // open ends of each kind, a dup shares the end instead of adding one
+    readers: usize,
+    writers: usize,
+    // ends ever opened, a FIFO opener waits for the other kind to change
+    reader_opens: usize,
+    writer_opens: usize,
+    read_wait: WaitQueue,
+    write_wait: WaitQueue,
 }
 
 impl PipeRingBuffer {
-    pub fn new() -> Self {
-        Self {
-            arr: [0; RING_BUFFER_SIZE],
+    /// Err carries the errno
+    pub fn new(capacity: usize) -> Result<Self, isize> {
+        Ok(Self {
+            pages: alloc_pages(capacity)?,
             head: 0,
             tail: 0,
             status: RingBufferStatus::Empty,
-            write_end: None,
+            readers: 0,
+            writers: 0,
+            reader_opens: 0,
+            writer_opens: 0,
+            read_wait: WaitQueue::new(),
+            write_wait: WaitQueue::new(),
+        })
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.pages.len() * SV39_PAGE_SIZE
+    }
+
+    /// Move the data into a buffer of `capacity` bytes, EBUSY if it does not fit.
+    /// Err carries the errno.
+    pub fn set_capacity(&mut self, capacity: usize) -> Result<(), isize> {
+        let len = self.read_bytes();
+        if len > capacity {
+            return Err(EBUSY);
         }
+
+        let pages = alloc_pages(capacity)?;
+        for (i, page) in pages.iter().enumerate() {
+            let start = i * SV39_PAGE_SIZE;
+            if start >= len {
+                break;
+            }
+            let amount = (len - start).min(SV39_PAGE_SIZE);
+            self.copy_out(
+                self.head + start,
+                &mut page.ppn().as_bytes_array()[..amount],
+            );
+        }
+        release_pages(core::mem::replace(&mut self.pages, pages));
+        self.head = 0;
+        self.tail = len % capacity;
+        self.status = match len {
+            0 => RingBufferStatus::Empty,
+            _ if len == capacity => RingBufferStatus::Full,
+            _ => RingBufferStatus::Normal,
+        };
+        Ok(())
     }
 
-    pub fn set_write_end(&mut self, write_end: &Arc<Pipe>) {
-        self.write_end = Some(Arc::downgrade(write_end));
+    /// Readers wait here for data, the write end to close or, on a FIFO, to open
+    pub fn get_read_wait(&self) -> &WaitQueue {
+        &self.read_wait
+    }
+
+    /// Writers wait here for free space, the read end to close or, on a FIFO, to open
+    pub fn get_write_wait(&self) -> &WaitQueue {
+        &self.write_wait
     }
 }
 
 impl PipeRingBuffer {
-    pub fn read_byte(&mut self) -> u8 {
-        self.status = RingBufferStatus::Normal;
-        let byte = self.arr[self.head];
-        // step head
-        self.head = (self.head + 1) % RING_BUFFER_SIZE;
-        if self.head == self.tail {
-            self.status = RingBufferStatus::Empty;
+    pub fn add_reader(&mut self) {
+        self.readers += 1;
+        self.reader_opens += 1;
+    }
+
+    pub fn add_writer(&mut self) {
+        self.writers += 1;
+        self.writer_opens += 1;
+    }
+
+    pub fn get_reader_opens(&self) -> usize {
+        self.reader_opens
+    }
+
+    pub fn get_writer_opens(&self) -> usize {
+        self.writer_opens
+    }
+
+    pub fn remove_reader(&mut self) {
+        self.readers -= 1;
+    }
+
+    pub fn remove_writer(&mut self) {
+        self.writers -= 1;
+    }
+
+    pub fn all_read_ends_are_closed(&self) -> bool {
+        self.readers == 0
+    }
+
+    pub fn all_write_ends_are_closed(&self) -> bool {
+        self.writers == 0
+    }
+}
+
+impl PipeRingBuffer {
+    /// Copy out as much as `buf` holds, the data may wrap around the end
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let len = self.read_bytes().min(buf.len());
+        if len == 0 {
+            return 0;
         }
 
-        byte
+        self.copy_out(self.head, &mut buf[..len]);
+        // step head
+        self.head = (self.head + len) % self.get_capacity();
+        self.status = if self.head == self.tail {
+            RingBufferStatus::Empty
+        } else {
+            RingBufferStatus::Normal
+        };
+
+        len
     }
 
-    pub fn write_byte(&mut self, byte: u8) {
-        self.status = RingBufferStatus::Normal;
-        self.arr[self.tail] = byte;
-        // step tail
-        self.tail = (self.tail + 1) % RING_BUFFER_SIZE;
-        if self.tail == self.head {
-            self.status = RingBufferStatus::Full;
+    /// Copy in as much as the free space holds
+    pub fn write(&mut self, buf: &[u8]) -> usize {
+        let len = self.write_bytes().min(buf.len());
+        if len == 0 {
+            return 0;
         }
+
+        self.copy_in(self.tail, &buf[..len]);
+        // step tail
+        self.tail = (self.tail + len) % self.get_capacity();
+        self.status = if self.tail == self.head {
+            RingBufferStatus::Full
+        } else {
+            RingBufferStatus::Normal
+        };
+
+        len
     }
 
     pub fn read_bytes(&self) -> usize {
@@ -61,7 +176,7 @@ impl PipeRingBuffer {
         } else if self.tail > self.head {
             self.tail - self.head
         } else {
-            RING_BUFFER_SIZE - self.head + self.tail
+            self.get_capacity() - self.head + self.tail
         }
     }
 
@@ -69,12 +184,66 @@ impl PipeRingBuffer {
         if self.status == RingBufferStatus::Full {
             0
         } else {
-            RING_BUFFER_SIZE - self.read_bytes()
+            self.get_capacity() - self.read_bytes()
         }
     }
 
-    pub fn all_write_ends_are_closed(&self) -> bool {
-        self.write_end.as_ref().unwrap().upgrade().is_none()
+    // copy out from the ring offset `pos`, a page never wraps around the end
+    fn copy_out(&self, pos: usize, buf: &mut [u8]) {
+        let mut done = 0;
+        while done < buf.len() {
+            let at = (pos + done) % self.get_capacity();
+            let page = self.pages[at / SV39_PAGE_SIZE].ppn().as_bytes_array();
+            let page_offset = at % SV39_PAGE_SIZE;
+            let amount = (buf.len() - done).min(SV39_PAGE_SIZE - page_offset);
+            buf[done..done + amount].copy_from_slice(&page[page_offset..page_offset + amount]);
+            done += amount;
+        }
+    }
+
+    fn copy_in(&mut self, pos: usize, buf: &[u8]) {
+        let mut done = 0;
+        while done < buf.len() {
+            let at = (pos + done) % self.get_capacity();
+            let page = self.pages[at / SV39_PAGE_SIZE].ppn().as_bytes_array();
+            let page_offset = at % SV39_PAGE_SIZE;
+            let amount = (buf.len() - done).min(SV39_PAGE_SIZE - page_offset);
+            page[page_offset..page_offset + amount].copy_from_slice(&buf[done..done + amount]);
+            done += amount;
+        }
+    }
+}
+
+impl Drop for PipeRingBuffer {
+    fn drop(&mut self) {
+        release_pages(core::mem::take(&mut self.pages));
     }
 }
 // region PipeRingBuffer end
+
+// frames for `capacity` bytes, ENOMEM beyond PIPE_TOTAL_MAX_SIZE or once the
+// frames run out
+fn alloc_pages(capacity: usize) -> Result<Vec<PpnTracker>, isize> {
+    let count = capacity / SV39_PAGE_SIZE;
+    let held = PIPE_PAGES.fetch_add(count, Ordering::Relaxed);
+    if held + count > PIPE_TOTAL_MAX_SIZE / SV39_PAGE_SIZE {
+        PIPE_PAGES.fetch_sub(count, Ordering::Relaxed);
+        return Err(ENOMEM);
+    }
+
+    let mut pages = Vec::with_capacity(count);
+    for _ in 0..count {
+        match mm::alloc_ppn_tracker() {
+            Some(page) => pages.push(page),
+            None => {
+                PIPE_PAGES.fetch_sub(count, Ordering::Relaxed);
+                return Err(ENOMEM);
+            }
+        }
+    }
+    Ok(pages)
+}
+
+fn release_pages(pages: Vec<PpnTracker>) {
+    PIPE_PAGES.fetch_sub(pages.len(), Ordering::Relaxed);
+}
@@ -0,0 +1,137 @@
+use crate::{
+    fs::{File, PollEvents},
+    sync::SpinNoIrqLock,
+    syscall::errno::{EAGAIN, EINVAL},
+    task::{self, SignalSet},
+};
+use alloc::string::{String, ToString};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+// region SignalFdInfo begin
+/// struct signalfd_siginfo, the sender is not recorded so only the number is set
+#[repr(C)]
+struct SignalFdInfo {
+    signo: u32,
+    _rest: [u32; 31],
+}
+// region SignalFdInfo end
+
+// region SignalFd begin
+/// Pending signals of the reading process in `mask` are taken by read instead
+/// of being delivered, they are usually blocked as well
+pub struct SignalFd {
+    mask: SpinNoIrqLock<SignalSet>,
+    nonblock: AtomicBool,
+}
+
+impl SignalFd {
+    pub fn new(mask: SignalSet, nonblock: bool) -> Self {
+        Self {
+            mask: SpinNoIrqLock::new("SignalFd", mask),
+            nonblock: AtomicBool::new(nonblock),
+        }
+    }
+
+    pub fn set_mask(&self, mask: SignalSet) {
+        *self.mask.lock() = mask;
+    }
+
+    fn try_read(&self, buf: &mut [u8], waker: Option<&Waker>) -> Poll<Result<usize, isize>> {
+        const INFO_SIZE: usize = size_of::<SignalFdInfo>();
+        if buf.len() < INFO_SIZE {
+            return Poll::Ready(Err(EINVAL));
+        }
+
+        let mask = *self.mask.lock();
+        let current_task = task::get_processor().current();
+        let mut task_inner = current_task.inner();
+        let signals = task_inner.get_signals_mut();
+
+        let mut len = 0;
+        while len + INFO_SIZE <= buf.len() {
+            let Some(signo) = signals.take_pending(mask) else {
+                break;
+            };
+            let info = SignalFdInfo {
+                signo: signo as u32,
+                _rest: [0; 31],
+            };
+            let info = unsafe {
+                core::slice::from_raw_parts(&info as *const SignalFdInfo as *const u8, INFO_SIZE)
+            };
+            buf[len..len + INFO_SIZE].copy_from_slice(info);
+            len += INFO_SIZE;
+        }
+        if len > 0 {
+            return Poll::Ready(Ok(len));
+        }
+
+        if self.is_nonblock() {
+            return Poll::Ready(Err(EAGAIN));
+        }
+        // registered under the process lock that send_signal raises under
+        if let Some(waker) = waker {
+            current_task.get_signal_raised().register(waker);
+        }
+        Poll::Pending
+    }
+}
+
+impl File for SignalFd {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn read(&self, buf: &mut [u8]) -> usize {
+        match self.try_read(buf, None) {
+            Poll::Ready(Ok(len)) => len,
+            _ => 0,
+        }
+    }
+
+    fn write(&self, _buf: &[u8]) -> usize {
+        0
+    }
+
+    fn poll_read(&self, buf: &mut [u8], cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        self.try_read(buf, Some(cx.waker()))
+    }
+
+    fn poll_events(&self, events: PollEvents, cx: &mut Context<'_>) -> PollEvents {
+        let mask = *self.mask.lock();
+        let current_task = task::get_processor().current();
+        let task_inner = current_task.inner();
+        if events.contains(PollEvents::IN) {
+            current_task.get_signal_raised().register(cx.waker());
+        }
+        if task_inner.get_signals_ref().has_pending(mask) {
+            PollEvents::IN
+        } else {
+            PollEvents::empty()
+        }
+    }
+
+    fn is_nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
+
+    fn as_signal_fd(&self) -> Option<&SignalFd> {
+        Some(self)
+    }
+
+    fn path(&self) -> String {
+        "anon_inode:[signalfd]".to_string()
+    }
+}
+// region SignalFd end
@@ -1,9 +1,38 @@
-use crate::{fs::File, sbi};
+use crate::{
+    fs::{File, PollEvents},
+    sbi,
+    sync::{SpinNoIrqLock, WaitQueue},
+    timer::{self, TimeSpec},
+};
 use alloc::string::ToString;
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::Context,
+};
+
+// the console raises no interrupt, a waiting poll looks again this often
+const CONSOLE_POLL_INTERVAL: TimeSpec = TimeSpec::from_nsec(10_000_000); // 10 ms
 
 // region Stdin begin
 pub struct Stdin;
 
+// a char taken from the console by poll, read returns it first
+static LOOKAHEAD: SpinNoIrqLock<Option<u8>> = SpinNoIrqLock::new("Stdin", None);
+
+// tasks polling for input, a single pending timer wakes them all
+static POLL_WAITERS: WaitQueue = WaitQueue::new();
+static POLL_TIMER_ARMED: AtomicBool = AtomicBool::new(false);
+
+impl Stdin {
+    fn getchar() -> Option<u8> {
+        // the legacy extension returns -1 with nothing typed
+        match sbi::console_getchar() {
+            usize::MAX => None,
+            c => Some(c as u8),
+        }
+    }
+}
+
 impl File for Stdin {
     fn readable(&self) -> bool {
         true
@@ -15,8 +44,11 @@ impl File for Stdin {
 
     fn read(&self, buf: &mut [u8]) -> usize {
         assert_eq!(buf.len(), 1, "Stdin: only support length 1 read");
-        let c = sbi::console_getchar();
-        buf[0] = c as u8;
+        let c = match LOOKAHEAD.lock().take() {
+            Some(c) => c,
+            None => sbi::console_getchar() as u8,
+        };
+        buf[0] = c;
         buf.len()
     }
 
@@ -24,6 +56,29 @@ impl File for Stdin {
         panic!("Stdin: write is not supported");
     }
 
+    fn poll_events(&self, events: PollEvents, cx: &mut Context<'_>) -> PollEvents {
+        let mut lookahead = LOOKAHEAD.lock();
+        if lookahead.is_none() {
+            *lookahead = Self::getchar();
+        }
+        if lookahead.is_some() {
+            return PollEvents::IN;
+        }
+        drop(lookahead);
+
+        if events.contains(PollEvents::IN) {
+            POLL_WAITERS.register(cx.waker());
+            if !POLL_TIMER_ARMED.swap(true, Ordering::Relaxed) {
+                let deadline = timer::get_current_tick() + CONSOLE_POLL_INTERVAL.to_ticks();
+                timer::add_timer(deadline, || {
+                    POLL_TIMER_ARMED.store(false, Ordering::Relaxed);
+                    POLL_WAITERS.wake_all();
+                });
+            }
+        }
+        PollEvents::empty()
+    }
+
     fn path(&self) -> alloc::string::String {
         "/dev/stdin".to_string()
     }
@@ -0,0 +1,143 @@
+use crate::{
+    fs::{File, PollEvents},
+    sync::{SpinNoIrqLock, WaitQueue},
+    syscall::errno::{EAGAIN, EINVAL},
+    task::IntervalTimer,
+    timer::ClockId,
+};
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+// region TimerFd begin
+/// A timer on the kernel timer queue, read takes the count of expirations
+pub struct TimerFd {
+    clock: ClockId,
+    timer: Arc<IntervalTimer>,
+    expirations: Arc<Expirations>,
+    nonblock: AtomicBool,
+}
+
+// shared with the notify callback of the timer
+struct Expirations {
+    count: SpinNoIrqLock<u64>,
+    read_wait: WaitQueue,
+}
+
+impl TimerFd {
+    pub fn new(clock: ClockId, nonblock: bool) -> Self {
+        let expirations = Arc::new(Expirations {
+            count: SpinNoIrqLock::new("TimerFd", 0),
+            read_wait: WaitQueue::new(),
+        });
+        let notified = expirations.clone();
+        let timer = IntervalTimer::new(move |count| {
+            *notified.count.lock() += count as u64;
+            notified.read_wait.wake_all();
+        });
+        Self {
+            clock,
+            timer,
+            expirations,
+            nonblock: AtomicBool::new(nonblock),
+        }
+    }
+
+    pub fn get_clock(&self) -> ClockId {
+        self.clock
+    }
+
+    /// Arm at `deadline` in ticks, or disarm with None. Expirations not read
+    /// yet are dropped. Return the old (remaining, interval).
+    pub fn set(&self, deadline: Option<usize>, interval: usize) -> (usize, usize) {
+        let old = self.timer.set(deadline, interval);
+        *self.expirations.count.lock() = 0;
+        old
+    }
+
+    /// Return (remaining, interval) in ticks
+    pub fn get(&self) -> (usize, usize) {
+        self.timer.get()
+    }
+
+    fn try_read(&self, buf: &mut [u8], waker: Option<&Waker>) -> Poll<Result<usize, isize>> {
+        if buf.len() < size_of::<u64>() {
+            return Poll::Ready(Err(EINVAL));
+        }
+
+        let mut count = self.expirations.count.lock();
+        if *count == 0 {
+            if self.is_nonblock() {
+                return Poll::Ready(Err(EAGAIN));
+            }
+            if let Some(waker) = waker {
+                self.expirations.read_wait.register(waker);
+            }
+            return Poll::Pending;
+        }
+
+        let value = core::mem::take(&mut *count);
+        drop(count);
+        buf[..size_of::<u64>()].copy_from_slice(&value.to_ne_bytes());
+        Poll::Ready(Ok(size_of::<u64>()))
+    }
+}
+
+impl File for TimerFd {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn read(&self, buf: &mut [u8]) -> usize {
+        match self.try_read(buf, None) {
+            Poll::Ready(Ok(len)) => len,
+            _ => 0,
+        }
+    }
+
+    fn write(&self, _buf: &[u8]) -> usize {
+        0
+    }
+
+    fn poll_read(&self, buf: &mut [u8], cx: &mut Context<'_>) -> Poll<Result<usize, isize>> {
+        self.try_read(buf, Some(cx.waker()))
+    }
+
+    fn poll_events(&self, events: PollEvents, cx: &mut Context<'_>) -> PollEvents {
+        let count = self.expirations.count.lock();
+        if events.contains(PollEvents::IN) {
+            self.expirations.read_wait.register(cx.waker());
+        }
+        if *count > 0 {
+            PollEvents::IN
+        } else {
+            PollEvents::empty()
+        }
+    }
+
+    fn is_nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
+
+    fn as_timer_fd(&self) -> Option<&TimerFd> {
+        Some(self)
+    }
+
+    fn path(&self) -> String {
+        "anon_inode:[timerfd]".to_string()
+    }
+}
+// region TimerFd end
@@ -0,0 +1,42 @@
+use crate::fs::File;
+use alloc::string::String;
+
+// region TmpDir begin
+pub struct TmpDir {
+    readable: bool,
+    writable: bool,
+    path: String,
+}
+
+impl TmpDir {
+    pub fn new(path: String, readable: bool, writable: bool) -> Self {
+        Self {
+            readable,
+            writable,
+            path,
+        }
+    }
+}
+
+impl File for TmpDir {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> usize {
+        0
+    }
+
+    fn write(&self, _buf: &[u8]) -> usize {
+        0
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+}
+// region TmpDir end
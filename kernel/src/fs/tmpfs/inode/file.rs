@@ -0,0 +1,66 @@
+use crate::{
+    fs::{tmpfs::TmpNode, File},
+    sync::SpinLock,
+};
+use alloc::{string::String, sync::Arc};
+
+// region TmpFile begin
+pub struct TmpFile {
+    readable: bool,
+    writable: bool,
+    path: String,
+    node: Arc<TmpNode>,
+    offset: SpinLock<usize>,
+}
+
+impl TmpFile {
+    pub fn new(path: String, node: Arc<TmpNode>, readable: bool, writable: bool) -> Self {
+        Self {
+            readable,
+            writable,
+            path,
+            node,
+            offset: SpinLock::new("TmpFile", 0),
+        }
+    }
+}
+
+impl File for TmpFile {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    fn read(&self, buf: &mut [u8]) -> usize {
+        assert!(self.readable);
+        let mut offset = self.offset.lock();
+        let len = self.node.read_at(*offset, buf);
+        *offset += len;
+        len
+    }
+
+    fn write(&self, buf: &[u8]) -> usize {
+        assert!(self.writable);
+        let mut offset = self.offset.lock();
+        let len = self.node.write_at(*offset, buf);
+        *offset += len;
+        len
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn truncate(&self, len: usize) -> bool {
+        self.writable && self.node.truncate(len)
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        assert!(self.readable);
+        self.node.read_at(offset, buf)
+    }
+}
+// region TmpFile end
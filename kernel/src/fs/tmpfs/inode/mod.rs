@@ -0,0 +1,109 @@
+pub use dir::*;
+pub use file::*;
+
+use super::TmpNode;
+use crate::{
+    config::ROOT_DIR,
+    fs::{Fifo, File, Inode, InodeType, LinuxDirent64, PathUtil},
+};
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+
+mod dir;
+mod file;
+
+// region TmpInode begin
+pub struct TmpInode {
+    readable: bool,
+    writable: bool,
+    path: String,
+    node: Arc<TmpNode>,
+}
+
+impl TmpInode {
+    pub fn new(path: String, node: Arc<TmpNode>, readable: bool, writable: bool) -> Self {
+        Self {
+            readable,
+            writable,
+            path,
+            node,
+        }
+    }
+}
+
+impl Inode for TmpInode {
+    fn name(&self) -> String {
+        match PathUtil::from_str(&self.path).name() {
+            name if name.is_empty() => ROOT_DIR.to_string(),
+            name => name,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.node.size()
+    }
+
+    fn get_type(&self) -> InodeType {
+        if self.node.is_dir() {
+            InodeType::Dir
+        } else if self.node.get_fifo().is_some() {
+            InodeType::Fifo
+        } else {
+            InodeType::File
+        }
+    }
+
+    fn to_file(&self) -> Arc<dyn File + Send + Sync> {
+        assert!(!self.node.is_dir());
+        Arc::new(TmpFile::new(
+            self.path.clone(),
+            self.node.clone(),
+            self.readable,
+            self.writable,
+        ))
+    }
+
+    fn to_dir(&self) -> Arc<dyn File + Send + Sync> {
+        assert!(self.node.is_dir());
+        Arc::new(TmpDir::new(self.path.clone(), self.readable, self.writable))
+    }
+
+    fn get_fifo(&self) -> Option<Arc<Fifo>> {
+        self.node.get_fifo()
+    }
+
+    fn get_entries(&self) -> Vec<LinuxDirent64> {
+        self.node
+            .child_names()
+            .iter()
+            .map(|name| LinuxDirent64::new(name))
+            .collect()
+    }
+
+    fn atime(&self) -> (usize, usize) {
+        (self.node.inner().atime, 0)
+    }
+
+    fn mtime(&self) -> (usize, usize) {
+        (self.node.inner().mtime, 0)
+    }
+
+    fn ctime(&self) -> (usize, usize) {
+        (self.node.inner().ctime, 0)
+    }
+
+    fn set_times(&self, atime: Option<usize>, mtime: Option<usize>) -> Result<(), isize> {
+        let mut inner = self.node.inner();
+        if let Some(secs) = atime {
+            inner.atime = secs;
+        }
+        if let Some(secs) = mtime {
+            inner.mtime = secs;
+        }
+        Ok(())
+    }
+}
+// region TmpInode end
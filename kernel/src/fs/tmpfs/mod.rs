@@ -0,0 +1,175 @@
+pub use inode::*;
+pub use node::*;
+
+use crate::{
+    config::DIR_SEPARATOR,
+    fs::{self, FileSystem, Inode, OpenFlags, PathUtil},
+    syscall::errno::{EBUSY, EEXIST, EINVAL, EISDIR, ENOENT, ENOTDIR, ENOTEMPTY},
+};
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+};
+
+mod inode;
+mod node;
+
+// region TmpFileSystem begin
+/// Filesystem kept entirely in memory, its content is lost on shutdown
+pub struct TmpFileSystem {
+    mount_point: String,
+    root: Arc<TmpNode>,
+}
+
+impl FileSystem for TmpFileSystem {
+    fn open(&'static self, path: &str, flags: OpenFlags) -> Option<Arc<dyn Inode>> {
+        let (readable, writable) = flags.read_write();
+        let node = match self.find(path) {
+            Some(node) => node,
+            None if flags.create() => {
+                let node = if flags.directory() {
+                    TmpNode::new_dir()
+                } else {
+                    TmpNode::new_file()
+                };
+                self.insert(path, node.clone(), false).ok()?;
+                node
+            }
+            None => return None,
+        };
+        let path = fs::to_absolute(&self.mount_point, &PathUtil::from_str(path).to_string());
+        Some(Arc::new(TmpInode::new(path, node, readable, writable)))
+    }
+
+    fn create_dir(&'static self, path: &str, _mode: usize) -> bool {
+        self.insert(path, TmpNode::new_dir(), true).is_ok()
+    }
+
+    fn create_fifo(&'static self, path: &str) -> Result<(), ()> {
+        self.insert(path, TmpNode::new_fifo(), false)
+    }
+
+    fn delete(&'static self, path: &str) -> Result<(), ()> {
+        let path = PathUtil::from_str(path);
+        let parent = self.find(&path.parent()).ok_or(())?;
+        let name = path.name();
+        match parent.child(&name) {
+            // directories must be emptied first
+            Some(node) if !node.is_dir() || node.is_empty_dir() => {
+                parent.remove_child(&name);
+                Ok(())
+            }
+            _ => Err(()),
+        }
+    }
+
+    fn rename(
+        &'static self,
+        old_path: &str,
+        new_path: &str,
+        no_replace: bool,
+    ) -> Result<(), isize> {
+        let old_path = PathUtil::from_str(old_path);
+        let new_path = PathUtil::from_str(new_path);
+        let old_name = old_path.name();
+        let new_name = new_path.name();
+
+        // root could not be moved
+        if old_name.is_empty() || new_name.is_empty() {
+            return Err(EBUSY);
+        }
+        // rename to itself
+        if old_path.to_string() == new_path.to_string() {
+            return Ok(());
+        }
+        // a directory could not be moved into itself
+        let old_prefix = old_path.to_string() + DIR_SEPARATOR;
+        if new_path.to_string().starts_with(&old_prefix) {
+            return Err(EINVAL);
+        }
+
+        let old_dir = self.find(&old_path.parent()).ok_or(ENOENT)?;
+        let new_dir = self.find(&new_path.parent()).ok_or(ENOENT)?;
+        let node = old_dir.child(&old_name).ok_or(ENOENT)?;
+        if let Some(target) = new_dir.child(&new_name) {
+            if no_replace {
+                return Err(EEXIST);
+            }
+            match (node.is_dir(), target.is_dir()) {
+                (true, false) => return Err(ENOTDIR),
+                (false, true) => return Err(EISDIR),
+                (true, true) if !target.is_empty_dir() => return Err(ENOTEMPTY),
+                _ => {}
+            }
+        }
+
+        old_dir.remove_child(&old_name);
+        new_dir.insert_child(new_name, node);
+        Ok(())
+    }
+
+    fn sync(&'static self) {}
+}
+
+impl TmpFileSystem {
+    pub fn new(mount_point: &str) -> Self {
+        Self {
+            mount_point: mount_point.to_string(),
+            root: TmpNode::new_dir(),
+        }
+    }
+
+    /// Create `path` and the missing directories above it
+    pub fn create_dir_all(&self, path: &str) -> Option<Arc<TmpNode>> {
+        let mut dir = self.root.clone();
+        for name in PathUtil::from_str(path).split() {
+            dir = match dir.child(name) {
+                Some(node) if node.is_dir() => node,
+                Some(_) => return None,
+                None => {
+                    let node = TmpNode::new_dir();
+                    dir.insert_child(name.to_string(), node.clone());
+                    node
+                }
+            };
+        }
+        Some(dir)
+    }
+
+    /// Create or replace a regular file holding `data`
+    pub fn create_file(&self, path: &str, data: &[u8]) -> Option<Arc<TmpNode>> {
+        let node = TmpNode::new_file();
+        if node.write_at(0, data) != data.len() {
+            return None;
+        }
+        self.insert(path, node.clone(), true).ok()?;
+        Some(node)
+    }
+
+    fn find(&self, path: &str) -> Option<Arc<TmpNode>> {
+        PathUtil::from_str(path)
+            .split()
+            .into_iter()
+            .try_fold(self.root.clone(), |dir, name| dir.child(name))
+    }
+
+    fn insert(&self, path: &str, node: Arc<TmpNode>, replace_file: bool) -> Result<(), ()> {
+        let path = PathUtil::from_str(path);
+        let name = path.name();
+        if name.is_empty() {
+            return Err(());
+        }
+        let parent = self.find(&path.parent()).ok_or(())?;
+        match parent.child(&name) {
+            Some(old) if !replace_file || old.is_dir() || node.is_dir() => Err(()),
+            _ => {
+                if parent.insert_child(name, node) {
+                    Ok(())
+                } else {
+                    Err(())
+                }
+            }
+        }
+    }
+}
+// region TmpFileSystem end
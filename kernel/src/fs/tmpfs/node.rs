@@ -0,0 +1,249 @@
+use crate::{
+    config::SV39_PAGE_SIZE,
+    fs::Fifo,
+    mm::{self, PpnTracker},
+    sync::{SpinLock, SpinLockGuard},
+    timer,
+};
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
+
+// region TmpNode begin
+pub struct TmpNode {
+    inner: SpinLock<TmpNodeInner>,
+}
+
+impl TmpNode {
+    pub fn new_dir() -> Arc<Self> {
+        Self::new(TmpNodeKind::Dir(BTreeMap::new()))
+    }
+
+    pub fn new_file() -> Arc<Self> {
+        Self::new(TmpNodeKind::File(TmpFileData {
+            pages: Vec::new(),
+            size: 0,
+        }))
+    }
+
+    pub fn new_fifo() -> Arc<Self> {
+        Self::new(TmpNodeKind::Fifo(Arc::new(Fifo::new())))
+    }
+
+    fn new(kind: TmpNodeKind) -> Arc<Self> {
+        let now = timer::get_wall_time().sec();
+        Arc::new(Self {
+            inner: SpinLock::new(
+                "TmpNode",
+                TmpNodeInner {
+                    kind,
+                    atime: now,
+                    mtime: now,
+                    ctime: now,
+                },
+            ),
+        })
+    }
+
+    pub fn inner(&self) -> SpinLockGuard<TmpNodeInner> {
+        self.inner.lock()
+    }
+}
+
+impl TmpNode {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.inner().kind, TmpNodeKind::Dir(_))
+    }
+
+    pub fn get_fifo(&self) -> Option<Arc<Fifo>> {
+        match self.inner().kind {
+            TmpNodeKind::Fifo(ref fifo) => Some(fifo.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self.inner().kind {
+            TmpNodeKind::File(ref data) => data.size,
+            _ => 0,
+        }
+    }
+
+    pub fn child(&self, name: &str) -> Option<Arc<TmpNode>> {
+        match self.inner().kind {
+            TmpNodeKind::Dir(ref children) => children.get(name).cloned(),
+            _ => None,
+        }
+    }
+
+    pub fn child_names(&self) -> Vec<String> {
+        match self.inner().kind {
+            TmpNodeKind::Dir(ref children) => children.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn insert_child(&self, name: String, node: Arc<TmpNode>) -> bool {
+        let mut inner = self.inner();
+        match inner.kind {
+            TmpNodeKind::Dir(ref mut children) => {
+                children.insert(name, node);
+                inner.touch();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove_child(&self, name: &str) -> Option<Arc<TmpNode>> {
+        let mut inner = self.inner();
+        let node = match inner.kind {
+            TmpNodeKind::Dir(ref mut children) => children.remove(name),
+            _ => None,
+        };
+        if node.is_some() {
+            inner.touch();
+        }
+        node
+    }
+
+    pub fn is_empty_dir(&self) -> bool {
+        match self.inner().kind {
+            TmpNodeKind::Dir(ref children) => children.is_empty(),
+            _ => false,
+        }
+    }
+
+    pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        let mut inner = self.inner();
+        inner.atime = timer::get_wall_time().sec();
+        match inner.kind {
+            TmpNodeKind::File(ref data) => data.read_at(offset, buf),
+            _ => 0,
+        }
+    }
+
+    pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
+        let mut inner = self.inner();
+        let written = match inner.kind {
+            TmpNodeKind::File(ref mut data) => data.write_at(offset, buf),
+            _ => return 0,
+        };
+        inner.touch();
+        written
+    }
+
+    pub fn truncate(&self, len: usize) -> bool {
+        let mut inner = self.inner();
+        let done = match inner.kind {
+            TmpNodeKind::File(ref mut data) => data.resize(len),
+            _ => return false,
+        };
+        inner.touch();
+        done
+    }
+}
+// region TmpNode end
+
+// region TmpNodeInner begin
+pub struct TmpNodeInner {
+    kind: TmpNodeKind,
+    pub atime: usize,
+    pub mtime: usize,
+    pub ctime: usize,
+}
+
+impl TmpNodeInner {
+    fn touch(&mut self) {
+        let now = timer::get_wall_time().sec();
+        self.mtime = now;
+        self.ctime = now;
+    }
+}
+// region TmpNodeInner end
+
+enum TmpNodeKind {
+    Dir(BTreeMap<String, Arc<TmpNode>>),
+    File(TmpFileData),
+    Fifo(Arc<Fifo>),
+}
+
+// region TmpFileData begin
+/// File content kept in physical frames, one frame per page
+struct TmpFileData {
+    pages: Vec<PpnTracker>,
+    size: usize,
+}
+
+impl TmpFileData {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        if offset >= self.size {
+            return 0;
+        }
+        let len = buf.len().min(self.size - offset);
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done;
+            let page = self.pages[pos / SV39_PAGE_SIZE].ppn().as_bytes_array();
+            let page_offset = pos % SV39_PAGE_SIZE;
+            let amount = (len - done).min(SV39_PAGE_SIZE - page_offset);
+            buf[done..done + amount].copy_from_slice(&page[page_offset..page_offset + amount]);
+            done += amount;
+        }
+        len
+    }
+
+    fn write_at(&mut self, offset: usize, buf: &[u8]) -> usize {
+        if offset > self.size {
+            // the hole reads as zeros
+            self.clear_tail();
+        }
+        if !self.reserve(offset + buf.len()) {
+            return 0;
+        }
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset + done;
+            let page = self.pages[pos / SV39_PAGE_SIZE].ppn().as_bytes_array();
+            let page_offset = pos % SV39_PAGE_SIZE;
+            let amount = (buf.len() - done).min(SV39_PAGE_SIZE - page_offset);
+            page[page_offset..page_offset + amount].copy_from_slice(&buf[done..done + amount]);
+            done += amount;
+        }
+        self.size = self.size.max(offset + buf.len());
+        buf.len()
+    }
+
+    fn resize(&mut self, len: usize) -> bool {
+        if len > self.size {
+            // new pages are zeroed, only clear the tail of the last page
+            self.clear_tail();
+            if !self.reserve(len) {
+                return false;
+            }
+        } else {
+            self.pages.truncate(len.div_ceil(SV39_PAGE_SIZE));
+        }
+        self.size = len;
+        true
+    }
+
+    fn reserve(&mut self, len: usize) -> bool {
+        while self.pages.len() * SV39_PAGE_SIZE < len {
+            match mm::alloc_ppn_tracker() {
+                Some(page) => self.pages.push(page),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn clear_tail(&mut self) {
+        let page_offset = self.size % SV39_PAGE_SIZE;
+        if page_offset != 0 {
+            let page = self.pages[self.size / SV39_PAGE_SIZE]
+                .ppn()
+                .as_bytes_array();
+            page[page_offset..].fill(0);
+        }
+    }
+}
+// region TmpFileData end
@@ -12,6 +12,7 @@ mod console;
 mod board;
 mod config;
 mod drivers;
+mod dtb;
 mod entry;
 mod fs;
 mod mm;
@@ -22,16 +23,34 @@ mod timer;
 mod trap;
 mod util;
 
-pub fn main() -> ! {
+use util::SchedPolicy;
+
+pub fn main(hart_id: usize, dtb_pa: usize) -> ! {
     print_logo();
     println!("[Kernel] Hello, world!");
+    println!("[Kernel] boot hart: {}, dtb: {:#x}", hart_id, dtb_pa);
     assert_eq!(*config::SKERNEL, 0xffff_ffff_c020_0000);
 
     util::init_log();
+    mm::init_heap();
+    board::init(dtb_pa);
+    init_cmdline();
     mm::init();
     trap::init_trap();
-    #[cfg(not(feature = "test"))]
+    timer::init();
+    let sched = util::get_cmdline()
+        .sched()
+        .unwrap_or(if cfg!(feature = "test") {
+            SchedPolicy::Fifo
+        } else {
+            SchedPolicy::RoundRobin
+        });
+    if sched == SchedPolicy::RoundRobin {
+        timer::enable_preemption();
+    }
+    // timer deadlines fire under every policy
     trap::enable_timer_interrupt();
+    fs::init();
     task::init();
     println!("[Kernel] initialized");
 
@@ -41,6 +60,15 @@ pub fn main() -> ! {
     run_test();
 }
 
+fn init_cmdline() {
+    util::init_cmdline(dtb::get_machine_info().bootargs());
+    let cmdline = util::get_cmdline();
+    println!("[Kernel] command line: {}", cmdline.raw());
+    if let Some(level) = cmdline.log_level() {
+        util::set_log_level(level);
+    }
+}
+
 fn os_start() -> ! {
     println!("[Kernel] current time: {}", timer::get_current_time());
     task::get_processor().run_tasks();
@@ -54,7 +82,7 @@ fn os_end() -> ! {
 
 #[cfg(feature = "test")]
 fn run_test() -> ! {
-    for test in [
+    const TESTS: &[&str] = &[
         "execve",
         "getcwd",
         "munmap",
@@ -87,7 +115,22 @@ fn run_test() -> ! {
         "openat",
         "dup",
         "unlink",
-    ] {
+    ];
+
+    // a manifest lists one test per line, `#` starts a comment
+    let manifest = util::get_cmdline().test_manifest().map(|path| {
+        let buf = fs::read_file(path).expect("Test manifest not found");
+        alloc::string::String::from_utf8(buf).expect("Test manifest is not UTF-8")
+    });
+    let tests: alloc::vec::Vec<&str> = match manifest {
+        Some(ref manifest) => manifest
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect(),
+        None => TESTS.to_vec(),
+    };
+    for test in tests {
         task::create_process(test);
     }
     task::get_processor().run_tasks();
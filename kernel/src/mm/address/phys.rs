@@ -12,8 +12,8 @@
 use simple_range::StepByOne;
 
 use crate::{
-    config::{PA_END, PA_START, SV39_PAGE_OFFSET, SV39_PAGE_SIZE},
-    mm::{PageTableEntry, SV39_PTE_BITS},
+    config::{SV39_PAGE_OFFSET, SV39_PAGE_SIZE},
+    mm::{self, PageTableEntry, SV39_PTE_BITS},
 };
 
 pub const SV39_PPN_BITS: usize = 44;
@@ -59,7 +59,7 @@ impl PhysPageNum {
     }
 
     fn modifiable(&self) -> bool {
-        *PA_START <= self.to_pa().0 && self.to_pa().0 < PA_END
+        mm::is_managed_ppn(*self)
     }
 
     pub fn as_bytes_array(&self) -> &'static mut [u8] {
@@ -0,0 +1,372 @@
+use crate::{
+    config::{ELF_DYN_BASE, SV39_PAGE_SIZE, USER_SPACE_END},
+    fs::{self, File, InodeType, OpenFlags},
+    mm::{MapArea, MapPermission, MapType, MemorySet, PpnOffset, VirtAddr, VirtPageNum},
+    syscall::errno::{EACCES, EIO, ENOENT, ENOEXEC},
+};
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+use log::trace;
+use xmas_elf::{
+    header::{self, Class, Data, Machine},
+    program::{ProgramHeader64, Type},
+    ElfFile,
+};
+
+// program headers further into the file are not looked for
+const ELF_HEADERS_MAX: usize = 0x10000;
+// longest PT_INTERP path, PATH_MAX
+const ELF_INTERP_MAX: usize = 4096;
+
+// region ElfReader begin
+/// An executable about to be loaded. Only its start is kept in memory, which
+/// holds the headers, the segments are read into the user frames they go to.
+pub struct ElfReader {
+    source: ElfSource,
+    size: usize,
+    head: Vec<u8>,
+}
+
+enum ElfSource {
+    File(Arc<dyn File + Send + Sync>),
+    #[cfg(feature = "embedded-initproc")]
+    Bytes(&'static [u8]),
+}
+
+impl ElfReader {
+    /// Err(ENOENT) if there is no such file, Err(EACCES) if it is not a regular one
+    pub fn open(path: &str) -> Result<Self, isize> {
+        let inode = fs::open_file(path, OpenFlags::RDONLY).ok_or(ENOENT)?;
+        if inode.get_type() != InodeType::File {
+            return Err(EACCES);
+        }
+        Ok(Self::new(ElfSource::File(inode.to_file()), inode.size()))
+    }
+
+    /// An executable built into the kernel
+    #[cfg(feature = "embedded-initproc")]
+    pub fn from_bytes(data: &'static [u8]) -> Self {
+        Self::new(ElfSource::Bytes(data), data.len())
+    }
+
+    fn new(source: ElfSource, size: usize) -> Self {
+        let mut reader = Self {
+            source,
+            size,
+            head: Vec::new(),
+        };
+        // the program headers mostly follow the ELF header in the first page
+        reader.head = reader.read_vec(0, size.min(SV39_PAGE_SIZE));
+        let ph_end = ElfFile::new(&reader.head).ok().and_then(|elf| {
+            let pt2 = &elf.header.pt2;
+            (pt2.ph_count() as usize)
+                .checked_mul(pt2.ph_entry_size() as usize)
+                .and_then(|ph_size| ph_size.checked_add(pt2.ph_offset() as usize))
+        });
+        if let Some(ph_end) = ph_end {
+            if reader.head.len() < ph_end && ph_end <= size.min(ELF_HEADERS_MAX) {
+                reader.head = reader.read_vec(0, ph_end);
+            }
+        }
+        reader
+    }
+
+    /// The start of the file, with all the headers if it is an ELF we can load
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        match &self.source {
+            ElfSource::File(file) => file.read_at(offset, buf),
+            #[cfg(feature = "embedded-initproc")]
+            ElfSource::Bytes(data) => {
+                let data = data.get(offset..).unwrap_or_default();
+                let len = buf.len().min(data.len());
+                buf[..len].copy_from_slice(&data[..len]);
+                len
+            }
+        }
+    }
+
+    fn read_vec(&self, offset: usize, len: usize) -> Vec<u8> {
+        let mut buf = vec![0; len];
+        let read = self.read_at(offset, &mut buf);
+        buf.truncate(read);
+        buf
+    }
+}
+// region ElfReader end
+
+/// The interpreter a dynamically linked ELF names in PT_INTERP, Err(ENOEXEC) if
+/// `elf` is not an ELF we can load
+pub fn get_elf_interp(elf: &ElfReader) -> Result<Option<String>, isize> {
+    let (elf_file, _) = check_elf(elf, ELF_DYN_BASE)?;
+    let Some(ph) = elf_file
+        .program_iter()
+        .find(|ph| ph.get_type() == Ok(Type::Interp))
+    else {
+        return Ok(None);
+    };
+    if ph.file_size() as usize > ELF_INTERP_MAX {
+        return Err(ENOEXEC);
+    }
+    // NUL terminated
+    let path = elf.read_vec(ph.offset() as usize, ph.file_size() as usize);
+    let path = core::str::from_utf8(&path).map_err(|_| ENOEXEC)?;
+    match path.strip_suffix('\0') {
+        Some(path) if !path.is_empty() => Ok(Some(path.to_string())),
+        _ => Err(ENOEXEC),
+    }
+}
+
+/// Check that `elf` is a RISC-V executable whose segments fit in user space when
+/// loaded at `dyn_base`, Err(ENOEXEC) otherwise. Also return its headers and the
+/// pages its PT_LOAD segments take up.
+pub(super) fn check_elf(
+    elf: &ElfReader,
+    dyn_base: usize,
+) -> Result<(ElfFile, Range<usize>), isize> {
+    // the magic is checked here, and that the header is all there
+    let elf_file = ElfFile::new(elf.head()).map_err(|_| ENOEXEC)?;
+    let pt1 = elf_file.header.pt1;
+    let pt2 = &elf_file.header.pt2;
+    if pt1.class() != Class::SixtyFour
+        || pt1.data() != Data::LittleEndian
+        || pt2.machine().as_machine() != Machine::RISC_V
+    {
+        return Err(ENOEXEC);
+    }
+    let bias = match pt2.type_().as_type() {
+        header::Type::Executable => 0,
+        header::Type::SharedObject => dyn_base,
+        _ => return Err(ENOEXEC),
+    };
+
+    // xmas_elf slices the program headers out of the head without checking
+    let ph_size = pt2.ph_entry_size() as usize;
+    let ph_end = (pt2.ph_count() as usize)
+        .checked_mul(ph_size)
+        .and_then(|size| size.checked_add(pt2.ph_offset() as usize));
+    if ph_size < size_of::<ProgramHeader64>() || ph_end.map_or(true, |end| end > elf.head().len()) {
+        return Err(ENOEXEC);
+    }
+
+    let mut pages: Option<Range<usize>> = None;
+    // where the last PT_LOAD segment ends, the next may share its last page
+    let mut load_end = 0;
+    for ph in elf_file.program_iter() {
+        let ph_type = ph.get_type().map_err(|_| ENOEXEC)?;
+        let offset = ph.offset() as usize;
+        let file_size = ph.file_size() as usize;
+        let mem_size = ph.mem_size() as usize;
+        if offset
+            .checked_add(file_size)
+            .map_or(true, |end| end > elf.size)
+        {
+            return Err(ENOEXEC);
+        }
+
+        match ph_type {
+            Type::Load => {
+                if file_size > mem_size {
+                    return Err(ENOEXEC);
+                }
+                // a segment is mapped page by page, so it has to sit in memory
+                // the way it sits in the file
+                let va = ph.virtual_addr() as usize;
+                let align = ph.align() as usize;
+                if align > 1 && (!align.is_power_of_two() || va % align != offset % align) {
+                    return Err(ENOEXEC);
+                }
+
+                let start = bias.checked_add(va).ok_or(ENOEXEC)?;
+                let end = start.checked_add(mem_size).ok_or(ENOEXEC)?;
+                if end > USER_SPACE_END {
+                    return Err(ENOEXEC);
+                }
+                // PT_LOAD segments come sorted by address and never overlap,
+                // though the boundary page may be shared, see map_elf
+                if pages.is_some() && start < load_end {
+                    return Err(ENOEXEC);
+                }
+                load_end = end;
+
+                let start = VirtAddr(start).to_vpn_floor().to_va().0;
+                let end = VirtAddr(end).to_vpn_ceil().to_va().0;
+                match &mut pages {
+                    Some(pages) => pages.end = end,
+                    None => pages = Some(start..end),
+                }
+            }
+            Type::Tls if file_size > mem_size => return Err(ENOEXEC),
+            _ => {}
+        }
+    }
+
+    // nothing to run without a PT_LOAD segment
+    let pages = pages.ok_or(ENOEXEC)?;
+    Ok((elf_file, pages))
+}
+
+// region ElfImage begin
+/// Where the segments of an ELF ended up
+pub(super) struct ElfImage {
+    /// added to every address in the ELF, 0 for ET_EXEC
+    pub bias: usize,
+    pub entry: usize,
+    /// the program headers in user memory, for AT_PHDR
+    pub phdr: usize,
+    /// the page after the highest segment
+    pub end: VirtPageNum,
+}
+// region ElfImage end
+
+impl MemorySet {
+    /// Map the PT_LOAD segments of `elf`, at `dyn_base` if it is ET_DYN.
+    /// `elf_file` holds its headers, see check_elf.
+    pub(super) fn map_elf(
+        &mut self,
+        elf: &ElfReader,
+        elf_file: &ElfFile,
+        dyn_base: usize,
+    ) -> Result<ElfImage, isize> {
+        let bias = match elf_file.header.pt2.type_().as_type() {
+            header::Type::SharedObject => dyn_base,
+            _ => 0,
+        };
+
+        // the pages of each area and their permission, a page shared by two
+        // segments gets an area of its own allowing what either of them does
+        let mut areas: Vec<(VirtPageNum, VirtPageNum, MapPermission)> = Vec::new();
+        let mut phdr = None;
+        for ph in elf_file.program_iter() {
+            // check_elf has made sure every header parses
+            match ph.get_type().unwrap() {
+                Type::Phdr => phdr = Some(bias + ph.virtual_addr() as usize),
+                Type::Load => {
+                    let mut map_perm = MapPermission::U;
+                    let ph_flags = ph.flags();
+                    if ph_flags.is_read() {
+                        map_perm |= MapPermission::R;
+                    }
+                    if ph_flags.is_write() {
+                        map_perm |= MapPermission::W;
+                    }
+                    if ph_flags.is_execute() {
+                        map_perm |= MapPermission::X;
+                    }
+
+                    let start_va = VirtAddr(bias + ph.virtual_addr() as usize);
+                    let end_va = VirtAddr(start_va.0 + ph.mem_size() as usize);
+                    trace!("MemorySet: map elf ph [{:#x}, {:#x})", start_va.0, end_va.0);
+                    let mut start = start_va.to_vpn_floor();
+                    let end = end_va.to_vpn_ceil();
+                    match areas.last_mut() {
+                        Some(last) if start < last.1 => {
+                            // only the last page of the previous segment
+                            let shared = start;
+                            let after = VirtPageNum(shared.0 + 1);
+                            if last.0 == shared {
+                                last.2 |= map_perm;
+                            } else {
+                                let perm = last.2 | map_perm;
+                                last.1 = shared;
+                                areas.push((shared, after, perm));
+                            }
+                            start = after;
+                        }
+                        _ => {}
+                    }
+                    if start < end {
+                        areas.push((start, end, map_perm));
+                    }
+
+                    // without PT_PHDR, the headers are found in the segment that
+                    // maps the start of the file
+                    let phoff = elf_file.header.pt2.ph_offset();
+                    if phdr.is_none()
+                        && ph.offset() <= phoff
+                        && phoff < ph.offset() + ph.file_size()
+                    {
+                        phdr = Some(start_va.0 + (phoff - ph.offset()) as usize);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut end = VirtPageNum(0);
+        for (start, area_end, map_perm) in areas {
+            let area = MapArea::new(start.to_va(), area_end.to_va(), MapType::Framed, map_perm);
+            end = end.max(area.vpn_range.end());
+            self.insert_area(area);
+        }
+        // the frames come zeroed, and segments never overlap, so a shared page
+        // takes the bytes of both
+        for ph in elf_file.program_iter() {
+            if ph.get_type().unwrap() == Type::Load {
+                // a segment starts anywhere in its first page
+                let start_va = VirtAddr(bias + ph.virtual_addr() as usize);
+                self.read_to_user(start_va, elf, ph.offset() as usize, ph.file_size() as usize)?;
+            }
+        }
+
+        Ok(ElfImage {
+            bias,
+            entry: bias + elf_file.header.pt2.entry_point() as usize,
+            phdr: phdr.unwrap_or(0),
+            end,
+        })
+    }
+
+    /// Copy `data` to user address `va`, the pages must be mapped
+    pub(super) fn copy_to_user(&self, va: VirtAddr, data: &[u8]) {
+        self.for_each_user_page(va, data.len(), |page, copied| {
+            page.copy_from_slice(&data[copied..copied + page.len()]);
+        });
+    }
+
+    /// Read `len` bytes at `offset` of `elf` straight into the frames mapped at
+    /// user address `va`. check_elf has made sure the file holds them, so a
+    /// short read is Err(EIO).
+    pub(super) fn read_to_user(
+        &self,
+        va: VirtAddr,
+        elf: &ElfReader,
+        offset: usize,
+        len: usize,
+    ) -> Result<(), isize> {
+        let mut short = false;
+        self.for_each_user_page(va, len, |page, copied| {
+            short |= elf.read_at(offset + copied, page) < page.len();
+        });
+        if short {
+            return Err(EIO);
+        }
+        Ok(())
+    }
+
+    // hand the part of each page in [va, va + len) to `f`, with how much came before
+    fn for_each_user_page(&self, va: VirtAddr, len: usize, mut f: impl FnMut(&mut [u8], usize)) {
+        let mut copied = 0;
+        while copied < len {
+            let va = VirtAddr(va.0 + copied);
+            let page = &mut self
+                .page_table
+                .translate(va.to_vpn_floor())
+                .unwrap()
+                .ppn()
+                .low_to_high()
+                .as_bytes_array()[va.page_offset()..];
+            let page_len = page.len().min(len - copied);
+            f(&mut page[..page_len], copied);
+            copied += page_len;
+        }
+    }
+}
@@ -1,19 +1,32 @@
+pub use elf::*;
 pub use map_area::*;
 
 use crate::{
+    board,
     config::{
-        EBSS, EDATA, ERODATA, ETEXT, KERNEL_STACK_SP, KERNEL_STACK_TOP, MMIO, PA_END, PA_START,
-        SBSS, SDATA, SRODATA, STEXT, SV39_PAGE_SIZE, TRAP_CX_PTR, USER_STACK_BOTTOM,
-        USER_STACK_TOP,
+        EBSS, EDATA, ELF_DYN_BASE, ELF_INTERP_BASE, ERODATA, ETEXT, KERNEL_STACK_SP,
+        KERNEL_STACK_TOP, PA_END, PA_START, SBSS, SDATA, SIGRETURN_TRAMPOLINE, SRODATA, STEXT,
+        SV39_PAGE_SIZE, TRAP_CX_PTR, USER_SPACE_END, USER_STACK_SP, USER_STACK_TOP,
     },
     mm::{PageTable, PageTableEntry, PpnOffset, VirtAddr, VirtPageNum},
+    syscall::errno::ENOEXEC,
 };
-use alloc::vec::Vec;
+#[cfg(feature = "stackful")]
+use crate::{config::KERNEL_STACK_REGION, mm::get_kernel_space};
+use alloc::{string::String, vec::Vec};
 use core::arch::asm;
 use log::{trace, warn};
 use riscv::register::satp;
+use user_stack::*;
+use xmas_elf::program::Type;
 
+mod elf;
 mod map_area;
+mod user_stack;
+
+// li a7, 139 (rt_sigreturn); ecall
+const SIGRETURN_CODE: [u8; 8] = [0x93, 0x08, 0xb0, 0x08, 0x73, 0x00, 0x00, 0x00];
+const PT_GNU_STACK: Type = Type::OsSpecific(0x6474_e551);
 
 pub trait MemorySpace {
     fn activate(&self);
@@ -21,6 +34,21 @@ pub trait MemorySpace {
     fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry>;
 }
 
+// region ElfInfo begin
+/// How a freshly loaded user memory set is entered
+pub struct ElfInfo {
+    /// the interpreter's entry if there is one
+    pub entry: usize,
+    /// argc is at sp, see init_user_stack
+    pub sp: usize,
+    pub brk: usize,
+    /// where the heap has to stop, the interpreter may sit above it
+    pub brk_limit: usize,
+    /// the initial TLS block, 0 without PT_TLS
+    pub tp: usize,
+}
+// region ElfInfo end
+
 // region MemorySet begin
 pub struct MemorySet {
     page_table: PageTable,
@@ -134,15 +162,33 @@ impl MemorySet {
         ));
 
         // map ppn range
-        trace!("MemorySet: ppn [{:#x}, {:#x})", *PA_START, PA_END);
+        let pa_end = PA_END.min(board::memory_end());
+        trace!("MemorySet: ppn [{:#x}, {:#x})", *PA_START, pa_end);
         self.insert_area(MapArea::new(
             VirtAddr(*PA_START),
-            VirtAddr(PA_END),
+            VirtAddr(pa_end),
             MapType::Direct,
             MapPermission::R | MapPermission::W,
         ));
 
-        // map kernel stack
+        // map ppn ranges beyond the kernel layout
+        for (start, end) in board::extra_memory() {
+            trace!("MemorySet: ppn [{:#x}, {:#x})", start, end);
+            self.insert_area(MapArea::new(
+                VirtAddr(start),
+                VirtAddr(end),
+                MapType::Direct,
+                MapPermission::R | MapPermission::W,
+            ));
+        }
+
+        // map kernel stack, every task traps onto it in the stackless build
+        #[cfg(not(feature = "stackful"))]
+        assert!(
+            KERNEL_STACK_SP <= board::memory_end(),
+            "MemorySet: RAM ends at {:#x}, below the kernel stack",
+            board::memory_end()
+        );
         trace!(
             "MemorySet: kernel stack [{:#x}, {:#x})",
             KERNEL_STACK_TOP,
@@ -156,7 +202,7 @@ impl MemorySet {
         ));
 
         // map MMIO
-        for &pair in MMIO {
+        for pair in board::mmio() {
             trace!("MemorySet: MMIO [{:#x}, {:#x})", pair.0, pair.0 + pair.1);
             self.insert_area(MapArea::new(
                 VirtAddr(pair.0),
@@ -174,49 +220,124 @@ impl MemorySet {
     }
 }
 
+// Kernel Stack
+#[cfg(feature = "stackful")]
+impl MemorySet {
+    pub fn insert_kernel_stack(&mut self, start_va: VirtAddr, end_va: VirtAddr) {
+        trace!(
+            "MemorySet: map kernel stack [{:#x}, {:#x})",
+            start_va.0,
+            end_va.0
+        );
+        self.insert_area(MapArea::new(
+            start_va,
+            end_va,
+            MapType::Framed,
+            MapPermission::R | MapPermission::W,
+        ));
+    }
+
+    pub fn remove_kernel_stack(&mut self, start_va: VirtAddr) {
+        if let Some(idx) = self
+            .areas
+            .iter()
+            .position(|area| area.vpn_range.start() == start_va.to_vpn_floor())
+        {
+            let mut area = self.areas.remove(idx);
+            area.unmap_all(&mut self.page_table);
+        }
+    }
+
+    // kernel stacks are mapped in KernelSpace only, user spaces see them through
+    // the shared root entry
+    fn share_kernel_stacks(&mut self) {
+        let vpn = VirtAddr(KERNEL_STACK_REGION).to_vpn();
+        self.page_table
+            .share_root_entry(vpn, &mut get_kernel_space().inner().page_table);
+    }
+}
+
 // User Space
 impl MemorySet {
-    // return MemorySet for user space, elf entry, base size
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
-        use xmas_elf::{program::Type, ElfFile};
+    /// Return the user memory set and where to start it. With `interp_data`, the
+    /// interpreter is loaded too and entered first. Err(ENOEXEC) if either is not
+    /// an ELF we can load, Err(EIO) if it cannot be read.
+    pub fn from_elf(
+        elf: &ElfReader,
+        interp: Option<&ElfReader>,
+        args: &[String],
+        envs: &[String],
+    ) -> Result<(Self, ElfInfo), isize> {
+        // check everything before a page is mapped
+        let (elf_file, pages) = check_elf(elf, ELF_DYN_BASE)?;
+        let tls = elf_file
+            .program_iter()
+            .find(|ph| ph.get_type() == Ok(Type::Tls))
+            .filter(|ph| ph.mem_size() > 0);
+        // the TLS block goes right after the last segment
+        let program_end = match tls {
+            Some(ph) => pages
+                .end
+                .checked_add(ph.mem_size() as usize)
+                .filter(|&end| end <= USER_SPACE_END)
+                .ok_or(ENOEXEC)?,
+            None => pages.end,
+        };
+        // the program break grows up to the interpreter
+        let mut brk_limit = USER_SPACE_END;
+        let interp = match interp {
+            Some(interp) => {
+                let (interp_file, interp_pages) = check_elf(interp, ELF_INTERP_BASE)?;
+                // an ET_EXEC program may well sit where the interpreter goes
+                if interp_pages.start < program_end && pages.start < interp_pages.end {
+                    return Err(ENOEXEC);
+                }
+                if interp_pages.start >= program_end {
+                    brk_limit = interp_pages.start;
+                }
+                Some((interp, interp_file))
+            }
+            None => None,
+        };
+
         let mut memory_set = Self::empty();
 
         // map kernel space
         memory_set.map_kernel_space();
+        #[cfg(feature = "stackful")]
+        memory_set.share_kernel_stacks();
 
         // handle elf
-        let elf = ElfFile::new(elf_data).unwrap();
-        let elf_header = elf.header;
-
-        // map elf program headers
-        let mut max_vpn = VirtPageNum(0);
-        let ph_count = elf_header.pt2.ph_count();
-        for i in 0..ph_count {
-            let ph = elf.program_header(i).unwrap();
-            if ph.get_type().unwrap() == Type::Load {
-                // read permission
-                let mut map_perm = MapPermission::U;
-                let ph_flags = ph.flags();
-                if ph_flags.is_read() {
-                    map_perm |= MapPermission::R;
-                }
-                if ph_flags.is_write() {
-                    map_perm |= MapPermission::W;
-                }
-                if ph_flags.is_execute() {
-                    map_perm |= MapPermission::X;
-                }
-
-                // map program header
-                let start_va = VirtAddr(ph.virtual_addr() as usize);
-                let end_va = VirtAddr(ph.virtual_addr() as usize + ph.mem_size() as usize);
-                trace!("MemorySet: map elf ph [{:#x}, {:#x})", start_va.0, end_va.0);
-                let area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
-                max_vpn = area.vpn_range.end();
-                let elf_data =
-                    &elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize];
-                memory_set.insert_area_with_data(area, elf_data);
-            }
+        let image = memory_set.map_elf(elf, &elf_file, ELF_DYN_BASE)?;
+        let mut max_vpn = image.end;
+
+        // the interpreter relocates the program and jumps to AT_ENTRY
+        let interp = interp
+            .map(|(interp, interp_file)| memory_set.map_elf(interp, &interp_file, ELF_INTERP_BASE))
+            .transpose()?;
+
+        // map the initial TLS block, .tdata then the zeroed .tbss
+        // tp points at its start, variant I of the RISC-V ELF psABI
+        let mut tp = 0;
+        if let Some(ph) = tls {
+            let start_va = max_vpn.to_va();
+            let end_va = VirtAddr(start_va.0 + ph.mem_size() as usize);
+            trace!("MemorySet: map TLS [{:#x}, {:#x})", start_va.0, end_va.0);
+            let area = MapArea::new(
+                start_va,
+                end_va,
+                MapType::Framed,
+                MapPermission::U | MapPermission::R | MapPermission::W,
+            );
+            max_vpn = area.vpn_range.end();
+            memory_set.insert_area(area);
+            memory_set.read_to_user(
+                start_va,
+                elf,
+                ph.offset() as usize,
+                ph.file_size() as usize,
+            )?;
+            tp = start_va.0;
         }
 
         // map User Heap
@@ -229,19 +350,42 @@ impl MemorySet {
             MapPermission::U | MapPermission::R | MapPermission::W,
         ));
 
-        // map User Stack
+        // map User Stack, executable only if PT_GNU_STACK asks for it
         trace!(
             "MemorySet: map User Stack [{:#x}, {:#x})",
             USER_STACK_TOP,
-            USER_STACK_BOTTOM
+            USER_STACK_SP
         );
+        let mut stack_perm = MapPermission::U | MapPermission::R | MapPermission::W;
+        if elf_file
+            .program_iter()
+            .any(|ph| ph.get_type() == Ok(PT_GNU_STACK) && ph.flags().is_execute())
+        {
+            stack_perm |= MapPermission::X;
+        }
         memory_set.insert_area(MapArea::new(
             VirtAddr(USER_STACK_TOP),
-            VirtAddr(USER_STACK_BOTTOM),
+            VirtAddr(USER_STACK_SP),
             MapType::Framed,
-            MapPermission::U | MapPermission::R | MapPermission::W,
+            stack_perm,
         ));
 
+        // map Sigreturn Trampoline
+        trace!(
+            "MemorySet: map Sigreturn Trampoline [{:#x}, {:#x})",
+            SIGRETURN_TRAMPOLINE,
+            SIGRETURN_TRAMPOLINE + SV39_PAGE_SIZE
+        );
+        memory_set.insert_area_with_data(
+            MapArea::new(
+                VirtAddr(SIGRETURN_TRAMPOLINE),
+                VirtAddr(SIGRETURN_TRAMPOLINE + SV39_PAGE_SIZE),
+                MapType::Framed,
+                MapPermission::U | MapPermission::R | MapPermission::X,
+            ),
+            &SIGRETURN_CODE,
+        );
+
         // map Trap Context
         trace!(
             "MemorySet: map TrapContext [{:#x}, {:#x})",
@@ -255,15 +399,39 @@ impl MemorySet {
             MapPermission::R | MapPermission::W,
         ));
 
-        (
-            memory_set,
-            elf_header.pt2.entry_point() as usize,
-            program_brk_va.0,
-        )
+        // what the program and its interpreter learn from the kernel
+        let auxv = [
+            (AT_PHDR, image.phdr),
+            (AT_PHENT, elf_file.header.pt2.ph_entry_size() as usize),
+            (AT_PHNUM, elf_file.header.pt2.ph_count() as usize),
+            (AT_PAGESZ, SV39_PAGE_SIZE),
+            (AT_BASE, interp.as_ref().map_or(0, |interp| interp.bias)),
+            (AT_FLAGS, 0),
+            (AT_ENTRY, image.entry),
+            (AT_UID, 0),
+            (AT_EUID, 0),
+            (AT_GID, 0),
+            (AT_EGID, 0),
+            // times() counts in timer ticks
+            (AT_CLKTCK, board::clock_freq()),
+            (AT_SECURE, 0),
+        ];
+        let sp = memory_set.init_user_stack(USER_STACK_SP, args, envs, &auxv);
+
+        let info = ElfInfo {
+            entry: interp.map_or(image.entry, |interp| interp.entry),
+            sp,
+            brk: program_brk_va.0,
+            brk_limit,
+            tp,
+        };
+        Ok((memory_set, info))
     }
 
     pub fn from_another(another: &Self) -> Self {
         let mut memory_set = Self::empty();
+        #[cfg(feature = "stackful")]
+        memory_set.share_kernel_stacks();
 
         // copy areas
         for area in another.areas.iter() {
@@ -0,0 +1,87 @@
+use crate::{
+    mm::{MemorySet, VirtAddr},
+    timer,
+};
+use alloc::{string::String, vec::Vec};
+
+// auxiliary vector keys, see getauxval(3)
+pub(super) const AT_NULL: usize = 0;
+pub(super) const AT_PHDR: usize = 3;
+pub(super) const AT_PHENT: usize = 4;
+pub(super) const AT_PHNUM: usize = 5;
+pub(super) const AT_PAGESZ: usize = 6;
+pub(super) const AT_BASE: usize = 7;
+pub(super) const AT_FLAGS: usize = 8;
+pub(super) const AT_ENTRY: usize = 9;
+pub(super) const AT_UID: usize = 11;
+pub(super) const AT_EUID: usize = 12;
+pub(super) const AT_GID: usize = 13;
+pub(super) const AT_EGID: usize = 14;
+pub(super) const AT_CLKTCK: usize = 17;
+pub(super) const AT_SECURE: usize = 23;
+pub(super) const AT_RANDOM: usize = 25;
+
+impl MemorySet {
+    /// Lay out argc, argv, envp and `auxv` below `sp` the way the ELF psABI has
+    /// them at process entry, return the new sp. AT_RANDOM and AT_NULL are added.
+    pub(super) fn init_user_stack(
+        &self,
+        sp: usize,
+        args: &[String],
+        envs: &[String],
+        auxv: &[(usize, usize)],
+    ) -> usize {
+        let mut sp = sp;
+
+        // the strings and the random bytes sit at the top
+        let arg_ptrs: Vec<usize> = args.iter().map(|arg| self.push_str(&mut sp, arg)).collect();
+        let env_ptrs: Vec<usize> = envs.iter().map(|env| self.push_str(&mut sp, env)).collect();
+        // no entropy source, musl and glibc take their canaries from it anyway
+        let mut seed = timer::get_current_tick() as u64 | 1;
+        let random: Vec<u8> = (0..16)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect();
+        sp -= random.len();
+        self.copy_to_user(VirtAddr(sp), &random);
+        let random_ptr = sp;
+
+        // argc, argv, NULL, envp, NULL, then the auxv pairs
+        let mut table = Vec::new();
+        table.push(args.len());
+        table.extend(arg_ptrs);
+        table.push(0);
+        table.extend(env_ptrs);
+        table.push(0);
+        for &(key, value) in auxv
+            .iter()
+            .chain([(AT_RANDOM, random_ptr), (AT_NULL, 0)].iter())
+        {
+            table.push(key);
+            table.push(value);
+        }
+
+        // sp is 16-byte aligned at entry
+        sp = (sp - table.len() * size_of::<usize>()) & !0xf;
+        let bytes = unsafe {
+            core::slice::from_raw_parts(
+                table.as_ptr() as *const u8,
+                table.len() * size_of::<usize>(),
+            )
+        };
+        self.copy_to_user(VirtAddr(sp), bytes);
+        sp
+    }
+
+    // push `s` with its NUL, return where it went
+    fn push_str(&self, sp: &mut usize, s: &str) -> usize {
+        *sp -= s.len() + 1;
+        self.copy_to_user(VirtAddr(*sp), s.as_bytes());
+        self.copy_to_user(VirtAddr(*sp + s.len()), &[0]);
+        *sp
+    }
+}
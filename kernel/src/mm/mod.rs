@@ -11,8 +11,11 @@ mod page_table;
 mod ppn_allocator;
 mod space;
 
-pub fn init() {
+pub fn init_heap() {
     heap_allocator::init_heap();
+}
+
+pub fn init() {
     get_kernel_space().activate();
 }
 
@@ -92,5 +92,20 @@ impl PageTable {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.get_pte(vpn).map(|pte| *pte)
     }
+
+    /// Point the root entry covering `vpn` at the subtree of `owner`, creating it if
+    /// absent. Later mappings under it are seen by both page tables, and the subtree
+    /// stays owned by `owner`.
+    #[cfg(feature = "stackful")]
+    pub fn share_root_entry(&mut self, vpn: VirtPageNum, owner: &mut PageTable) {
+        let idx = vpn.indexes()[0];
+        let owner_pte = &mut owner.root_ppn.as_pte_array()[idx];
+        if !owner_pte.is_valid() {
+            let ppn_tracker = alloc_ppn_tracker().unwrap();
+            *owner_pte = PageTableEntry::new(ppn_tracker.ppn().high_to_low(), PTEFlags::V);
+            owner.ppn_tracker_list.push(ppn_tracker);
+        }
+        self.root_ppn.as_pte_array()[idx] = *owner_pte;
+    }
 }
 // region PageTable end
@@ -2,9 +2,10 @@ pub use ppn_offset::*;
 pub use ppn_tracker::*;
 
 use crate::{
+    board,
     config::{PA_END, PA_START},
     mm::{PhysAddr, PhysPageNum},
-    sync::UPSafeCell,
+    sync::SpinNoIrqLock,
 };
 use alloc::vec::Vec;
 use lazy_static::lazy_static;
@@ -24,6 +25,16 @@ pub fn alloc_contiguous_ppn_tracker(count: usize) -> Option<Vec<PpnTracker>> {
         .map(|ppns| ppns.into_iter().map(PpnTracker::new).collect())
 }
 
+/// Whether the ppn belongs to the RAM handed out by the allocator
+pub fn is_managed_ppn(ppn: PhysPageNum) -> bool {
+    PPN_ALLOCATOR.manages(ppn)
+}
+
+/// Hand the pages of RAM reserved at boot over to the allocator once it is free
+pub fn add_ppn_range(pa_begin: PhysAddr, pa_end: PhysAddr) {
+    PPN_ALLOCATOR.add_range(pa_begin, pa_end);
+}
+
 pub fn dealloc_ppn(ppn: PhysPageNum) {
     PPN_ALLOCATOR.dealloc(ppn);
 }
@@ -35,65 +46,115 @@ pub fn dealloc_contiguous_ppn(start: PhysPageNum, count: usize) {
 }
 
 lazy_static! {
-    static ref PPN_ALLOCATOR: PpnAllocator =
-        PpnAllocator::new(PhysAddr(*PA_START), PhysAddr(PA_END));
+    static ref PPN_ALLOCATOR: PpnAllocator = {
+        let pa_end = PA_END.min(board::memory_end());
+        let mut pa_ranges = Vec::from([(PhysAddr(*PA_START), PhysAddr(pa_end))]);
+        pa_ranges.extend(
+            board::extra_memory()
+                .into_iter()
+                .map(|(start, end)| (PhysAddr(start), PhysAddr(end))),
+        );
+        // keep the initrd away from the allocator until it is unpacked, then
+        // `add_ppn_range` gives its pages back
+        if let Some((start, end)) = board::initrd() {
+            pa_ranges = exclude_range(pa_ranges, (PhysAddr(start), PhysAddr(end)));
+        }
+        PpnAllocator::new(pa_ranges)
+    };
+}
+
+fn exclude_range(
+    pa_ranges: Vec<(PhysAddr, PhysAddr)>,
+    (hole_begin, hole_end): (PhysAddr, PhysAddr),
+) -> Vec<(PhysAddr, PhysAddr)> {
+    let mut ranges = Vec::new();
+    for (pa_begin, pa_end) in pa_ranges {
+        if hole_end.0 <= pa_begin.0 || pa_end.0 <= hole_begin.0 {
+            ranges.push((pa_begin, pa_end));
+            continue;
+        }
+        if pa_begin.0 < hole_begin.0 {
+            ranges.push((pa_begin, hole_begin));
+        }
+        if hole_end.0 < pa_end.0 {
+            ranges.push((hole_end, pa_end));
+        }
+    }
+    ranges
 }
 
 // region PpnAllocator begin
 struct PpnAllocator {
-    inner: UPSafeCell<PpnAllocatorInner>,
+    inner: SpinNoIrqLock<PpnAllocatorInner>,
 }
 
 impl PpnAllocator {
-    fn new(pa_begin: PhysAddr, pa_end: PhysAddr) -> Self {
-        let start_ppn = pa_begin.to_ppn_ceil();
-        let end_ppn = pa_end.to_ppn_floor();
-        trace!("PpnAllocator: PA  [{:#x}, {:#x})", pa_begin.0, pa_end.0);
-        trace!("PpnAllocator: PPN [{:#x}, {:#x})", start_ppn.0, end_ppn.0);
+    fn new(pa_ranges: Vec<(PhysAddr, PhysAddr)>) -> Self {
+        let ppn_ranges = pa_ranges
+            .into_iter()
+            .map(|(pa_begin, pa_end)| PpnRange::new(pa_begin, pa_end))
+            .collect();
         Self {
-            inner: unsafe {
-                UPSafeCell::new(PpnAllocatorInner {
-                    ppn_range: SimpleRange::new(start_ppn, end_ppn),
+            inner: SpinNoIrqLock::new(
+                "PpnAllocator",
+                PpnAllocatorInner {
+                    ppn_ranges,
                     recycled_ppn: Vec::new(),
-                })
-            },
+                },
+            ),
         }
     }
 
+    fn add_range(&self, pa_begin: PhysAddr, pa_end: PhysAddr) {
+        let ppn_range = PpnRange::new(pa_begin, pa_end);
+        self.inner.lock().ppn_ranges.push(ppn_range);
+    }
+
     fn contains(&self, ppn: PhysPageNum) -> bool {
-        self.inner.shared_access().contains(ppn)
+        self.inner.lock().contains(ppn)
+    }
+
+    fn manages(&self, ppn: PhysPageNum) -> bool {
+        self.inner
+            .lock()
+            .ppn_ranges
+            .iter()
+            .any(|ppn_range| ppn_range.begin <= ppn && ppn < ppn_range.range.end())
     }
 
     fn alloc(&self) -> Option<PhysPageNum> {
-        let mut inner = self.inner.exclusive_access();
+        let mut inner = self.inner.lock();
         if let Some(ppn) = inner.recycled_ppn.pop() {
             return Some(ppn);
         }
-        if inner.ppn_range.start() == inner.ppn_range.end() {
-            return None;
-        }
 
-        let ppn = inner.ppn_range.start();
-        inner.ppn_range.get_start_mut().step();
+        let ppn_range = inner
+            .ppn_ranges
+            .iter_mut()
+            .map(|ppn_range| &mut ppn_range.range)
+            .find(|range| range.start() != range.end())?;
+        let ppn = ppn_range.start();
+        ppn_range.get_start_mut().step();
         Some(ppn)
     }
 
     fn alloc_contiguous(&self, count: usize) -> Option<Vec<PhysPageNum>> {
-        let mut inner = self.inner.exclusive_access();
-        if inner.ppn_range.start().0 + count >= inner.ppn_range.end().0 {
-            None
-        } else {
-            {
-                let start = inner.ppn_range.start();
-                *inner.ppn_range.get_start_mut() = PhysPageNum(start.0 + count);
-            }
-            let arr: Vec<usize> = (1..count + 1).collect();
-            let v = arr
-                .iter()
-                .map(|x| PhysPageNum(inner.ppn_range.start().0 - x))
-                .collect();
-            Some(v)
+        let mut inner = self.inner.lock();
+        let ppn_range = inner
+            .ppn_ranges
+            .iter_mut()
+            .map(|ppn_range| &mut ppn_range.range)
+            .find(|range| range.start().0 + count < range.end().0)?;
+        {
+            let start = ppn_range.start();
+            *ppn_range.get_start_mut() = PhysPageNum(start.0 + count);
         }
+        let arr: Vec<usize> = (1..count + 1).collect();
+        let v = arr
+            .iter()
+            .map(|x| PhysPageNum(ppn_range.start().0 - x))
+            .collect();
+        Some(v)
     }
 
     fn dealloc(&self, ppn: PhysPageNum) {
@@ -101,7 +162,7 @@ impl PpnAllocator {
             self.contains(ppn),
             "PpnAllocator: dealloc an unallocated ppn"
         );
-        let mut inner = self.inner.exclusive_access();
+        let mut inner = self.inner.lock();
         inner.recycled_ppn.push(ppn);
     }
 }
@@ -109,13 +170,38 @@ impl PpnAllocator {
 
 // region PpnAllocatorInner begin
 struct PpnAllocatorInner {
-    ppn_range: SimpleRange<PhysPageNum>,
+    ppn_ranges: Vec<PpnRange>,
     recycled_ppn: Vec<PhysPageNum>,
 }
 
 impl PpnAllocatorInner {
     fn contains(&self, ppn: PhysPageNum) -> bool {
-        ppn < self.ppn_range.start() && !self.recycled_ppn.contains(&ppn)
+        self.ppn_ranges
+            .iter()
+            .any(|ppn_range| ppn_range.begin <= ppn && ppn < ppn_range.range.start())
+            && !self.recycled_ppn.contains(&ppn)
     }
 }
 // region PpnAllocatorInner end
+
+// region PpnRange begin
+struct PpnRange {
+    // where the range begins, allocated ppns are in [begin, range.start)
+    begin: PhysPageNum,
+    range: SimpleRange<PhysPageNum>,
+}
+
+impl PpnRange {
+    // only the whole pages in [pa_begin, pa_end)
+    fn new(pa_begin: PhysAddr, pa_end: PhysAddr) -> Self {
+        let start_ppn = pa_begin.to_ppn_ceil();
+        let end_ppn = pa_end.to_ppn_floor();
+        trace!("PpnAllocator: PA  [{:#x}, {:#x})", pa_begin.0, pa_end.0);
+        trace!("PpnAllocator: PPN [{:#x}, {:#x})", start_ppn.0, end_ppn.0);
+        Self {
+            begin: start_ppn,
+            range: SimpleRange::new(start_ppn, end_ppn),
+        }
+    }
+}
+// region PpnRange end
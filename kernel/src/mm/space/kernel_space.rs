@@ -1,8 +1,7 @@
 use crate::{
     mm::{MemorySet, MemorySpace, PageTableEntry, VirtPageNum},
-    sync::UPSafeCell,
+    sync::{SpinNoIrqLock, SpinNoIrqLockGuard},
 };
-use core::cell::RefMut;
 use lazy_static::lazy_static;
 
 pub fn get_kernel_space() -> &'static KernelSpace {
@@ -15,32 +14,32 @@ lazy_static! {
 
 // region KernelSpace begin
 pub struct KernelSpace {
-    inner: UPSafeCell<KernelSpaceInner>,
+    inner: SpinNoIrqLock<KernelSpaceInner>,
 }
 
 impl MemorySpace for KernelSpace {
     fn activate(&self) {
-        self.inner_mut().activate();
+        self.inner().activate();
     }
 
     fn get_satp(&self) -> usize {
-        self.inner_mut().get_satp()
+        self.inner().get_satp()
     }
 
     fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.inner_mut().translate(vpn)
+        self.inner().translate(vpn)
     }
 }
 
 impl KernelSpace {
     fn new() -> Self {
         KernelSpace {
-            inner: unsafe { UPSafeCell::new(KernelSpaceInner::new_kernel()) },
+            inner: SpinNoIrqLock::new("KernelSpace", KernelSpaceInner::new_kernel()),
         }
     }
 
-    pub fn inner_mut(&self) -> RefMut<KernelSpaceInner> {
-        self.inner.exclusive_access()
+    pub fn inner(&self) -> SpinNoIrqLockGuard<KernelSpaceInner> {
+        self.inner.lock()
     }
 }
 // region KernelSpace end
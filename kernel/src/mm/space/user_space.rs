@@ -1,14 +1,17 @@
 use crate::{
-    mm::{MemorySet, MemorySpace, PageTableEntry, VirtPageNum},
-    sync::UPSafeCell,
+    mm::{ElfReader, MemorySet, MemorySpace, PageTableEntry, VirtPageNum},
+    sync::{SpinNoIrqLock, SpinNoIrqLockGuard},
 };
-use core::cell::{Ref, RefMut};
+use alloc::string::String;
 
 // region UserSpace begin
 pub struct UserSpace {
     entry: usize,
+    sp: usize,
     base_size: usize,
-    inner: UPSafeCell<UserSpaceInner>,
+    brk_limit: usize,
+    tp: usize,
+    inner: SpinNoIrqLock<UserSpaceInner>,
 }
 
 impl MemorySpace for UserSpace {
@@ -17,38 +20,50 @@ impl MemorySpace for UserSpace {
     }
 
     fn get_satp(&self) -> usize {
-        self.inner_mut().get_satp()
+        self.inner().get_satp()
     }
 
     fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.inner_mut().translate(vpn)
+        self.inner().translate(vpn)
     }
 }
 
 impl UserSpace {
-    pub fn from_elf(elf_data: &[u8]) -> Self {
-        let (space, entry, base_size) = UserSpaceInner::from_elf(elf_data);
-        Self {
-            entry,
-            base_size,
-            inner: unsafe { UPSafeCell::new(space) },
-        }
+    /// Err(ENOEXEC) if `elf` or `interp` is not an ELF we can load, Err(EIO) if
+    /// either cannot be read
+    pub fn from_elf(
+        elf: &ElfReader,
+        interp: Option<&ElfReader>,
+        args: &[String],
+        envs: &[String],
+    ) -> Result<Self, isize> {
+        let (space, info) = UserSpaceInner::from_elf(elf, interp, args, envs)?;
+        Ok(Self {
+            entry: info.entry,
+            sp: info.sp,
+            base_size: info.brk,
+            brk_limit: info.brk_limit,
+            tp: info.tp,
+            inner: SpinNoIrqLock::new("UserSpace", space),
+        })
     }
 
     pub fn from_existed(user_space: &Self) -> Self {
         Self {
             entry: user_space.get_entry(),
+            sp: user_space.get_sp(),
             base_size: user_space.get_base_size(),
-            inner: unsafe { UPSafeCell::new(UserSpaceInner::from_another(&user_space.inner())) },
+            brk_limit: user_space.get_brk_limit(),
+            tp: user_space.get_tp(),
+            inner: SpinNoIrqLock::new(
+                "UserSpace",
+                UserSpaceInner::from_another(&user_space.inner()),
+            ),
         }
     }
 
-    pub fn inner(&self) -> Ref<UserSpaceInner> {
-        self.inner.shared_access()
-    }
-
-    pub fn inner_mut(&self) -> RefMut<UserSpaceInner> {
-        self.inner.exclusive_access()
+    pub fn inner(&self) -> SpinNoIrqLockGuard<UserSpaceInner> {
+        self.inner.lock()
     }
 }
 
@@ -57,9 +72,24 @@ impl UserSpace {
         self.entry
     }
 
+    /// The initial stack pointer, argc is there
+    pub fn get_sp(&self) -> usize {
+        self.sp
+    }
+
     pub fn get_base_size(&self) -> usize {
         self.base_size
     }
+
+    /// The program break may not go beyond it
+    pub fn get_brk_limit(&self) -> usize {
+        self.brk_limit
+    }
+
+    /// The initial thread pointer, 0 without a PT_TLS segment
+    pub fn get_tp(&self) -> usize {
+        self.tp
+    }
 }
 // region UserSpace end
 
@@ -0,0 +1,222 @@
+use crate::{
+    mm::VirtAddr,
+    sync::SpinNoIrqLock,
+    syscall::errno::{EAGAIN, EFAULT, EINVAL},
+    task::ProcessControlBlock,
+};
+use alloc::{
+    collections::{btree_map::BTreeMap, VecDeque},
+    vec::Vec,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+pub const FUTEX_BITSET_MATCH_ANY: u32 = u32::MAX;
+
+static FUTEX_TABLE: SpinNoIrqLock<FutexTable> = SpinNoIrqLock::new("FutexTable", FutexTable::new());
+
+// region FutexKey begin
+/// What the waiters on a futex word are queued by, see futex(2)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FutexKey {
+    /// FUTEX_PRIVATE_FLAG, the word is only used within its process
+    Private { pid: usize, addr: usize },
+    /// a page shared by processes has the same physical address in all of them
+    Shared { pa: usize },
+}
+
+impl FutexKey {
+    /// Key of the u32 at user address `addr` of `pcb`, Err carries the errno
+    pub fn new(pcb: &ProcessControlBlock, addr: usize, private: bool) -> Result<Self, isize> {
+        if addr % size_of::<u32>() != 0 {
+            return Err(EINVAL);
+        }
+        let pa = pcb.translate(VirtAddr(addr)).ok_or(EFAULT)?;
+        if private {
+            Ok(Self::Private {
+                pid: pcb.get_pid(),
+                addr,
+            })
+        } else {
+            Ok(Self::Shared { pa: pa.0 })
+        }
+    }
+}
+// region FutexKey end
+
+// region FutexTable begin
+struct FutexWaiter {
+    id: usize,
+    bitset: u32,
+    waker: Waker,
+}
+
+struct FutexTable {
+    queues: BTreeMap<FutexKey, VecDeque<FutexWaiter>>,
+    // the queue each waiter is on, one missing here has been woken up
+    waiting: BTreeMap<usize, FutexKey>,
+    next_id: usize,
+}
+
+impl FutexTable {
+    const fn new() -> Self {
+        Self {
+            queues: BTreeMap::new(),
+            waiting: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn enqueue(&mut self, key: FutexKey, bitset: u32, waker: Waker) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queues
+            .entry(key)
+            .or_default()
+            .push_back(FutexWaiter { id, bitset, waker });
+        self.waiting.insert(id, key);
+        id
+    }
+
+    fn is_waiting(&self, id: usize) -> bool {
+        self.waiting.contains_key(&id)
+    }
+
+    // a waiter that timed out, got a signal or is dropped with its task
+    fn cancel(&mut self, id: usize) {
+        if let Some(key) = self.waiting.remove(&id) {
+            let queue = self.queues.get_mut(&key).unwrap();
+            queue.retain(|waiter| waiter.id != id);
+            if queue.is_empty() {
+                self.queues.remove(&key);
+            }
+        }
+    }
+
+    // up to `count` waiters whose bitset meets `bitset`, oldest first
+    fn take(&mut self, key: FutexKey, count: usize, bitset: u32) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        let Some(queue) = self.queues.get_mut(&key) else {
+            return wakers;
+        };
+        queue.retain(|waiter| {
+            if wakers.len() == count || waiter.bitset & bitset == 0 {
+                return true;
+            }
+            self.waiting.remove(&waiter.id);
+            wakers.push(waiter.waker.clone());
+            false
+        });
+        if queue.is_empty() {
+            self.queues.remove(&key);
+        }
+        wakers
+    }
+
+    fn requeue(&mut self, from: FutexKey, to: FutexKey, count: usize) -> usize {
+        let Some(mut queue) = self.queues.remove(&from) else {
+            return 0;
+        };
+        let moved: Vec<FutexWaiter> = queue.drain(..count.min(queue.len())).collect();
+        if !queue.is_empty() {
+            self.queues.insert(from, queue);
+        }
+
+        let requeued = moved.len();
+        for waiter in moved {
+            self.waiting.insert(waiter.id, to);
+            self.queues.entry(to).or_default().push_back(waiter);
+        }
+        requeued
+    }
+}
+// region FutexTable end
+
+// region FutexWait begin
+/// Wait on the u32 at user address `addr` while it holds `expected`, until woken
+/// up with a bitset meeting `bitset`. Err(EAGAIN) if it holds something else.
+pub fn futex_wait(key: FutexKey, addr: usize, expected: u32, bitset: u32) -> FutexWait {
+    FutexWait {
+        key,
+        addr,
+        expected,
+        bitset,
+        id: None,
+    }
+}
+
+pub struct FutexWait {
+    key: FutexKey,
+    addr: usize,
+    expected: u32,
+    bitset: u32,
+    id: Option<usize>,
+}
+
+impl Future for FutexWait {
+    type Output = Result<(), isize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // the value is checked under the table lock a waker takes as well
+        let mut table = FUTEX_TABLE.lock();
+        match self.id {
+            None => {
+                let value = unsafe { (self.addr as *const u32).read_volatile() };
+                if value != self.expected {
+                    return Poll::Ready(Err(EAGAIN));
+                }
+                self.id = Some(table.enqueue(self.key, self.bitset, cx.waker().clone()));
+                Poll::Pending
+            }
+            Some(id) if table.is_waiting(id) => Poll::Pending,
+            Some(_) => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl Drop for FutexWait {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            FUTEX_TABLE.lock().cancel(id);
+        }
+    }
+}
+// region FutexWait end
+
+/// Wake up to `count` waiters on `key` whose bitset meets `bitset`, return how
+/// many were woken up
+pub fn futex_wake(key: FutexKey, count: usize, bitset: u32) -> usize {
+    let wakers = FUTEX_TABLE.lock().take(key, count, bitset);
+    let woken = wakers.len();
+    wakers.into_iter().for_each(Waker::wake);
+    woken
+}
+
+/// Wake up to `wake_count` waiters on `from` and move up to `requeue_count` of the
+/// others over to `to`, return (woken, requeued). With `expected`, Err(EAGAIN)
+/// unless the u32 at the user address holds the value.
+pub fn futex_requeue(
+    from: FutexKey,
+    to: FutexKey,
+    wake_count: usize,
+    requeue_count: usize,
+    expected: Option<(usize, u32)>,
+) -> Result<(usize, usize), isize> {
+    let mut table = FUTEX_TABLE.lock();
+    if let Some((addr, expected)) = expected {
+        let value = unsafe { (addr as *const u32).read_volatile() };
+        if value != expected {
+            return Err(EAGAIN);
+        }
+    }
+    let wakers = table.take(from, wake_count, FUTEX_BITSET_MATCH_ANY);
+    let requeued = table.requeue(from, to, requeue_count);
+    drop(table);
+
+    let woken = wakers.len();
+    wakers.into_iter().for_each(Waker::wake);
+    Ok((woken, requeued))
+}
@@ -0,0 +1,54 @@
+use crate::config::{ETEXT, STEXT};
+use core::{arch::asm, fmt::Display};
+
+const MAX_FRAMES: usize = 16;
+
+// region Backtrace begin
+/// Return addresses collected by walking the frame pointers,
+/// resolve them with `addr2line -e <kernel>`
+#[derive(Clone, Copy)]
+pub struct Backtrace {
+    frames: [usize; MAX_FRAMES],
+    len: usize,
+}
+
+impl Backtrace {
+    #[inline(always)]
+    pub fn capture() -> Self {
+        let mut backtrace = Self {
+            frames: [0; MAX_FRAMES],
+            len: 0,
+        };
+        let mut fp: usize;
+        unsafe { asm!("mv {}, s0", out(reg) fp) };
+
+        // riscv frame: ra at fp - 8, the caller's fp at fp - 16
+        while backtrace.len < MAX_FRAMES && fp != 0 && fp % 8 == 0 {
+            let ra = unsafe { *((fp - 8) as *const usize) };
+            // stop at the trap entry, where the saved registers belong to user
+            if !(*STEXT..*ETEXT).contains(&ra) {
+                break;
+            }
+            backtrace.frames[backtrace.len] = ra;
+            backtrace.len += 1;
+
+            let prev_fp = unsafe { *((fp - 16) as *const usize) };
+            // the stack grows down, callers always sit above
+            if prev_fp <= fp {
+                break;
+            }
+            fp = prev_fp;
+        }
+        backtrace
+    }
+}
+
+impl Display for Backtrace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, ra) in self.frames[..self.len].iter().enumerate() {
+            writeln!(f, "    #{} {:#x}", i, ra)?;
+        }
+        Ok(())
+    }
+}
+// region Backtrace end
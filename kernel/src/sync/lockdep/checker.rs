@@ -0,0 +1,186 @@
+use super::{backtrace::Backtrace, LockMode};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::panic::Location;
+use spin::Mutex;
+
+/// Record the lock at `addr` before it is taken, panics on a recursive
+/// acquisition or a lock order inversion
+pub fn acquire(class: &'static str, addr: usize, location: &'static Location, mode: LockMode) {
+    let site = Site::capture(location);
+    let mut lockdep = LOCKDEP.lock();
+    if mode != LockMode::Try {
+        lockdep.check(class, addr, &site, mode);
+    }
+    lockdep.held.push(HeldLock {
+        class,
+        addr,
+        mode,
+        site,
+    });
+}
+
+pub fn release(addr: usize) {
+    let mut lockdep = LOCKDEP.lock();
+    // locks are not always released in order
+    if let Some(index) = lockdep.held.iter().rposition(|held| held.addr == addr) {
+        lockdep.held.remove(index);
+    }
+}
+
+/// A task switched out keeps its sleeping locks, so each kernel stack has its own
+/// held list, `0` stands for the idle loop
+#[cfg(feature = "stackful")]
+pub fn switch_context(from: usize, to: usize) {
+    let mut lockdep = LOCKDEP.lock();
+    let held = core::mem::take(&mut lockdep.held);
+    if !held.is_empty() {
+        lockdep.parked.insert(from, held);
+    }
+    lockdep.held = lockdep.parked.remove(&to).unwrap_or_default();
+}
+
+/// Locks must not outlive a syscall, the kernel stack is abandoned on return to user
+pub fn assert_no_locks_held(context: &str) {
+    let lockdep = LOCKDEP.lock();
+    if lockdep.held.is_empty() {
+        return;
+    }
+    println!(
+        "[lockdep] {} lock(s) held at {}",
+        lockdep.held.len(),
+        context
+    );
+    for held in lockdep.held.iter() {
+        println!("[lockdep] {} taken at {}", held.class, held.site.location);
+        print!("{}", held.site.backtrace);
+    }
+    drop(lockdep);
+    panic!("lockdep: lock held at {}", context);
+}
+
+// the kernel only runs on the boot hart, so one record stands for the hart
+static LOCKDEP: Mutex<LockDep> = Mutex::new(LockDep::new());
+
+// region LockDep begin
+struct LockDep {
+    held: Vec<HeldLock>,
+    #[cfg(feature = "stackful")]
+    parked: BTreeMap<usize, Vec<HeldLock>>,
+    // (held class, taken class) -> where the order was first seen
+    order: BTreeMap<(&'static str, &'static str), OrderEdge>,
+}
+
+impl LockDep {
+    const fn new() -> Self {
+        Self {
+            held: Vec::new(),
+            #[cfg(feature = "stackful")]
+            parked: BTreeMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn check(&mut self, class: &'static str, addr: usize, site: &Site, mode: LockMode) {
+        if let Some(held) = self.held.iter().find(|held| {
+            held.addr == addr && !(mode == LockMode::Shared && held.mode == LockMode::Shared)
+        }) {
+            println!("[lockdep] recursive acquisition of {}", class);
+            println!("[lockdep] first taken at {}", held.site.location);
+            print!("{}", held.site.backtrace);
+            println!("[lockdep] taken again at {}", site.location);
+            print!("{}", site.backtrace);
+            panic!("lockdep: recursive acquisition of {}", class);
+        }
+
+        for index in 0..self.held.len() {
+            let held = &self.held[index];
+            // locks of one class, such as two PCBs, are not ordered among themselves
+            if held.class == class {
+                continue;
+            }
+            if let Some((key, edge)) = self.find_path(class, held.class) {
+                println!(
+                    "[lockdep] lock order inversion: {} -> {}",
+                    held.class, class
+                );
+                println!("[lockdep] {} held since {}", held.class, held.site.location);
+                print!("{}", held.site.backtrace);
+                println!("[lockdep] {} taken at {}", class, site.location);
+                print!("{}", site.backtrace);
+                println!("[lockdep] but {} -> {} was recorded before", key.0, key.1);
+                println!("[lockdep] {} held since {}", key.0, edge.held.location);
+                print!("{}", edge.held.backtrace);
+                println!("[lockdep] {} taken at {}", key.1, edge.taken.location);
+                print!("{}", edge.taken.backtrace);
+                panic!(
+                    "lockdep: lock order inversion between {} and {}",
+                    held.class, class
+                );
+            }
+            let edge = OrderEdge {
+                held: held.site,
+                taken: *site,
+            };
+            self.order.entry((held.class, class)).or_insert(edge);
+        }
+    }
+
+    /// The first recorded edge of a path `from` -> ... -> `to`
+    fn find_path(
+        &self,
+        from: &'static str,
+        to: &'static str,
+    ) -> Option<((&'static str, &'static str), &OrderEdge)> {
+        let mut visited = Vec::from([from]);
+        let mut stack: Vec<(&'static str, Option<(&'static str, &'static str)>)> =
+            Vec::from([(from, None)]);
+        while let Some((class, first)) = stack.pop() {
+            for (&key, _) in self
+                .order
+                .range((class, "")..)
+                .take_while(|(key, _)| key.0 == class)
+            {
+                let first = first.unwrap_or(key);
+                if key.1 == to {
+                    return Some((first, &self.order[&first]));
+                }
+                if !visited.contains(&key.1) {
+                    visited.push(key.1);
+                    stack.push((key.1, Some(first)));
+                }
+            }
+        }
+        None
+    }
+}
+// region LockDep end
+
+struct HeldLock {
+    class: &'static str,
+    addr: usize,
+    mode: LockMode,
+    site: Site,
+}
+
+struct OrderEdge {
+    held: Site,
+    taken: Site,
+}
+
+// region Site begin
+#[derive(Clone, Copy)]
+struct Site {
+    location: &'static Location<'static>,
+    backtrace: Backtrace,
+}
+
+impl Site {
+    #[inline(always)]
+    fn capture(location: &'static Location<'static>) -> Self {
+        Self {
+            location,
+            backtrace: Backtrace::capture(),
+        }
+    }
+}
+// region Site end
@@ -0,0 +1,39 @@
+#[cfg(feature = "lockdep")]
+pub use checker::*;
+#[cfg(not(feature = "lockdep"))]
+pub use disabled::*;
+
+#[cfg(feature = "lockdep")]
+mod backtrace;
+#[cfg(feature = "lockdep")]
+mod checker;
+
+// region LockMode begin
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Exclusive,
+    // a shared holder could take the same lock again
+    Shared,
+    // never waits, so only the order is recorded
+    Try,
+}
+// region LockMode end
+
+#[cfg(not(feature = "lockdep"))]
+mod disabled {
+    use super::LockMode;
+    use core::panic::Location;
+
+    #[inline(always)]
+    pub fn acquire(_class: &str, _addr: usize, _location: &Location, _mode: LockMode) {}
+
+    #[inline(always)]
+    pub fn release(_addr: usize) {}
+
+    #[cfg(feature = "stackful")]
+    #[inline(always)]
+    pub fn switch_context(_from: usize, _to: usize) {}
+
+    #[inline(always)]
+    pub fn assert_no_locks_held(_context: &str) {}
+}
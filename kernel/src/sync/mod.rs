@@ -1,3 +1,12 @@
-pub use up::*;
+pub use futex::*;
+pub use mutex::*;
+pub use rwlock::*;
+pub use spin::*;
+pub use wait_queue::*;
 
-mod up;
+mod futex;
+pub mod lockdep;
+mod mutex;
+mod rwlock;
+mod spin;
+mod wait_queue;
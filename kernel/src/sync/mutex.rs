@@ -0,0 +1,113 @@
+use crate::sync::{
+    lockdep::{self, LockMode},
+    SpinNoIrqLock, WaitQueue,
+};
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+    panic::Location,
+};
+
+// region Mutex begin
+/// Sleeping lock, a contended `lock` blocks the current task instead of spinning
+pub struct Mutex<T> {
+    name: &'static str,
+    locked: SpinNoIrqLock<bool>,
+    waiters: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Sync for Mutex<T> {}
+unsafe impl<T: Send> Send for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(name: &'static str, data: T) -> Self {
+        Self {
+            name,
+            locked: SpinNoIrqLock::new("MutexState", false),
+            waiters: WaitQueue::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Blocks with `WaitQueue::wait` semantics when the lock is taken, or sleeps
+    /// until it is released in stackful mode. Since a stackless task restarts
+    /// its syscall from the beginning, `lock` must come before any other side
+    /// effect of the syscall, and no other lock may be held; lockdep checks the
+    /// latter.
+    #[track_caller]
+    pub fn lock(&self) -> MutexGuard<T> {
+        lockdep::acquire(
+            self.name,
+            self.addr(),
+            Location::caller(),
+            LockMode::Exclusive,
+        );
+        #[cfg(not(feature = "stackful"))]
+        if !self.try_acquire() {
+            // the task restarts the syscall, and takes the lock from the beginning.
+            // A single hart never preempts the kernel, so only a guard kept across
+            // an `await` makes the lock contended here.
+            lockdep::release(self.addr());
+            self.waiters.wait();
+        }
+        // the lock stays recorded as wanted while the task sleeps
+        #[cfg(feature = "stackful")]
+        while !self.try_acquire() {
+            self.waiters.sleep();
+        }
+        MutexGuard { mutex: self }
+    }
+
+    #[allow(unused)]
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<MutexGuard<T>> {
+        if !self.try_acquire() {
+            return None;
+        }
+        lockdep::acquire(self.name, self.addr(), Location::caller(), LockMode::Try);
+        Some(MutexGuard { mutex: self })
+    }
+
+    fn try_acquire(&self) -> bool {
+        let mut locked = self.locked.lock();
+        if *locked {
+            return false;
+        }
+        *locked = true;
+        true
+    }
+
+    fn addr(&self) -> usize {
+        self as *const _ as usize
+    }
+}
+// region Mutex end
+
+// region MutexGuard begin
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        *self.mutex.locked.lock() = false;
+        lockdep::release(self.mutex.addr());
+        self.mutex.waiters.wake_one();
+    }
+}
+// region MutexGuard end
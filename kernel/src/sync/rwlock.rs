@@ -0,0 +1,147 @@
+use crate::sync::{
+    lockdep::{self, LockMode},
+    SpinNoIrqLock, WaitQueue,
+};
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+    panic::Location,
+};
+
+// region RwLock begin
+/// Sleeping reader-writer lock, contended tasks block like `Mutex`
+pub struct RwLock<T> {
+    name: &'static str,
+    state: SpinNoIrqLock<RwLockState>,
+    waiters: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+unsafe impl<T: Send> Send for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(name: &'static str, data: T) -> Self {
+        Self {
+            name,
+            state: SpinNoIrqLock::new(
+                "RwLockState",
+                RwLockState {
+                    readers: 0,
+                    writer: false,
+                },
+            ),
+            waiters: WaitQueue::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    #[track_caller]
+    pub fn read(&self) -> RwLockReadGuard<T> {
+        lockdep::acquire(self.name, self.addr(), Location::caller(), LockMode::Shared);
+        let mut state = self.state.lock();
+        #[cfg(not(feature = "stackful"))]
+        if state.writer {
+            drop(state);
+            lockdep::release(self.addr());
+            self.waiters.wait();
+        }
+        #[cfg(feature = "stackful")]
+        while state.writer {
+            drop(state);
+            self.waiters.sleep();
+            state = self.state.lock();
+        }
+        state.readers += 1;
+        RwLockReadGuard { lock: self }
+    }
+
+    #[track_caller]
+    pub fn write(&self) -> RwLockWriteGuard<T> {
+        lockdep::acquire(
+            self.name,
+            self.addr(),
+            Location::caller(),
+            LockMode::Exclusive,
+        );
+        let mut state = self.state.lock();
+        #[cfg(not(feature = "stackful"))]
+        if state.writer || state.readers != 0 {
+            drop(state);
+            lockdep::release(self.addr());
+            self.waiters.wait();
+        }
+        #[cfg(feature = "stackful")]
+        while state.writer || state.readers != 0 {
+            drop(state);
+            self.waiters.sleep();
+            state = self.state.lock();
+        }
+        state.writer = true;
+        RwLockWriteGuard { lock: self }
+    }
+
+    fn addr(&self) -> usize {
+        self as *const _ as usize
+    }
+}
+// region RwLock end
+
+struct RwLockState {
+    readers: usize,
+    writer: bool,
+}
+
+// region RwLockReadGuard begin
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        lockdep::release(self.lock.addr());
+        let mut state = self.lock.state.lock();
+        state.readers -= 1;
+        if state.readers == 0 {
+            drop(state);
+            self.lock.waiters.wake_all();
+        }
+    }
+}
+// region RwLockReadGuard end
+
+// region RwLockWriteGuard begin
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.lock().writer = false;
+        lockdep::release(self.lock.addr());
+        self.lock.waiters.wake_all();
+    }
+}
+// region RwLockWriteGuard end
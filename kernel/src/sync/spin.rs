@@ -0,0 +1,162 @@
+use crate::sync::lockdep::{self, LockMode};
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    panic::Location,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use riscv::register::sstatus;
+
+pub type SpinLock<T> = SpinMutex<T, Spin>;
+pub type SpinLockGuard<'a, T> = SpinMutexGuard<'a, T, Spin>;
+
+/// Spin lock that keeps supervisor interrupts off while held, for state
+/// shared with the trap handler and the scheduler
+pub type SpinNoIrqLock<T> = SpinMutex<T, SpinNoIrq>;
+pub type SpinNoIrqLockGuard<'a, T> = SpinMutexGuard<'a, T, SpinNoIrq>;
+
+// region IrqPolicy begin
+/// What to do with interrupts around a critical section
+pub trait IrqPolicy {
+    type State;
+
+    fn enter() -> Self::State;
+    fn exit(state: Self::State);
+}
+
+pub struct Spin;
+
+impl IrqPolicy for Spin {
+    type State = ();
+
+    fn enter() -> Self::State {}
+
+    fn exit(_state: Self::State) {}
+}
+
+pub struct SpinNoIrq;
+
+impl IrqPolicy for SpinNoIrq {
+    // sstatus.SIE before entering
+    type State = bool;
+
+    fn enter() -> Self::State {
+        let sie = sstatus::read().sie();
+        unsafe { sstatus::clear_sie() };
+        sie
+    }
+
+    fn exit(sie: Self::State) {
+        if sie {
+            unsafe { sstatus::set_sie() };
+        }
+    }
+}
+// region IrqPolicy end
+
+// region SpinMutex begin
+pub struct SpinMutex<T, I: IrqPolicy> {
+    // lock class, see `sync::lockdep`
+    name: &'static str,
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+    _policy: PhantomData<I>,
+}
+
+unsafe impl<T: Send, I: IrqPolicy> Sync for SpinMutex<T, I> {}
+unsafe impl<T: Send, I: IrqPolicy> Send for SpinMutex<T, I> {}
+
+impl<T, I: IrqPolicy> SpinMutex<T, I> {
+    pub const fn new(name: &'static str, data: T) -> Self {
+        Self {
+            name,
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(data),
+            _policy: PhantomData,
+        }
+    }
+
+    #[track_caller]
+    pub fn lock(&self) -> SpinMutexGuard<T, I> {
+        // interrupts go off first, so that a handler could not spin on a lock we hold
+        let irq_state = I::enter();
+        // check before spinning, a deadlock would never return
+        lockdep::acquire(
+            self.name,
+            self.addr(),
+            Location::caller(),
+            LockMode::Exclusive,
+        );
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.locked.load(Ordering::Relaxed) {
+                core::hint::spin_loop();
+            }
+        }
+        SpinMutexGuard {
+            lock: self,
+            irq_state: Some(irq_state),
+        }
+    }
+
+    #[allow(unused)]
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<SpinMutexGuard<T, I>> {
+        let irq_state = I::enter();
+        if self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            lockdep::acquire(self.name, self.addr(), Location::caller(), LockMode::Try);
+            Some(SpinMutexGuard {
+                lock: self,
+                irq_state: Some(irq_state),
+            })
+        } else {
+            I::exit(irq_state);
+            None
+        }
+    }
+
+    fn addr(&self) -> usize {
+        self as *const _ as usize
+    }
+}
+// region SpinMutex end
+
+// region SpinMutexGuard begin
+pub struct SpinMutexGuard<'a, T, I: IrqPolicy> {
+    lock: &'a SpinMutex<T, I>,
+    irq_state: Option<I::State>,
+}
+
+impl<T, I: IrqPolicy> Deref for SpinMutexGuard<'_, T, I> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T, I: IrqPolicy> DerefMut for SpinMutexGuard<'_, T, I> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T, I: IrqPolicy> Drop for SpinMutexGuard<'_, T, I> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+        lockdep::release(self.lock.addr());
+        // restore interrupts only after the lock is released
+        if let Some(irq_state) = self.irq_state.take() {
+            I::exit(irq_state);
+        }
+    }
+}
+// region SpinMutexGuard end
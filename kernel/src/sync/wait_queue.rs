@@ -0,0 +1,64 @@
+#[cfg(not(feature = "stackful"))]
+use crate::sync::lockdep;
+use crate::{sync::SpinNoIrqLock, task};
+use alloc::collections::VecDeque;
+use core::task::Waker;
+
+// region WaitQueue begin
+/// Wakers of tasks blocked on a condition, a blocked task is out of the ready
+/// queue until one of its wakers fires
+pub struct WaitQueue {
+    queue: SpinNoIrqLock<VecDeque<Waker>>,
+}
+
+impl WaitQueue {
+    pub const fn new() -> Self {
+        Self {
+            queue: SpinNoIrqLock::new("WaitQueue", VecDeque::new()),
+        }
+    }
+
+    /// Block the current task, its pending syscall is restarted from the `ecall`
+    /// once woken up. The kernel stack is abandoned, so no lock guard may be held.
+    #[cfg(not(feature = "stackful"))]
+    pub fn wait(&self) -> ! {
+        // a guard held here would never be dropped
+        lockdep::assert_no_locks_held("WaitQueue::wait");
+        task::get_processor().block_current(self)
+    }
+
+    /// Block the current task until woken up, then return right here on its own
+    /// kernel stack. Unlike `wait`, lock guards may be held across it.
+    #[cfg(feature = "stackful")]
+    pub fn sleep(&self) {
+        task::get_processor().sleep_current(self)
+    }
+
+    /// Wake `waker` along with the others, a future returns `Pending` after this.
+    /// A waker already queued, from an earlier poll, is not queued again.
+    pub fn register(&self, waker: &Waker) {
+        let mut queue = self.queue.lock();
+        if !queue.iter().any(|queued| queued.will_wake(waker)) {
+            queue.push_back(waker.clone());
+        }
+    }
+
+    pub fn wake_one(&self) -> bool {
+        let waker = self.queue.lock().pop_front();
+        match waker {
+            Some(waker) => {
+                waker.wake();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn wake_all(&self) -> usize {
+        let wakers = core::mem::take(&mut *self.queue.lock());
+        let count = wakers.len();
+        wakers.into_iter().for_each(Waker::wake);
+        count
+    }
+}
+// region WaitQueue end
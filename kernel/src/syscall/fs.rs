@@ -1,32 +1,55 @@
 use crate::{
     config::ROOT_DIR,
-    fs::{self, Inode, InodeType, LinuxDirent64, OpenFlags, PathUtil},
-    syscall::translate_str,
-    task,
+    fs::{self, EventFd, File, InodeType, LinuxDirent64, OpenFlags, PathUtil, EFD_SEMAPHORE},
+    syscall::{
+        errno::{EBADF, EEXIST, EINTR, EINVAL, ENOENT, EPERM, EPIPE},
+        translate_str,
+    },
+    task::{self, Interrupted, SIGPIPE},
+    timer,
 };
 use alloc::{string::ToString, sync::Arc};
+use core::future;
 
-pub fn sys_read(fd: usize, buffer: *mut u8, len: usize) -> isize {
-    let current_task = task::get_processor().current();
-    let task_inner = current_task.inner();
-    if let Some(fd_impl) = task_inner.find_fd(fd) {
+pub async fn sys_read(fd: usize, buffer: *mut u8, len: usize) -> isize {
+    let file = task::get_processor().current().inner().find_fd(fd);
+    if let Some(fd_impl) = file {
         assert!(fd_impl.readable(), "fd {} not readable", fd);
         let slice = unsafe { core::slice::from_raw_parts_mut(buffer, len) };
-        drop(task_inner);
-        fd_impl.read(slice) as isize
+        let read = future::poll_fn(|cx| fd_impl.poll_read(slice, cx));
+        match task::interruptible(read).await {
+            Ok(Ok(len)) => len as isize,
+            Ok(Err(errno)) => -errno,
+            Err(Interrupted) => -EINTR,
+        }
     } else {
         panic!("sys_read: fd {} not supported", fd);
     }
 }
 
-pub fn sys_write(fd: usize, buffer: *const u8, len: usize) -> isize {
-    let current_task = task::get_processor().current();
-    let task_inner = current_task.inner();
-    if let Some(fd_impl) = task_inner.find_fd(fd) {
+pub async fn sys_write(fd: usize, buffer: *const u8, len: usize) -> isize {
+    let file = task::get_processor().current().inner().find_fd(fd);
+    if let Some(fd_impl) = file {
         assert!(fd_impl.writable(), "fd {} is not writable", fd);
         let slice = unsafe { core::slice::from_raw_parts(buffer, len) };
-        drop(task_inner);
-        fd_impl.write(slice) as isize
+        // a pipe may take the buffer in several parts
+        let mut written = 0;
+        while written < len {
+            let write = future::poll_fn(|cx| fd_impl.poll_write(&slice[written..], cx));
+            match task::interruptible(write).await {
+                Ok(Ok(0)) => break,
+                Ok(Ok(n)) => written += n,
+                // the error shows up on the next call after a partial write
+                _ if written > 0 => break,
+                Ok(Err(EPIPE)) => {
+                    task::send_signal(&task::get_processor().current(), SIGPIPE);
+                    return -EPIPE;
+                }
+                Ok(Err(errno)) => return -errno,
+                Err(Interrupted) => return -EINTR,
+            }
+        }
+        written as isize
     } else {
         panic!("sys_write: fd {} not supported", fd);
     }
@@ -49,7 +72,7 @@ pub fn sys_chdir(path_ptr: *const u8) -> isize {
         // '/' could not be opened
         task::get_processor()
             .current()
-            .inner_mut()
+            .inner()
             .set_cwd(ROOT_DIR.to_string());
         return 0;
     }
@@ -57,48 +80,53 @@ pub fn sys_chdir(path_ptr: *const u8) -> isize {
     let inode = fs::open_file(&path, OpenFlags::RDONLY);
     if let Some(inode) = inode {
         if inode.get_type() == InodeType::Dir {
-            task::get_processor().current().inner_mut().set_cwd(path);
+            task::get_processor().current().inner().set_cwd(path);
             return 0;
         }
     }
     -1
 }
 
-pub fn sys_open(_dir_fd: i32, path_ptr: *const u8, flags: usize) -> isize {
+pub async fn sys_open(_dir_fd: i32, path_ptr: *const u8, flags: usize) -> isize {
     let path = translate_str(path_ptr);
     let path = PathUtil::from_user(path).to_string();
     let flags = OpenFlags::from_bits(flags as u32).unwrap();
 
-    let inode = fs::open_file(&path, flags);
-    if let Some(inode) = inode {
-        let current_task = task::get_processor().current();
-        let mut task_inner = current_task.inner_mut();
-
-        match inode.get_type() {
-            InodeType::File => {
-                if flags.directory() {
-                    return -1;
-                }
-                let file = Arc::new(inode.to_file());
-                let fd = task_inner.alloc_fd(file);
-                return fd as isize;
-            }
-            InodeType::Dir => {
-                let dir = Arc::new(inode.to_dir());
-                let fd = task_inner.alloc_fd(dir);
-                return fd as isize;
+    let inode = match fs::open_file(&path, flags) {
+        Some(inode) => inode,
+        None => return -1,
+    };
+    let file: Arc<dyn File + Send + Sync> = match inode.get_type() {
+        InodeType::File if flags.directory() => return -1,
+        InodeType::File => inode.to_file(),
+        InodeType::Dir => inode.to_dir(),
+        // may wait for the other end to be opened
+        InodeType::Fifo => {
+            let fifo = inode.get_fifo().unwrap();
+            match task::interruptible(fifo.open(flags)).await {
+                Ok(Ok(pipe)) => pipe,
+                Ok(Err(errno)) => return -errno,
+                Err(Interrupted) => return -EINTR,
             }
-            _ => {}
         }
-    }
-    -1
+        _ => return -1,
+    };
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let fd = task_inner.alloc_fd(file);
+    task_inner.set_cloexec(fd, flags.cloexec());
+    fd as isize
 }
 
 pub fn sys_close(fd: usize) -> isize {
     let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
+    let mut task_inner = current_task.inner();
 
-    match task_inner.take_fd(fd) {
+    let file = task_inner.take_fd(fd);
+    // a pipe end takes the pipe lock when dropped, so not under ours
+    drop(task_inner);
+    match file {
         Some(_) => 0,
         None => -1,
     }
@@ -115,13 +143,46 @@ pub fn sys_mkdir(_dir_fd: usize, path_ptr: *const u8, mode: usize) -> isize {
     }
 }
 
-pub fn sys_pipe(pipe_ptr: *mut i32) -> isize {
-    let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
+pub fn sys_mknodat(_dir_fd: usize, path_ptr: *const u8, mode: usize, _dev: usize) -> isize {
+    const S_IFMT: usize = 0o170000;
+    const S_IFIFO: usize = 0o010000;
+    const S_IFREG: usize = 0o100000;
+
+    let path = translate_str(path_ptr);
+    let path = PathUtil::from_user(path).to_string();
+    if fs::open_inode(&path).is_some() {
+        return -EEXIST;
+    }
 
-    let (pipe_read, pipe_write) = fs::make_pipe();
+    let created = match mode & S_IFMT {
+        S_IFIFO => fs::create_fifo(&path).is_ok(),
+        0 | S_IFREG => fs::open_file(&path, OpenFlags::CREATE).is_some(),
+        // device nodes are not supported
+        _ => false,
+    };
+    if created {
+        0
+    } else {
+        -EPERM
+    }
+}
+
+pub fn sys_pipe(pipe_ptr: *mut i32, flags: usize) -> isize {
+    let flags = match OpenFlags::from_bits(flags as u32) {
+        Some(flags) if (flags - OpenFlags::NONBLOCK - OpenFlags::CLOEXEC).is_empty() => flags,
+        _ => return -EINVAL,
+    };
+
+    let (pipe_read, pipe_write) = match fs::make_pipe(flags) {
+        Ok(pipe) => pipe,
+        Err(errno) => return -errno,
+    };
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
     let read_fd = task_inner.alloc_fd(pipe_read);
     let write_fd = task_inner.alloc_fd(pipe_write);
+    task_inner.set_cloexec(read_fd, flags.cloexec());
+    task_inner.set_cloexec(write_fd, flags.cloexec());
 
     unsafe {
         *pipe_ptr = read_fd as i32;
@@ -130,9 +191,85 @@ pub fn sys_pipe(pipe_ptr: *mut i32) -> isize {
     0
 }
 
-pub fn sys_mount(_source_ptr: *const u8, _target_ptr: *const u8, _fs_type_ptr: *const u8) -> isize {
-    // unsupported for rust-fatfs
-    0
+pub fn sys_eventfd2(init_value: usize, flags: usize) -> isize {
+    let semaphore = flags & EFD_SEMAPHORE != 0;
+    let flags = match OpenFlags::from_bits((flags & !EFD_SEMAPHORE) as u32) {
+        Some(flags) if (flags - OpenFlags::NONBLOCK - OpenFlags::CLOEXEC).is_empty() => flags,
+        _ => return -EINVAL,
+    };
+
+    // the initial value is an unsigned int
+    let event_fd = EventFd::new(init_value as u32 as u64, semaphore, flags.nonblock());
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let fd = task_inner.alloc_fd(Arc::new(event_fd));
+    task_inner.set_cloexec(fd, flags.cloexec());
+    fd as isize
+}
+
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    const F_GETFD: usize = 1;
+    const F_SETFD: usize = 2;
+    const F_GETFL: usize = 3;
+    const F_SETFL: usize = 4;
+    const F_SETPIPE_SZ: usize = 1031;
+    const F_GETPIPE_SZ: usize = 1032;
+    const FD_CLOEXEC: usize = 1;
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let file = match task_inner.find_fd(fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+
+    match cmd {
+        F_GETFD => match task_inner.get_cloexec(fd) {
+            true => FD_CLOEXEC as isize,
+            false => 0,
+        },
+        F_SETFD => {
+            task_inner.set_cloexec(fd, arg & FD_CLOEXEC != 0);
+            0
+        }
+        F_GETFL => {
+            let mut flags = match (file.readable(), file.writable()) {
+                (true, true) => OpenFlags::RDWR,
+                (false, true) => OpenFlags::WRONLY,
+                _ => OpenFlags::RDONLY,
+            };
+            flags.set(OpenFlags::NONBLOCK, file.is_nonblock());
+            flags.bits() as isize
+        }
+        // only O_NONBLOCK can be changed, the rest is ignored like Linux does
+        F_SETFL => {
+            file.set_nonblock(OpenFlags::from_bits_truncate(arg as u32).nonblock());
+            0
+        }
+        F_SETPIPE_SZ => match file.as_pipe().map(|pipe| pipe.set_size(arg)) {
+            Some(Ok(size)) => size as isize,
+            Some(Err(errno)) => -errno,
+            None => -EBADF,
+        },
+        F_GETPIPE_SZ => match file.as_pipe() {
+            Some(pipe) => pipe.get_size() as isize,
+            None => -EBADF,
+        },
+        _ => -EINVAL,
+    }
+}
+
+pub fn sys_mount(_source_ptr: *const u8, target_ptr: *const u8, fs_type_ptr: *const u8) -> isize {
+    // only tmpfs could be mounted, the rest are unsupported for rust-fatfs
+    if fs_type_ptr.is_null() || translate_str(fs_type_ptr) != "tmpfs" {
+        return 0;
+    }
+    let target = translate_str(target_ptr);
+    let target = PathUtil::from_user(target).to_string();
+    match fs::mount_tmpfs(&target) {
+        Ok(()) => 0,
+        Err(errno) => -errno,
+    }
 }
 
 pub fn sys_umount(_target_ptr: *const u8) -> isize {
@@ -151,7 +288,7 @@ pub fn sys_unlink(_dirfd: usize, path_ptr: *const u8, _flags: usize) -> isize {
 
 pub fn sys_dup(old_fd: usize) -> isize {
     let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
+    let mut task_inner = current_task.inner();
 
     let old = match task_inner.find_fd(old_fd) {
         Some(fd) => fd,
@@ -159,14 +296,13 @@ pub fn sys_dup(old_fd: usize) -> isize {
     };
 
     let new_fd = task_inner.alloc_fd(old);
-    drop(task_inner);
 
     new_fd as isize
 }
 
 pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
     let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
+    let mut task_inner = current_task.inner();
 
     let old = match task_inner.find_fd(old_fd) {
         Some(fd) => fd,
@@ -174,7 +310,6 @@ pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
     };
 
     task_inner.insert_fd(new_fd, old);
-    drop(task_inner);
 
     new_fd as isize
 }
@@ -258,7 +393,7 @@ pub fn sys_getdents(fd: usize, dirent_ptr: *const u8, len: usize) -> isize {
     if inode.get_type() != InodeType::Dir {
         return -1;
     }
-    let entries = inode.to_dir().get_entries();
+    let entries = inode.get_entries();
 
     let mut offset = 0;
     for entry in entries {
@@ -274,3 +409,106 @@ pub fn sys_getdents(fd: usize, dirent_ptr: *const u8, len: usize) -> isize {
 
     offset as isize
 }
+
+pub fn sys_renameat2(
+    _old_dirfd: usize,
+    old_path_ptr: *const u8,
+    _new_dirfd: usize,
+    new_path_ptr: *const u8,
+    flags: usize,
+) -> isize {
+    const RENAME_NOREPLACE: usize = 1 << 0;
+    const RENAME_EXCHANGE: usize = 1 << 1;
+
+    // swapping two entries is unsupported for rust-fatfs
+    if flags & RENAME_EXCHANGE != 0 {
+        return -EINVAL;
+    }
+
+    let old_path = translate_str(old_path_ptr);
+    let old_path = PathUtil::from_user(old_path).to_string();
+    let new_path = translate_str(new_path_ptr);
+    let new_path = PathUtil::from_user(new_path).to_string();
+    match fs::rename(&old_path, &new_path, flags & RENAME_NOREPLACE != 0) {
+        Ok(()) => 0,
+        Err(errno) => -errno,
+    }
+}
+
+pub fn sys_truncate(path_ptr: *const u8, len: usize) -> isize {
+    let path = translate_str(path_ptr);
+    let path = PathUtil::from_user(path).to_string();
+    match fs::truncate(&path, len) {
+        Ok(()) => 0,
+        Err(errno) => -errno,
+    }
+}
+
+pub fn sys_ftruncate(fd: usize, len: usize) -> isize {
+    let file = match task::get_processor().current().inner().find_fd(fd) {
+        Some(fd) => fd,
+        None => return -EBADF,
+    };
+    // only a regular file open for writing can be truncated
+    if file.truncate(len) {
+        0
+    } else {
+        -EINVAL
+    }
+}
+
+pub fn sys_sync() -> isize {
+    fs::sync();
+    0
+}
+
+pub fn sys_fsync(fd: usize) -> isize {
+    let file = match task::get_processor().current().inner().find_fd(fd) {
+        Some(fd) => fd,
+        None => return -EBADF,
+    };
+    file.sync();
+    0
+}
+
+pub fn sys_utimensat(
+    dir_fd: usize,
+    path_ptr: *const u8,
+    times_ptr: *const u8,
+    _flags: usize,
+) -> isize {
+    const UTIME_NOW: i64 = (1 << 30) - 1;
+    const UTIME_OMIT: i64 = (1 << 30) - 2;
+
+    // a null path means the file referred by dir_fd, see futimens(3)
+    let path = if path_ptr.is_null() {
+        let file = task::get_processor().current().inner().find_fd(dir_fd);
+        match file {
+            Some(fd) => fd.path(),
+            None => return -EBADF,
+        }
+    } else {
+        let path = translate_str(path_ptr);
+        PathUtil::from_user(path).to_string()
+    };
+
+    let now = timer::get_wall_time().sec();
+    let (atime, mtime) = if times_ptr.is_null() {
+        (Some(now), Some(now))
+    } else {
+        // struct timespec times[2], { tv_sec, tv_nsec }
+        let times = unsafe { core::slice::from_raw_parts(times_ptr as *const [i64; 2], 2) };
+        let convert = |time: &[i64; 2]| match time[1] {
+            UTIME_OMIT => None,
+            UTIME_NOW => Some(now),
+            _ => Some(time[0] as usize),
+        };
+        (convert(&times[0]), convert(&times[1]))
+    };
+
+    match fs::open_inode(&path).map(|inode| inode.set_times(atime, mtime)) {
+        Some(Ok(())) => 0,
+        Some(Err(errno)) => -errno,
+        None => -ENOENT,
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    sync::{self, FutexKey, FUTEX_BITSET_MATCH_ANY},
+    syscall::errno::{EINTR, EINVAL, ENOSYS, ETIMEDOUT},
+    task::{self, Interrupted},
+    timer::{self, ClockId, TimeSpec},
+};
+use core::{
+    future::{self, Future},
+    pin::Pin,
+    task::Poll,
+};
+
+pub async fn sys_futex(
+    addr: usize,
+    op: usize,
+    value: usize,
+    timeout_ptr: *const u8,
+    addr2: usize,
+    value3: usize,
+) -> isize {
+    const FUTEX_WAIT: usize = 0;
+    const FUTEX_WAKE: usize = 1;
+    const FUTEX_REQUEUE: usize = 3;
+    const FUTEX_CMP_REQUEUE: usize = 4;
+    const FUTEX_WAIT_BITSET: usize = 9;
+    const FUTEX_WAKE_BITSET: usize = 10;
+    const FUTEX_PRIVATE_FLAG: usize = 128;
+    const FUTEX_CLOCK_REALTIME: usize = 256;
+
+    let private = op & FUTEX_PRIVATE_FLAG != 0;
+    let clock = if op & FUTEX_CLOCK_REALTIME != 0 {
+        ClockId::Realtime
+    } else {
+        ClockId::Monotonic
+    };
+    let cmd = op & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME);
+    if op & FUTEX_CLOCK_REALTIME != 0 && cmd != FUTEX_WAIT && cmd != FUTEX_WAIT_BITSET {
+        return -ENOSYS;
+    }
+
+    let current_task = task::get_processor().current();
+    let key = match FutexKey::new(&current_task, addr, private) {
+        Ok(key) => key,
+        Err(errno) => return -errno,
+    };
+    match cmd {
+        FUTEX_WAIT => {
+            let bitset = FUTEX_BITSET_MATCH_ANY;
+            futex_wait(key, addr, value as u32, bitset, timeout_ptr, None).await
+        }
+        FUTEX_WAIT_BITSET => match value3 as u32 {
+            0 => -EINVAL,
+            bitset => futex_wait(key, addr, value as u32, bitset, timeout_ptr, Some(clock)).await,
+        },
+        FUTEX_WAKE => sync::futex_wake(key, value, FUTEX_BITSET_MATCH_ANY) as isize,
+        FUTEX_WAKE_BITSET => match value3 as u32 {
+            0 => -EINVAL,
+            bitset => sync::futex_wake(key, value, bitset) as isize,
+        },
+        FUTEX_REQUEUE | FUTEX_CMP_REQUEUE => {
+            let to = match FutexKey::new(&current_task, addr2, private) {
+                Ok(key) => key,
+                Err(errno) => return -errno,
+            };
+            // the requeue count is passed in place of the timeout
+            let requeue_count = timeout_ptr as usize;
+            let expected = (cmd == FUTEX_CMP_REQUEUE).then_some((addr, value3 as u32));
+            match sync::futex_requeue(key, to, value, requeue_count, expected) {
+                Ok((woken, requeued)) if cmd == FUTEX_CMP_REQUEUE => (woken + requeued) as isize,
+                Ok((woken, _)) => woken as isize,
+                Err(errno) => -errno,
+            }
+        }
+        _ => -ENOSYS,
+    }
+}
+
+// a relative timeout, or an absolute one on `clock` if given
+async fn futex_wait(
+    key: FutexKey,
+    addr: usize,
+    expected: u32,
+    bitset: u32,
+    timeout_ptr: *const u8,
+    clock: Option<ClockId>,
+) -> isize {
+    let deadline = if timeout_ptr.is_null() {
+        None
+    } else {
+        let timeout = unsafe { *(timeout_ptr as *const TimeSpec) };
+        if !timeout.is_valid() {
+            return -EINVAL;
+        }
+        let now = timer::get_current_tick();
+        match clock {
+            Some(clock) => {
+                let clock_now = timer::get_clock_time(clock);
+                Some(now + timeout.saturating_sub(clock_now).to_ticks())
+            }
+            None => Some(now.saturating_add(timeout.to_ticks())),
+        }
+    };
+
+    // the waiter leaves the queue once `wait` is dropped
+    let mut wait = sync::futex_wait(key, addr, expected, bitset);
+    let mut sleep = deadline.map(timer::sleep_until);
+    let woken = future::poll_fn(|cx| {
+        if let Poll::Ready(result) = Pin::new(&mut wait).poll(cx) {
+            return Poll::Ready(result);
+        }
+        let timed_out = sleep
+            .as_mut()
+            .is_some_and(|sleep| Pin::new(sleep).poll(cx).is_ready());
+        if timed_out {
+            Poll::Ready(Err(ETIMEDOUT))
+        } else {
+            Poll::Pending
+        }
+    });
+    match task::interruptible(woken).await {
+        Ok(Ok(())) => 0,
+        Ok(Err(errno)) => -errno,
+        Err(Interrupted) => -EINTR,
+    }
+}
@@ -19,7 +19,7 @@ pub fn sys_sbrk(increase: i32) -> isize {
 
 pub fn sys_mmap(_: usize, _: usize, _: usize, _: usize, fd: usize, _: usize) -> isize {
     let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
+    let mut task_inner = current_task.inner();
     let ptr = task_inner.alloc_mmap(fd);
 
     ptr as isize
@@ -27,7 +27,7 @@ pub fn sys_mmap(_: usize, _: usize, _: usize, _: usize, fd: usize, _: usize) ->
 
 pub fn sys_munmap(start: usize) -> isize {
     let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
+    let mut task_inner = current_task.inner();
     task_inner.dealloc_mmap(start);
 
     0
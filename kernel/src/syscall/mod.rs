@@ -1,14 +1,28 @@
 use fs::*;
+use futex::*;
 use mm::*;
+use poll::*;
 use process::*;
+use signal::*;
 use system::*;
+use timer::*;
 
+use crate::task;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use log::error;
 
+pub mod errno;
 mod fs;
+mod futex;
 mod mm;
+mod poll;
 mod process;
+mod signal;
 mod system;
+mod timer;
 
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
@@ -19,6 +33,8 @@ const SYSCALL_BRK: usize = 214;
 const SYSCALL_GETPID: usize = 172;
 const SYSCALL_GETPPID: usize = 173;
 const SYSCALL_CLONE: usize = 220;
+const SYSCALL_SET_TID_ADDRESS: usize = 96;
+const SYSCALL_FUTEX: usize = 98;
 const SYSCALL_EXEC: usize = 221;
 const SYSCALL_WAITPID: usize = 260;
 const SYSCALL_GETCWD: usize = 17;
@@ -26,7 +42,16 @@ const SYSCALL_CHDIR: usize = 49;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_MKDIR: usize = 34;
+const SYSCALL_MKNODAT: usize = 33;
 const SYSCALL_PIPE: usize = 59;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_EVENTFD2: usize = 19;
+const SYSCALL_EPOLL_CREATE1: usize = 20;
+const SYSCALL_EPOLL_CTL: usize = 21;
+const SYSCALL_EPOLL_PWAIT: usize = 22;
+const SYSCALL_PSELECT6: usize = 72;
+const SYSCALL_PPOLL: usize = 73;
+const SYSCALL_SIGNALFD4: usize = 74;
 const SYSCALL_NANOSLEEP: usize = 101;
 const SYSCALL_MOUNT: usize = 40;
 const SYSCALL_UMOUNT: usize = 39;
@@ -39,27 +64,94 @@ const SYSCALL_GETDENTS: usize = 61;
 const SYSCALL_TIMES: usize = 153;
 const SYSCALL_MMAP: usize = 222;
 const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_RENAMEAT2: usize = 276;
+const SYSCALL_TRUNCATE: usize = 45;
+const SYSCALL_FTRUNCATE: usize = 46;
+const SYSCALL_SYNC: usize = 81;
+const SYSCALL_FSYNC: usize = 82;
+const SYSCALL_FDATASYNC: usize = 83;
+const SYSCALL_UTIMENSAT: usize = 88;
+const SYSCALL_CLOCK_SETTIME: usize = 112;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_CLOCK_NANOSLEEP: usize = 115;
+const SYSCALL_GETITIMER: usize = 102;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_TIMER_CREATE: usize = 107;
+const SYSCALL_TIMER_GETTIME: usize = 108;
+const SYSCALL_TIMER_GETOVERRUN: usize = 109;
+const SYSCALL_TIMER_SETTIME: usize = 110;
+const SYSCALL_TIMER_DELETE: usize = 111;
+const SYSCALL_TIMERFD_CREATE: usize = 85;
+const SYSCALL_TIMERFD_SETTIME: usize = 86;
+const SYSCALL_TIMERFD_GETTIME: usize = 87;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_RT_SIGACTION: usize = 134;
+const SYSCALL_RT_SIGPROCMASK: usize = 135;
+const SYSCALL_RT_SIGRETURN: usize = 139;
 
 pub fn syscall(id: usize, args: [usize; 6]) -> isize {
     match id {
-        SYSCALL_READ => sys_read(args[0], args[1] as *mut u8, args[2]),
-        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_READ => task::run_syscall(sys_read(args[0], args[1] as *mut u8, args[2])),
+        SYSCALL_WRITE => task::run_syscall(sys_write(args[0], args[1] as *const u8, args[2])),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
         SYSCALL_YIELD => sys_yield(),
         SYSCALL_GET_TIME => sys_get_time(args[0] as *mut u8, args[1]),
         SYSCALL_BRK => sys_brk(args[0] as i32),
         SYSCALL_GETPID => sys_getpid(),
         SYSCALL_GETPPID => sys_getppid(),
-        SYSCALL_CLONE => sys_clone(args[0], args[1]),
-        SYSCALL_EXEC => sys_exec(args[0] as *const u8, args[1] as *const u8),
-        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32, args[2]),
+        SYSCALL_CLONE => sys_clone(args[0], args[1], args[3], args[4]),
+        SYSCALL_SET_TID_ADDRESS => sys_set_tid_address(args[0]),
+        SYSCALL_FUTEX => task::run_syscall(sys_futex(
+            args[0],
+            args[1],
+            args[2],
+            args[3] as *const u8,
+            args[4],
+            args[5],
+        )),
+        SYSCALL_EXEC => sys_exec(
+            args[0] as *const u8,
+            args[1] as *const usize,
+            args[2] as *const usize,
+        ),
+        SYSCALL_WAITPID => {
+            task::run_syscall(sys_waitpid(args[0] as isize, args[1] as *mut i32, args[2]))
+        }
         SYSCALL_GETCWD => sys_getcwd(args[0] as *mut u8, args[1]),
         SYSCALL_CHDIR => sys_chdir(args[0] as *const u8),
-        SYSCALL_OPEN => sys_open(args[0] as i32, args[1] as *const u8, args[2]),
+        SYSCALL_OPEN => task::run_syscall(sys_open(args[0] as i32, args[1] as *const u8, args[2])),
         SYSCALL_CLOSE => sys_close(args[0]),
         SYSCALL_MKDIR => sys_mkdir(args[0], args[1] as *const u8, args[2]),
-        SYSCALL_PIPE => sys_pipe(args[0] as *mut i32),
-        SYSCALL_NANOSLEEP => sys_nanosleep(args[0] as *const u8, args[1]),
+        SYSCALL_MKNODAT => sys_mknodat(args[0], args[1] as *const u8, args[2], args[3]),
+        SYSCALL_PIPE => sys_pipe(args[0] as *mut i32, args[1]),
+        SYSCALL_FCNTL => sys_fcntl(args[0], args[1], args[2]),
+        SYSCALL_EVENTFD2 => sys_eventfd2(args[0], args[1]),
+        SYSCALL_EPOLL_CREATE1 => sys_epoll_create1(args[0]),
+        SYSCALL_EPOLL_CTL => sys_epoll_ctl(args[0], args[1], args[2], args[3] as *const u8),
+        SYSCALL_EPOLL_PWAIT => task::run_syscall(sys_epoll_pwait(
+            args[0],
+            args[1] as *mut u8,
+            args[2],
+            args[3] as i32,
+            args[4] as *const u8,
+        )),
+        SYSCALL_PSELECT6 => task::run_syscall(sys_pselect6(
+            args[0],
+            args[1] as *mut u8,
+            args[2] as *mut u8,
+            args[3] as *mut u8,
+            args[4] as *const u8,
+            args[5] as *const u8,
+        )),
+        SYSCALL_PPOLL => task::run_syscall(sys_ppoll(
+            args[0] as *mut u8,
+            args[1],
+            args[2] as *const u8,
+            args[3] as *const u8,
+        )),
+        SYSCALL_NANOSLEEP => {
+            task::run_syscall(sys_nanosleep(args[0] as *const u8, args[1] as *mut u8))
+        }
         SYSCALL_MOUNT => sys_mount(
             args[0] as *const u8,
             args[1] as *const u8,
@@ -75,6 +167,51 @@ pub fn syscall(id: usize, args: [usize; 6]) -> isize {
         SYSCALL_TIMES => sys_times(args[0] as *const u8),
         SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2], args[3], args[4], args[5]),
         SYSCALL_MUNMAP => sys_munmap(args[0]),
+        SYSCALL_RENAMEAT2 => sys_renameat2(
+            args[0],
+            args[1] as *const u8,
+            args[2],
+            args[3] as *const u8,
+            args[4],
+        ),
+        SYSCALL_TRUNCATE => sys_truncate(args[0] as *const u8, args[1]),
+        SYSCALL_FTRUNCATE => sys_ftruncate(args[0], args[1]),
+        SYSCALL_SYNC => sys_sync(),
+        SYSCALL_FSYNC | SYSCALL_FDATASYNC => sys_fsync(args[0]),
+        SYSCALL_UTIMENSAT => {
+            sys_utimensat(args[0], args[1] as *const u8, args[2] as *const u8, args[3])
+        }
+        SYSCALL_CLOCK_SETTIME => sys_clock_settime(args[0], args[1] as *const u8),
+        SYSCALL_CLOCK_GETTIME => sys_clock_gettime(args[0], args[1] as *mut u8),
+        SYSCALL_CLOCK_NANOSLEEP => task::run_syscall(sys_clock_nanosleep(
+            args[0],
+            args[1],
+            args[2] as *const u8,
+            args[3] as *mut u8,
+        )),
+        SYSCALL_GETITIMER => sys_getitimer(args[0], args[1] as *mut u8),
+        SYSCALL_SETITIMER => sys_setitimer(args[0], args[1] as *const u8, args[2] as *mut u8),
+        SYSCALL_TIMER_CREATE => sys_timer_create(args[0], args[1] as *const u8, args[2] as *mut u8),
+        SYSCALL_TIMER_GETTIME => sys_timer_gettime(args[0], args[1] as *mut u8),
+        SYSCALL_TIMER_GETOVERRUN => sys_timer_getoverrun(args[0]),
+        SYSCALL_TIMER_SETTIME => {
+            sys_timer_settime(args[0], args[1], args[2] as *const u8, args[3] as *mut u8)
+        }
+        SYSCALL_TIMER_DELETE => sys_timer_delete(args[0]),
+        SYSCALL_TIMERFD_CREATE => sys_timerfd_create(args[0], args[1]),
+        SYSCALL_TIMERFD_SETTIME => {
+            sys_timerfd_settime(args[0], args[1], args[2] as *const u8, args[3] as *mut u8)
+        }
+        SYSCALL_TIMERFD_GETTIME => sys_timerfd_gettime(args[0], args[1] as *mut u8),
+        SYSCALL_KILL => sys_kill(args[0] as isize, args[1]),
+        SYSCALL_RT_SIGACTION => sys_rt_sigaction(args[0], args[1] as *const u8, args[2] as *mut u8),
+        SYSCALL_RT_SIGPROCMASK => {
+            sys_rt_sigprocmask(args[0], args[1] as *const u8, args[2] as *mut u8)
+        }
+        SYSCALL_RT_SIGRETURN => sys_rt_sigreturn(),
+        SYSCALL_SIGNALFD4 => {
+            sys_signalfd4(args[0] as isize, args[1] as *const u8, args[2], args[3])
+        }
         _ => {
             error!("Unsupported syscall id: {}", id);
             sys_exit(-1);
@@ -93,3 +230,19 @@ pub fn translate_str<'a>(ptr: *const u8) -> &'a str {
     }
     path
 }
+
+/// Collect a NULL terminated array of C strings like argv, a null `ptr` is empty
+pub fn translate_str_array(ptr: *const usize) -> Vec<String> {
+    let mut strs = Vec::new();
+    if ptr.is_null() {
+        return strs;
+    }
+    unsafe {
+        let mut i = 0;
+        while *ptr.add(i) != 0 {
+            strs.push(translate_str(*ptr.add(i) as *const u8).to_string());
+            i += 1;
+        }
+    }
+    strs
+}
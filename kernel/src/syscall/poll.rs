@@ -0,0 +1,302 @@
+use crate::{
+    fs::{EpollEvent, EventPoll, File, OpenFlags, PollEvents},
+    syscall::errno::{EBADF, EINTR, EINVAL},
+    task::{self, Interrupted, SignalSet},
+    timer::{self, TimeSpec},
+};
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{
+    future::{self, Future},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+const FD_SETSIZE: usize = 1024;
+
+// region PollFd begin
+#[repr(C)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+// region PollFd end
+
+pub async fn sys_ppoll(
+    fds_ptr: *mut u8,
+    nfds: usize,
+    timeout_ptr: *const u8,
+    sigmask_ptr: *const u8,
+) -> isize {
+    let poll_fds = unsafe { core::slice::from_raw_parts_mut(fds_ptr as *mut PollFd, nfds) };
+    let deadline = match get_deadline(timeout_ptr) {
+        Some(deadline) => deadline,
+        None => return -EINVAL,
+    };
+
+    // a negative fd is skipped, a closed one reports POLLNVAL
+    let files: Vec<Option<Arc<dyn File + Send + Sync>>> = {
+        let current_task = task::get_processor().current();
+        let task_inner = current_task.inner();
+        poll_fds
+            .iter()
+            .map(|poll_fd| match poll_fd.fd {
+                fd if fd < 0 => None,
+                fd => task_inner.find_fd(fd as usize),
+            })
+            .collect()
+    };
+    set_temporary_mask(sigmask_ptr);
+
+    let ready = wait_ready(deadline, |cx| {
+        let mut count = 0;
+        for (poll_fd, file) in poll_fds.iter_mut().zip(files.iter()) {
+            let events = PollEvents::from_bits_truncate(poll_fd.events as u16);
+            let revents = match file {
+                _ if poll_fd.fd < 0 => PollEvents::empty(),
+                // errors and hangups are reported even if not asked for
+                Some(file) => {
+                    file.poll_events(events, cx) & (events | PollEvents::ERR | PollEvents::HUP)
+                }
+                None => PollEvents::NVAL,
+            };
+            poll_fd.revents = revents.bits() as i16;
+            if !revents.is_empty() {
+                count += 1;
+            }
+        }
+        count
+    });
+    match ready.await {
+        Ok(count) => count as isize,
+        Err(Interrupted) => -EINTR,
+    }
+}
+
+pub async fn sys_pselect6(
+    nfds: usize,
+    read_fds_ptr: *mut u8,
+    write_fds_ptr: *mut u8,
+    except_fds_ptr: *mut u8,
+    timeout_ptr: *const u8,
+    sigmask_ptr: *const u8,
+) -> isize {
+    if nfds > FD_SETSIZE {
+        return -EINVAL;
+    }
+    let deadline = match get_deadline(timeout_ptr) {
+        Some(deadline) => deadline,
+        None => return -EINVAL,
+    };
+
+    // fd_set is a bitmap of longs, only the words covering nfds are touched
+    let words = nfds.div_ceil(64);
+    let mut fd_sets = [read_fds_ptr, write_fds_ptr, except_fds_ptr].map(|ptr| {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { core::slice::from_raw_parts_mut(ptr as *mut u64, words) })
+        }
+    });
+
+    // the fds any set asks about, and which of the three sets do
+    let mut watched = Vec::new();
+    {
+        let current_task = task::get_processor().current();
+        let task_inner = current_task.inner();
+        for fd in 0..nfds {
+            let asked = fd_sets.each_ref().map(|fd_set| {
+                fd_set
+                    .as_ref()
+                    .is_some_and(|fd_set| fd_set[fd / 64] & (1 << (fd % 64)) != 0)
+            });
+            if !asked.contains(&true) {
+                continue;
+            }
+            match task_inner.find_fd(fd) {
+                Some(file) => watched.push((fd, file, asked)),
+                None => return -EBADF,
+            }
+        }
+    }
+    // the structure holds the mask pointer and its size
+    let sigmask_ptr = if sigmask_ptr.is_null() {
+        sigmask_ptr
+    } else {
+        unsafe { *(sigmask_ptr as *const *const u8) }
+    };
+    set_temporary_mask(sigmask_ptr);
+
+    let mut ready_sets = [vec![0u64; words], vec![0u64; words], vec![0u64; words]];
+    let ready = wait_ready(deadline, |cx| {
+        ready_sets
+            .iter_mut()
+            .for_each(|ready_set| ready_set.fill(0));
+        let mut count = 0;
+        for (fd, file, asked) in watched.iter() {
+            let mut events = PollEvents::empty();
+            events.set(PollEvents::IN, asked[0]);
+            events.set(PollEvents::OUT, asked[1]);
+            events.set(PollEvents::PRI, asked[2]);
+            let revents = file.poll_events(events, cx);
+
+            // like Linux, an error counts as readable and writable
+            let hits = [
+                revents.intersects(PollEvents::IN | PollEvents::HUP | PollEvents::ERR),
+                revents.intersects(PollEvents::OUT | PollEvents::ERR),
+                revents.contains(PollEvents::PRI),
+            ];
+            for (index, ready_set) in ready_sets.iter_mut().enumerate() {
+                if asked[index] && hits[index] {
+                    ready_set[fd / 64] |= 1 << (fd % 64);
+                    count += 1;
+                }
+            }
+        }
+        count
+    });
+    let count = match ready.await {
+        Ok(count) => count,
+        Err(Interrupted) => return -EINTR,
+    };
+
+    // the sets are left with the ready fds only
+    for (fd_set, ready_set) in fd_sets.iter_mut().zip(ready_sets.iter()) {
+        if let Some(fd_set) = fd_set {
+            fd_set.copy_from_slice(ready_set);
+        }
+    }
+    count as isize
+}
+
+pub fn sys_epoll_create1(flags: usize) -> isize {
+    let flags = match OpenFlags::from_bits(flags as u32) {
+        Some(flags) if (flags - OpenFlags::CLOEXEC).is_empty() => flags,
+        _ => return -EINVAL,
+    };
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let fd = task_inner.alloc_fd(Arc::new(EventPoll::new()));
+    task_inner.set_cloexec(fd, flags.cloexec());
+    fd as isize
+}
+
+pub fn sys_epoll_ctl(epoll_fd: usize, op: usize, fd: usize, event_ptr: *const u8) -> isize {
+    const EPOLL_CTL_ADD: usize = 1;
+    const EPOLL_CTL_DEL: usize = 2;
+    const EPOLL_CTL_MOD: usize = 3;
+
+    let (epoll_file, file) = {
+        let current_task = task::get_processor().current();
+        let task_inner = current_task.inner();
+        match (task_inner.find_fd(epoll_fd), task_inner.find_fd(fd)) {
+            (Some(epoll_file), Some(file)) => (epoll_file, file),
+            _ => return -EBADF,
+        }
+    };
+    let epoll = match epoll_file.as_event_poll() {
+        Some(epoll) if epoll_fd != fd => epoll,
+        _ => return -EINVAL,
+    };
+
+    let result = match op {
+        EPOLL_CTL_ADD => epoll.add(fd, &file, unsafe { *(event_ptr as *const EpollEvent) }),
+        EPOLL_CTL_MOD => epoll.modify(fd, &file, unsafe { *(event_ptr as *const EpollEvent) }),
+        EPOLL_CTL_DEL => epoll.delete(fd),
+        _ => Err(EINVAL),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(errno) => -errno,
+    }
+}
+
+pub async fn sys_epoll_pwait(
+    epoll_fd: usize,
+    events_ptr: *mut u8,
+    max_events: usize,
+    timeout: i32,
+    sigmask_ptr: *const u8,
+) -> isize {
+    if max_events as i32 <= 0 {
+        return -EINVAL;
+    }
+    let epoll_file = match task::get_processor().current().inner().find_fd(epoll_fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+    let epoll = match epoll_file.as_event_poll() {
+        Some(epoll) => epoll,
+        None => return -EINVAL,
+    };
+    let events =
+        unsafe { core::slice::from_raw_parts_mut(events_ptr as *mut EpollEvent, max_events) };
+
+    // in milliseconds, negative waits forever
+    let deadline = usize::try_from(timeout).ok().map(|timeout| {
+        let timeout = TimeSpec::from_nsec(timeout * 1_000_000);
+        timer::get_current_tick().saturating_add(timeout.to_ticks())
+    });
+    set_temporary_mask(sigmask_ptr);
+
+    let ready = wait_ready(deadline, |cx| match epoll.poll_wait(events, cx) {
+        Poll::Ready(count) => count,
+        Poll::Pending => 0,
+    });
+    match ready.await {
+        Ok(count) => count as isize,
+        Err(Interrupted) => -EINTR,
+    }
+}
+
+// the deadline in ticks, Some(None) for a null timeout that waits forever
+fn get_deadline(timeout_ptr: *const u8) -> Option<Option<usize>> {
+    if timeout_ptr.is_null() {
+        return Some(None);
+    }
+    let timeout = unsafe { *(timeout_ptr as *const TimeSpec) };
+    if !timeout.is_valid() {
+        return None;
+    }
+    Some(Some(
+        timer::get_current_tick().saturating_add(timeout.to_ticks()),
+    ))
+}
+
+// blocked while waiting, the old mask is back once the task returns to user
+fn set_temporary_mask(sigmask_ptr: *const u8) {
+    if sigmask_ptr.is_null() {
+        return;
+    }
+    let mask = unsafe { *(sigmask_ptr as *const SignalSet) };
+    let current_task = task::get_processor().current();
+    current_task
+        .inner()
+        .get_signals_mut()
+        .set_temporary_blocked(mask);
+}
+
+/// Poll `ready` until it counts something ready, the deadline passes with 0
+/// or a signal is to be handled
+async fn wait_ready<F>(deadline: Option<usize>, mut ready: F) -> Result<usize, Interrupted>
+where
+    F: FnMut(&mut Context<'_>) -> usize,
+{
+    let mut sleep = deadline.map(timer::sleep_until);
+    let wait = future::poll_fn(|cx| {
+        let count = ready(cx);
+        if count > 0 {
+            return Poll::Ready(count);
+        }
+        let timed_out = sleep
+            .as_mut()
+            .is_some_and(|sleep| Pin::new(sleep).poll(cx).is_ready());
+        if timed_out {
+            Poll::Ready(0)
+        } else {
+            Poll::Pending
+        }
+    });
+    task::interruptible(wait).await
+}
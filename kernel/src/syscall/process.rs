@@ -1,11 +1,14 @@
 use crate::{
-    fs::{self, File, Inode, OpenFlags, PathUtil},
-    syscall::translate_str,
-    task::{self, Tms},
-    timer::{self, TimeVal},
+    fs::PathUtil,
+    mm::{self, ElfReader},
+    syscall::errno::{EINTR, EINVAL, ELOOP, ENOEXEC},
+    syscall::{translate_str, translate_str_array},
+    task::{self, Interrupted, Tms},
+    timer::{self, ClockId, TimeSpec},
 };
+use alloc::string::{String, ToString};
 use alloc::vec;
-use alloc::{string::ToString, vec::Vec};
+use core::{future, task::Poll};
 
 pub fn sys_exit(exit_code: i32) -> ! {
     task::get_processor().exit_current(exit_code);
@@ -15,16 +18,53 @@ pub fn sys_yield() -> isize {
     task::get_processor().schedule();
 }
 
-pub fn sys_nanosleep(req_ptr: *const u8, _rem_ptr: usize) -> isize {
-    let now = timer::get_current_time();
-    let wait_until = unsafe { *(req_ptr as *const TimeVal) } + now;
-    loop {
-        let current_time = timer::get_current_time();
-        if current_time >= wait_until {
-            break;
+pub async fn sys_nanosleep(req_ptr: *const u8, rem_ptr: *mut u8) -> isize {
+    clock_sleep(ClockId::Monotonic, 0, req_ptr, rem_ptr).await
+}
+
+pub async fn sys_clock_nanosleep(
+    clock_id: usize,
+    flags: usize,
+    req_ptr: *const u8,
+    rem_ptr: *mut u8,
+) -> isize {
+    let clock = match ClockId::from_raw(clock_id) {
+        Some(clock) => clock,
+        None => return -EINVAL,
+    };
+    clock_sleep(clock, flags, req_ptr, rem_ptr).await
+}
+
+async fn clock_sleep(clock: ClockId, flags: usize, req_ptr: *const u8, rem_ptr: *mut u8) -> isize {
+    const TIMER_ABSTIME: usize = 1;
+
+    let req = unsafe { *(req_ptr as *const TimeSpec) };
+    if !req.is_valid() {
+        return -EINVAL;
+    }
+
+    let now = timer::get_current_tick();
+    let deadline = if flags & TIMER_ABSTIME != 0 {
+        // taken against the clock now, a later clock_settime is not followed
+        let clock_now = timer::get_clock_time(clock);
+        now + req.saturating_sub(clock_now).to_ticks()
+    } else {
+        now.saturating_add(req.to_ticks())
+    };
+
+    match task::interruptible(timer::sleep_until(deadline)).await {
+        Ok(()) => 0,
+        Err(Interrupted) => {
+            // an absolute sleep is simply restarted with the same request
+            if flags & TIMER_ABSTIME == 0 && !rem_ptr.is_null() {
+                let left = deadline.saturating_sub(timer::get_current_tick());
+                unsafe {
+                    *(rem_ptr as *mut TimeSpec) = TimeSpec::from_ticks(left);
+                }
+            }
+            -EINTR
         }
     }
-    0
 }
 
 pub fn sys_getpid() -> isize {
@@ -37,113 +77,176 @@ pub fn sys_getppid() -> isize {
     parent_pid as isize
 }
 
-pub fn sys_clone(flags: usize, sp: usize) -> isize {
-    const SIGCHLD: usize = 17;
+pub fn sys_clone(flags: usize, sp: usize, tls: usize, child_tid: usize) -> isize {
+    const CLONE_VM: usize = 0x0000_0100;
+    const CLONE_THREAD: usize = 0x0001_0000;
+    const CLONE_SETTLS: usize = 0x0008_0000;
+    const CLONE_CHILD_CLEARTID: usize = 0x0020_0000;
+
+    // the child always gets a copy of the address space, so threads are not
+    // supported
+    if flags & (CLONE_VM | CLONE_THREAD) != 0 {
+        return -EINVAL;
+    }
 
     let current_task = task::get_processor().current();
     let new_task = current_task.fork();
     let new_pid = new_task.get_pid();
-    if flags != SIGCHLD || sp != 0 {
+    if sp != 0 {
         new_task.get_trap_cx_mut().set_sp(sp);
     }
+    if flags & CLONE_SETTLS != 0 {
+        new_task.get_trap_cx_mut().set_tp(tls);
+    }
+    if flags & CLONE_CHILD_CLEARTID != 0 {
+        new_task.inner().set_clear_child_tid(child_tid);
+    }
     new_task.get_trap_cx_mut().set_a0(0);
     task::add_task(new_task);
 
     new_pid as isize
 }
 
-pub fn sys_exec(path_ptr: *const u8, _argv: *const u8) -> isize {
-    let path = translate_str(path_ptr);
-    let path = PathUtil::from_user(path).to_string();
-    let entry = fs::open_file(&path, OpenFlags::RDONLY);
+pub fn sys_set_tid_address(tid_ptr: usize) -> isize {
+    let current_task = task::get_processor().current();
+    current_task.inner().set_clear_child_tid(tid_ptr);
+    current_task.get_pid() as isize
+}
+
+pub fn sys_exec(path_ptr: *const u8, argv_ptr: *const usize, envp_ptr: *const usize) -> isize {
+    // how many #! interpreters may in turn be scripts, as on Linux
+    const SHEBANG_MAX_DEPTH: usize = 4;
 
-    if let Some(entry) = entry {
-        // get target file
-        let len = entry.size();
-        let file = entry.to_file();
+    // the strings are copied out before the old image goes away
+    let mut path = translate_str(path_ptr).to_string();
+    let mut args = translate_str_array(argv_ptr);
+    let envs = translate_str_array(envp_ptr);
+
+    let mut depth = 0;
+    loop {
+        // only the headers are read here, the segments go straight to user memory
+        let elf = match ElfReader::open(&PathUtil::from_user(&path).to_string()) {
+            Ok(elf) => elf,
+            Err(errno) => return -errno,
+        };
+
+        // a script is run by its interpreter, with the script path in place of argv[0]
+        match parse_shebang(elf.head()) {
+            Ok(Some((interp, arg))) => {
+                depth += 1;
+                if depth > SHEBANG_MAX_DEPTH {
+                    return -ELOOP;
+                }
+                let mut interp_args = vec![interp.clone()];
+                interp_args.extend(arg);
+                interp_args.push(path);
+                interp_args.extend(args.into_iter().skip(1));
+                args = interp_args;
+                path = interp;
+                continue;
+            }
+            Ok(None) => {}
+            Err(errno) => return -errno,
+        }
 
-        // prepare mut buffer
-        let mut buffer: Vec<u8> = vec![0; len];
-        let buffer = buffer.as_mut_slice();
-        file.read(buffer);
+        // a dynamically linked program needs its interpreter as well
+        let interp = match mm::get_elf_interp(&elf) {
+            Ok(Some(interp_path)) => match ElfReader::open(&interp_path) {
+                Ok(interp) => Some(interp),
+                Err(errno) => return -errno,
+            },
+            Ok(None) => None,
+            Err(errno) => return -errno,
+        };
 
         // execute task
         let current_task = task::get_processor().current();
-        current_task.exec(buffer);
-        0
-    } else {
-        -1
+        return match current_task.exec(&elf, interp.as_ref(), &args, &envs) {
+            Ok(()) => 0,
+            Err(errno) => -errno,
+        };
     }
 }
 
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, _option: usize) -> isize {
-    let current_task = task::get_processor().current();
-    let mut task_inner = current_task.inner_mut();
-    let children = task_inner.get_children_mut();
-
-    // pid not found
-    if !children
-        .iter()
-        .any(|child| child.get_pid() == pid as usize || pid == -1)
-    {
-        return -1;
+// the interpreter and its optional argument on a #! line, see execve(2)
+fn parse_shebang(data: &[u8]) -> Result<Option<(String, Option<String>)>, isize> {
+    // no more of the line is looked at, like BINPRM_BUF_SIZE
+    const SHEBANG_MAX_LEN: usize = 256;
+    const BLANK: [char; 2] = [' ', '\t'];
+
+    let Some(line) = data.strip_prefix(b"#!") else {
+        return Ok(None);
+    };
+    let line = &line[..line.len().min(SHEBANG_MAX_LEN)];
+    let line = line.split(|&c| c == b'\n').next().unwrap();
+    let line = core::str::from_utf8(line).map_err(|_| ENOEXEC)?;
+
+    // the rest of the line is a single argument, blanks and all
+    let line = line.trim_matches(BLANK);
+    let (interp, arg) = match line.split_once(BLANK) {
+        Some((interp, arg)) => (interp, Some(arg.trim_start_matches(BLANK).to_string())),
+        None => (line, None),
+    };
+    if interp.is_empty() {
+        return Err(ENOEXEC);
     }
+    Ok(Some((interp.to_string(), arg)))
+}
 
-    if let Some(child) = children
-        .iter()
-        .find(|child| (child.get_pid() == pid as usize || pid == -1) && child.is_zombie())
-    {
-        // child is zombie
-        let pid = child.get_pid();
-        let exit_code = child.get_exit_code();
-        if !exit_code_ptr.is_null() {
-            unsafe {
-                match exit_code {
-                    0 => {
-                        *exit_code_ptr = exit_code;
-                    }
-                    _ => {
-                        *exit_code_ptr = exit_code << 8;
-                    }
-                }
-            }
-        }
+pub async fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, _option: usize) -> isize {
+    future::poll_fn(|cx| {
+        let current_task = task::get_processor().current();
+        let mut task_inner = current_task.inner();
+        let children = task_inner.get_children_mut();
 
-        // read tms
-        let (cutime_inc, cstime_inc): (usize, usize);
+        // pid not found
+        if !children
+            .iter()
+            .any(|child| child.get_pid() == pid as usize || pid == -1)
         {
-            let child_inner = child.inner();
-            cutime_inc =
-                child_inner.get_tms_ref().get_utime() + child_inner.get_tms_ref().get_cutime();
-            cstime_inc =
-                child_inner.get_tms_ref().get_stime() + child_inner.get_tms_ref().get_cstime();
-            drop(child_inner);
+            return Poll::Ready(-1);
         }
 
-        children.retain(|c| c.get_pid() != pid);
-
-        // update tms
+        if let Some(child) = children
+            .iter()
+            .find(|child| (child.get_pid() == pid as usize || pid == -1) && child.is_zombie())
         {
-            task_inner.get_tms_mut().add_cstime(cstime_inc);
-            task_inner.get_tms_mut().add_cutime(cutime_inc);
-        }
+            // child is zombie
+            let pid = child.get_pid();
+            if !exit_code_ptr.is_null() {
+                unsafe {
+                    *exit_code_ptr = child.get_wait_status();
+                }
+            }
 
-        pid as isize
-    } else {
-        // child is not zombie
-        let pid = if pid == -1 {
-            children
-                .iter()
-                .find(|child| !child.is_zombie())
-                .unwrap()
-                .get_pid()
+            // read tms
+            let (cutime_inc, cstime_inc): (usize, usize);
+            {
+                let child_inner = child.inner();
+                cutime_inc =
+                    child_inner.get_tms_ref().get_utime() + child_inner.get_tms_ref().get_cutime();
+                cstime_inc =
+                    child_inner.get_tms_ref().get_stime() + child_inner.get_tms_ref().get_cstime();
+                drop(child_inner);
+            }
+
+            children.retain(|c| c.get_pid() != pid);
+
+            // update tms
+            {
+                task_inner.get_tms_mut().add_cstime(cstime_inc);
+                task_inner.get_tms_mut().add_cutime(cutime_inc);
+            }
+
+            Poll::Ready(pid as isize)
         } else {
-            pid as usize
-        };
-        drop(task_inner);
-        current_task.get_trap_cx_mut().move_to_prev_ins();
-        task::get_processor().wait_for_child(pid);
-    }
+            // child is not zombie, polled again once a child exits
+            drop(task_inner);
+            current_task.get_child_exit().register(cx.waker());
+            Poll::Pending
+        }
+    })
+    .await
 }
 
 pub fn sys_times(buf: *const u8) -> isize {
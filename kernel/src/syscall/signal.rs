@@ -0,0 +1,121 @@
+use crate::{
+    fs::{OpenFlags, SignalFd},
+    syscall::errno::{EBADF, EINVAL, ESRCH},
+    task::{self, SigAction, SignalSet, SIGKILL, SIGSTOP},
+};
+use alloc::sync::Arc;
+
+pub fn sys_kill(pid: isize, signo: usize) -> isize {
+    if signo != 0 && !task::is_valid_signal(signo) {
+        return -EINVAL;
+    }
+    // process groups are not supported, so no process matches
+    if pid <= 0 {
+        return -ESRCH;
+    }
+    let pcb = match task::find_process(pid as usize) {
+        Some(pcb) => pcb,
+        None => return -ESRCH,
+    };
+    // signal 0 only checks that the process exists
+    if signo != 0 && !pcb.is_zombie() {
+        task::send_signal(&pcb, signo);
+    }
+    0
+}
+
+pub fn sys_rt_sigaction(signo: usize, act_ptr: *const u8, old_act_ptr: *mut u8) -> isize {
+    if !task::is_valid_signal(signo) {
+        return -EINVAL;
+    }
+    // SIGKILL and SIGSTOP could not be caught or ignored
+    if !act_ptr.is_null() && (signo == SIGKILL || signo == SIGSTOP) {
+        return -EINVAL;
+    }
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let signals = task_inner.get_signals_mut();
+    if !old_act_ptr.is_null() {
+        unsafe {
+            *(old_act_ptr as *mut SigAction) = signals.get_action(signo);
+        }
+    }
+    if !act_ptr.is_null() {
+        let act = unsafe { *(act_ptr as *const SigAction) };
+        signals.set_action(signo, act);
+    }
+    0
+}
+
+pub fn sys_rt_sigprocmask(how: usize, set_ptr: *const u8, old_set_ptr: *mut u8) -> isize {
+    const SIG_BLOCK: usize = 0;
+    const SIG_UNBLOCK: usize = 1;
+    const SIG_SETMASK: usize = 2;
+
+    // a bad `how` leaves the old set unwritten
+    if !set_ptr.is_null() && ![SIG_BLOCK, SIG_UNBLOCK, SIG_SETMASK].contains(&how) {
+        return -EINVAL;
+    }
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let signals = task_inner.get_signals_mut();
+    let old = signals.get_blocked();
+    if !old_set_ptr.is_null() {
+        unsafe {
+            *(old_set_ptr as *mut SignalSet) = old;
+        }
+    }
+    if set_ptr.is_null() {
+        return 0;
+    }
+
+    let set = unsafe { *(set_ptr as *const SignalSet) };
+    let blocked = match how {
+        SIG_BLOCK => old.union(set),
+        SIG_UNBLOCK => old.difference(set),
+        _ => set,
+    };
+    signals.set_blocked(blocked);
+    0
+}
+
+pub fn sys_rt_sigreturn() -> isize {
+    task::return_from_handler() as isize
+}
+
+pub fn sys_signalfd4(fd: isize, mask_ptr: *const u8, mask_size: usize, flags: usize) -> isize {
+    if mask_size != size_of::<SignalSet>() {
+        return -EINVAL;
+    }
+    let flags = match OpenFlags::from_bits(flags as u32) {
+        Some(flags) if (flags - OpenFlags::NONBLOCK - OpenFlags::CLOEXEC).is_empty() => flags,
+        _ => return -EINVAL,
+    };
+    // SIGKILL and SIGSTOP are never taken by a signalfd
+    let mut mask = unsafe { *(mask_ptr as *const SignalSet) };
+    mask.remove(SIGKILL);
+    mask.remove(SIGSTOP);
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    // an existing signalfd only gets the new mask
+    if fd != -1 {
+        let file = match task_inner.find_fd(fd as usize) {
+            Some(file) => file,
+            None => return -EBADF,
+        };
+        return match file.as_signal_fd() {
+            Some(signal_fd) => {
+                signal_fd.set_mask(mask);
+                fd
+            }
+            None => -EINVAL,
+        };
+    }
+
+    let fd = task_inner.alloc_fd(Arc::new(SignalFd::new(mask, flags.nonblock())));
+    task_inner.set_cloexec(fd, flags.cloexec());
+    fd as isize
+}
@@ -1,8 +1,11 @@
-use crate::timer::{self, TimeVal};
+use crate::{
+    syscall::errno::{EINVAL, EPERM},
+    timer::{self, ClockId, TimeSpec, TimeVal},
+};
 
 pub fn sys_get_time(ts_ptr: *mut u8, _tz: usize) -> isize {
     let ts_ptr = ts_ptr as *mut TimeVal;
-    let now = timer::get_current_time();
+    let now = timer::get_wall_time().to_time_val();
     unsafe {
         *ts_ptr = now;
     }
@@ -53,3 +56,33 @@ pub fn sys_uname(uts_name_ptr: *const u8) -> isize {
     }
     0
 }
+
+pub fn sys_clock_gettime(clock_id: usize, ts_ptr: *mut u8) -> isize {
+    let clock = match ClockId::from_raw(clock_id) {
+        Some(clock) => clock,
+        None => return -EINVAL,
+    };
+    let ts_ptr = ts_ptr as *mut TimeSpec;
+    unsafe {
+        *ts_ptr = timer::get_clock_time(clock);
+    }
+    0
+}
+
+pub fn sys_clock_settime(clock_id: usize, ts_ptr: *const u8) -> isize {
+    // only the wall clock could be set
+    match ClockId::from_raw(clock_id) {
+        Some(ClockId::Realtime) => {}
+        Some(_) => return -EPERM,
+        None => return -EINVAL,
+    }
+    let time = unsafe { *(ts_ptr as *const TimeSpec) };
+    if !time.is_valid() {
+        return -EINVAL;
+    }
+    // the wall clock could not go before boot
+    match timer::set_wall_time(time) {
+        Ok(_) => 0,
+        Err(_) => -EINVAL,
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    fs::{OpenFlags, TimerFd},
+    syscall::errno::{EAGAIN, EBADF, EINVAL},
+    task::{
+        self, ITimerSpec, ITimerVal, IntervalTimer, PosixTimer, ProcessControlBlock, ITIMER_PROF,
+        ITIMER_REAL, ITIMER_VIRTUAL, SIGALRM,
+    },
+    timer::{self, ClockId},
+};
+use alloc::sync::Arc;
+
+pub fn sys_getitimer(which: usize, value_ptr: *mut u8) -> isize {
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let timers = task_inner.get_timers_mut();
+    let value = match which {
+        ITIMER_REAL => timers.get_real().map_or((0, 0), |timer| timer.get()),
+        ITIMER_VIRTUAL => timers.get_virtual_mut().get(),
+        ITIMER_PROF => timers.get_prof_mut().get(),
+        _ => return -EINVAL,
+    };
+    unsafe {
+        *(value_ptr as *mut ITimerVal) = ITimerVal::from_ticks(value);
+    }
+    0
+}
+
+pub fn sys_setitimer(which: usize, value_ptr: *const u8, old_value_ptr: *mut u8) -> isize {
+    let (value, interval) = unsafe { *(value_ptr as *const ITimerVal) }.to_ticks();
+
+    let current_task = task::get_processor().current();
+    let old = match which {
+        ITIMER_REAL => {
+            let deadline = (value != 0).then(|| timer::get_current_tick() + value);
+            real_timer(&current_task).set(deadline, interval)
+        }
+        ITIMER_VIRTUAL => current_task
+            .inner()
+            .get_timers_mut()
+            .get_virtual_mut()
+            .set(value, interval),
+        ITIMER_PROF => current_task
+            .inner()
+            .get_timers_mut()
+            .get_prof_mut()
+            .set(value, interval),
+        _ => return -EINVAL,
+    };
+    if !old_value_ptr.is_null() {
+        unsafe {
+            *(old_value_ptr as *mut ITimerVal) = ITimerVal::from_ticks(old);
+        }
+    }
+    0
+}
+
+// ITIMER_REAL is created on first use, it needs the Arc of the process
+fn real_timer(pcb: &Arc<ProcessControlBlock>) -> Arc<IntervalTimer> {
+    let mut inner = pcb.inner();
+    if let Some(timer) = inner.get_timers_ref().get_real() {
+        return timer.clone();
+    }
+    let timer = IntervalTimer::signaling(Arc::downgrade(pcb), Some(SIGALRM));
+    inner.get_timers_mut().set_real(timer.clone());
+    timer
+}
+
+// region SigEvent begin
+/// The head of struct sigevent
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SigEvent {
+    _value: usize,
+    signo: i32,
+    notify: i32,
+}
+// region SigEvent end
+
+pub fn sys_timer_create(clock_id: usize, event_ptr: *const u8, timer_id_ptr: *mut u8) -> isize {
+    const SIGEV_SIGNAL: i32 = 0;
+    const SIGEV_NONE: i32 = 1;
+
+    let clock = match ClockId::from_raw(clock_id) {
+        Some(clock) => clock,
+        None => return -EINVAL,
+    };
+    // a null sigevent means SIGALRM
+    let signo = if event_ptr.is_null() {
+        Some(SIGALRM)
+    } else {
+        let event = unsafe { *(event_ptr as *const SigEvent) };
+        match event.notify {
+            SIGEV_SIGNAL if task::is_valid_signal(event.signo as usize) => {
+                Some(event.signo as usize)
+            }
+            SIGEV_NONE => None,
+            _ => return -EINVAL,
+        }
+    };
+
+    let current_task = task::get_processor().current();
+    let timer = IntervalTimer::signaling(Arc::downgrade(&current_task), signo);
+    let timer_id = current_task
+        .inner()
+        .get_timers_mut()
+        .insert_posix(PosixTimer::new(clock, timer));
+    let Some(timer_id) = timer_id else {
+        return -EAGAIN;
+    };
+    unsafe {
+        *(timer_id_ptr as *mut i32) = timer_id as i32;
+    }
+    0
+}
+
+pub fn sys_timer_settime(
+    timer_id: usize,
+    flags: usize,
+    value_ptr: *const u8,
+    old_value_ptr: *mut u8,
+) -> isize {
+    let value = unsafe { *(value_ptr as *const ITimerSpec) };
+    if !value.is_valid() {
+        return -EINVAL;
+    }
+    let (clock, timer) = match find_posix_timer(timer_id) {
+        Some(found) => found,
+        None => return -EINVAL,
+    };
+
+    let deadline = get_deadline(clock, flags, &value);
+    let interval = value.get_interval().to_ticks();
+    let old = timer.set(deadline, interval);
+    if !old_value_ptr.is_null() {
+        unsafe {
+            *(old_value_ptr as *mut ITimerSpec) = ITimerSpec::from_ticks(old);
+        }
+    }
+    0
+}
+
+pub fn sys_timer_gettime(timer_id: usize, value_ptr: *mut u8) -> isize {
+    let (_, timer) = match find_posix_timer(timer_id) {
+        Some(found) => found,
+        None => return -EINVAL,
+    };
+    unsafe {
+        *(value_ptr as *mut ITimerSpec) = ITimerSpec::from_ticks(timer.get());
+    }
+    0
+}
+
+pub fn sys_timer_getoverrun(timer_id: usize) -> isize {
+    match find_posix_timer(timer_id) {
+        Some((_, timer)) => timer.get_overrun() as isize,
+        None => -EINVAL,
+    }
+}
+
+pub fn sys_timer_delete(timer_id: usize) -> isize {
+    let current_task = task::get_processor().current();
+    let removed = current_task.inner().get_timers_mut().remove_posix(timer_id);
+    match removed {
+        Some(_) => 0,
+        None => -EINVAL,
+    }
+}
+
+pub fn sys_timerfd_create(clock_id: usize, flags: usize) -> isize {
+    let clock = match ClockId::from_raw(clock_id) {
+        Some(clock) => clock,
+        None => return -EINVAL,
+    };
+    let flags = match OpenFlags::from_bits(flags as u32) {
+        Some(flags) if (flags - OpenFlags::NONBLOCK - OpenFlags::CLOEXEC).is_empty() => flags,
+        _ => return -EINVAL,
+    };
+
+    let current_task = task::get_processor().current();
+    let mut task_inner = current_task.inner();
+    let fd = task_inner.alloc_fd(Arc::new(TimerFd::new(clock, flags.nonblock())));
+    task_inner.set_cloexec(fd, flags.cloexec());
+    fd as isize
+}
+
+pub fn sys_timerfd_settime(
+    fd: usize,
+    flags: usize,
+    value_ptr: *const u8,
+    old_value_ptr: *mut u8,
+) -> isize {
+    let value = unsafe { *(value_ptr as *const ITimerSpec) };
+    if !value.is_valid() {
+        return -EINVAL;
+    }
+    let file = match task::get_processor().current().inner().find_fd(fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+    let Some(timer_fd) = file.as_timer_fd() else {
+        return -EINVAL;
+    };
+
+    let deadline = get_deadline(timer_fd.get_clock(), flags, &value);
+    let old = timer_fd.set(deadline, value.get_interval().to_ticks());
+    if !old_value_ptr.is_null() {
+        unsafe {
+            *(old_value_ptr as *mut ITimerSpec) = ITimerSpec::from_ticks(old);
+        }
+    }
+    0
+}
+
+pub fn sys_timerfd_gettime(fd: usize, value_ptr: *mut u8) -> isize {
+    let file = match task::get_processor().current().inner().find_fd(fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+    let Some(timer_fd) = file.as_timer_fd() else {
+        return -EINVAL;
+    };
+    unsafe {
+        *(value_ptr as *mut ITimerSpec) = ITimerSpec::from_ticks(timer_fd.get());
+    }
+    0
+}
+
+fn find_posix_timer(timer_id: usize) -> Option<(ClockId, Arc<IntervalTimer>)> {
+    let current_task = task::get_processor().current();
+    let task_inner = current_task.inner();
+    let posix = task_inner.get_timers_ref().find_posix(timer_id)?;
+    Some((posix.get_clock(), posix.get_timer().clone()))
+}
+
+// the deadline in ticks `value` arms a timer at, None if it disarms it
+fn get_deadline(clock: ClockId, flags: usize, value: &ITimerSpec) -> Option<usize> {
+    // TFD_TIMER_ABSTIME is the same bit
+    const TIMER_ABSTIME: usize = 1;
+
+    let now = timer::get_current_tick();
+    match value.get_value().to_ticks() {
+        0 => None,
+        // an absolute time against the clock of the timer
+        _ if flags & TIMER_ABSTIME != 0 => {
+            let clock_now = timer::get_clock_time(clock);
+            Some(now + value.get_value().saturating_sub(clock_now).to_ticks())
+        }
+        ticks => Some(now + ticks),
+    }
+}
@@ -6,11 +6,41 @@ mod switch;
 #[repr(C)]
 pub struct TaskContext {
     s: [usize; 12], // +0 ~ +11
+    #[cfg(feature = "stackful")]
+    ra: usize, // +12
+    #[cfg(feature = "stackful")]
+    sp: usize, // +13
 }
 
 impl TaskContext {
+    #[cfg(not(feature = "stackful"))]
     pub fn empty() -> Self {
         Self { s: [0; 12] }
     }
+
+    #[cfg(feature = "stackful")]
+    pub fn empty() -> Self {
+        Self {
+            s: [0; 12],
+            ra: 0,
+            sp: 0,
+        }
+    }
+
+    /// `__restore_task` always goes to `trap_return` on the shared kernel stack
+    #[cfg(not(feature = "stackful"))]
+    pub fn goto_trap_return(_kernel_sp: usize) -> Self {
+        Self::empty()
+    }
+
+    /// A new task starts on an empty kernel stack and returns to user directly
+    #[cfg(feature = "stackful")]
+    pub fn goto_trap_return(kernel_sp: usize) -> Self {
+        Self {
+            s: [0; 12],
+            ra: crate::trap::trap_return as usize,
+            sp: kernel_sp,
+        }
+    }
 }
 // region TaskContext end
@@ -1,6 +1,7 @@
-use crate::{task::TaskContext, trap};
+use crate::task::TaskContext;
 use core::arch::asm;
 
+#[cfg(not(feature = "stackful"))]
 #[naked]
 pub unsafe extern "C" fn __save_task(task_cx: *mut TaskContext) {
     // a0 -> *mut TaskContext
@@ -24,6 +25,7 @@ pub unsafe extern "C" fn __save_task(task_cx: *mut TaskContext) {
     )
 }
 
+#[cfg(not(feature = "stackful"))]
 #[naked]
 pub unsafe extern "C" fn __restore_task(task_cx: *const TaskContext) -> ! {
     // a0 -> *const TaskContext
@@ -45,7 +47,52 @@ pub unsafe extern "C" fn __restore_task(task_cx: *const TaskContext) -> ! {
         // goto trap::trap_return
         "la t0, {trap_return}",
         "jr t0",
-        trap_return = sym trap::trap_return,
+        trap_return = sym crate::trap::trap_return,
+        options(noreturn)
+    )
+}
+
+#[cfg(feature = "stackful")]
+#[naked]
+pub unsafe extern "C" fn __switch(
+    current_task_cx: *mut TaskContext,
+    next_task_cx: *const TaskContext,
+) {
+    // a0 -> *mut TaskContext
+    // a1 -> *const TaskContext
+    asm!(
+        // save ra, sp, s0 - s11
+        "sd ra, 12 * 8(a0)",
+        "sd sp, 13 * 8(a0)",
+        "sd s0, 0 * 8(a0)",
+        "sd s1, 1 * 8(a0)",
+        "sd s2, 2 * 8(a0)",
+        "sd s3, 3 * 8(a0)",
+        "sd s4, 4 * 8(a0)",
+        "sd s5, 5 * 8(a0)",
+        "sd s6, 6 * 8(a0)",
+        "sd s7, 7 * 8(a0)",
+        "sd s8, 8 * 8(a0)",
+        "sd s9, 9 * 8(a0)",
+        "sd s10, 10 * 8(a0)",
+        "sd s11, 11 * 8(a0)",
+        // restore ra, sp, s0 - s11
+        "ld ra, 12 * 8(a1)",
+        "ld s0, 0 * 8(a1)",
+        "ld s1, 1 * 8(a1)",
+        "ld s2, 2 * 8(a1)",
+        "ld s3, 3 * 8(a1)",
+        "ld s4, 4 * 8(a1)",
+        "ld s5, 5 * 8(a1)",
+        "ld s6, 6 * 8(a1)",
+        "ld s7, 7 * 8(a1)",
+        "ld s8, 8 * 8(a1)",
+        "ld s9, 9 * 8(a1)",
+        "ld s10, 10 * 8(a1)",
+        "ld s11, 11 * 8(a1)",
+        "ld sp, 13 * 8(a1)",
+        // return to the next task
+        "ret",
         options(noreturn)
     )
 }
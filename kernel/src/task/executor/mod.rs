@@ -0,0 +1,68 @@
+/* Syscall executor
+ *
+ * A blocking syscall is an `async fn` run by `run_syscall`, which polls it
+ * right away. Most syscalls are `Ready` at the first poll.
+ *
+ * Stackless: a `Pending` syscall is kept in the PCB and the task is parked,
+ * the kernel stack is abandoned. Once a waker puts the task back, trap_return
+ * polls the syscall again instead of re-executing the `ecall`.
+ *
+ * Stackful: the task sleeps on its own kernel stack and polls again in place.
+ */
+
+#[cfg(not(feature = "stackful"))]
+pub use pending::*;
+
+use crate::task::{self, ProcessControlBlock};
+use alloc::sync::Arc;
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+#[cfg(not(feature = "stackful"))]
+mod pending;
+mod waker;
+
+#[cfg(not(feature = "stackful"))]
+pub fn run_syscall<F>(syscall: F) -> isize
+where
+    F: Future<Output = isize> + 'static,
+{
+    let pcb = task::get_processor().current();
+    let mut syscall = PendingSyscall::new(syscall);
+    match syscall.poll(&pcb) {
+        Poll::Ready(ret) => ret,
+        Poll::Pending => {
+            pcb.inner().set_pending_syscall(syscall);
+            drop(pcb);
+            task::get_processor().park_current();
+        }
+    }
+}
+
+#[cfg(feature = "stackful")]
+pub fn run_syscall<F>(syscall: F) -> isize
+where
+    F: Future<Output = isize> + 'static,
+{
+    let pcb = task::get_processor().current();
+    // the syscall lives on the kernel stack of the task
+    let mut syscall = core::pin::pin!(syscall);
+    loop {
+        if let Poll::Ready(ret) = poll(syscall.as_mut(), &pcb) {
+            return ret;
+        }
+        task::get_processor().park_current();
+    }
+}
+
+fn poll(
+    syscall: Pin<&mut dyn Future<Output = isize>>,
+    pcb: &Arc<ProcessControlBlock>,
+) -> Poll<isize> {
+    let waker = Waker::from(pcb.clone());
+    let mut cx = Context::from_waker(&waker);
+    syscall.poll(&mut cx)
+}
@@ -0,0 +1,50 @@
+use super::poll;
+use crate::task::{self, ProcessControlBlock};
+use alloc::{boxed::Box, sync::Arc};
+use core::{future::Future, pin::Pin, task::Poll};
+
+/// Poll the syscall the current task is parked in, it has been woken up. The
+/// task only gets back here once the syscall is done.
+pub fn resume_syscall() {
+    let pcb = task::get_processor().current();
+    let syscall = pcb.inner().take_pending_syscall();
+    let Some(mut syscall) = syscall else {
+        return;
+    };
+
+    // switched in from another address space, the syscall may touch user memory
+    pcb.activate_user_space();
+
+    match syscall.poll(&pcb) {
+        Poll::Ready(ret) => pcb.get_trap_cx_mut().set_a0(ret as usize),
+        Poll::Pending => {
+            pcb.inner().set_pending_syscall(syscall);
+            drop(pcb);
+            task::get_processor().park_current();
+        }
+    }
+}
+
+// region PendingSyscall begin
+pub struct PendingSyscall {
+    future: Pin<Box<dyn Future<Output = isize>>>,
+}
+
+// only polled by the task it belongs to, on the boot hart
+unsafe impl Send for PendingSyscall {}
+
+impl PendingSyscall {
+    pub fn new<F>(future: F) -> Self
+    where
+        F: Future<Output = isize> + 'static,
+    {
+        Self {
+            future: Box::pin(future),
+        }
+    }
+
+    pub fn poll(&mut self, pcb: &Arc<ProcessControlBlock>) -> Poll<isize> {
+        poll(self.future.as_mut(), pcb)
+    }
+}
+// region PendingSyscall end
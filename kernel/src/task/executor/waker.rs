@@ -0,0 +1,13 @@
+use crate::task::ProcessControlBlock;
+use alloc::{sync::Arc, task::Wake};
+
+// a waker of a task puts it back to the ready queue
+impl Wake for ProcessControlBlock {
+    fn wake(self: Arc<Self>) {
+        self.wake_up();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wake_up();
+    }
+}
@@ -0,0 +1,50 @@
+// region CpuTimer begin
+/// ITIMER_VIRTUAL or ITIMER_PROF, counting down the CPU time charged to the
+/// process. Times are in ticks.
+#[derive(Clone, Copy)]
+pub struct CpuTimer {
+    // zero when disarmed
+    value: usize,
+    interval: usize,
+}
+
+impl CpuTimer {
+    pub const fn new() -> Self {
+        Self {
+            value: 0,
+            interval: 0,
+        }
+    }
+
+    /// Return the old (value, interval)
+    pub fn set(&mut self, value: usize, interval: usize) -> (usize, usize) {
+        let old = self.get();
+        self.value = value;
+        self.interval = interval;
+        old
+    }
+
+    pub fn get(&self) -> (usize, usize) {
+        (self.value, self.interval)
+    }
+
+    /// Count down `ticks` of CPU time, true if the timer expires
+    pub fn charge(&mut self, ticks: usize) -> bool {
+        if self.value == 0 {
+            return false;
+        }
+        if ticks < self.value {
+            self.value -= ticks;
+            return false;
+        }
+
+        // the overshoot is taken off the next period
+        let overshoot = (ticks - self.value) % self.interval.max(1);
+        self.value = match self.interval {
+            0 => 0,
+            interval => (interval - overshoot).max(1),
+        };
+        true
+    }
+}
+// region CpuTimer end
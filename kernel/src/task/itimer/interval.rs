@@ -0,0 +1,131 @@
+use crate::{
+    sync::SpinNoIrqLock,
+    task::{self, ProcessControlBlock},
+    timer::{self, TimerId},
+};
+use alloc::{
+    boxed::Box,
+    sync::{Arc, Weak},
+};
+
+// region IntervalTimer begin
+/// A timer on the kernel timer queue that notifies on expiry, and is rearmed
+/// every interval if there is one. Times are in ticks.
+pub struct IntervalTimer {
+    // called out of the lock with the count of expirations, missed ones included
+    notify: Box<dyn Fn(usize) + Send + Sync>,
+    inner: SpinNoIrqLock<IntervalTimerInner>,
+}
+
+struct IntervalTimerInner {
+    deadline: Option<usize>,
+    interval: usize,
+    timer: Option<TimerId>,
+    // expirations missed since the last one that was signaled
+    overrun: usize,
+}
+
+impl Drop for IntervalTimer {
+    fn drop(&mut self) {
+        if let Some(timer) = self.inner.lock().timer.take() {
+            timer::cancel_timer(timer);
+        }
+    }
+}
+
+impl IntervalTimer {
+    pub fn new<F: Fn(usize) + Send + Sync + 'static>(notify: F) -> Arc<Self> {
+        Arc::new(Self {
+            notify: Box::new(notify),
+            inner: SpinNoIrqLock::new(
+                "IntervalTimer",
+                IntervalTimerInner {
+                    deadline: None,
+                    interval: 0,
+                    timer: None,
+                    overrun: 0,
+                },
+            ),
+        })
+    }
+
+    /// A timer sending `signo` to `owner`, or nothing for None like SIGEV_NONE
+    pub fn signaling(owner: Weak<ProcessControlBlock>, signo: Option<usize>) -> Arc<Self> {
+        Self::new(move |_| {
+            if let (Some(signo), Some(owner)) = (signo, owner.upgrade()) {
+                task::send_signal(&owner, signo);
+            }
+        })
+    }
+
+    /// Arm the timer at `deadline`, or disarm it with None. Return the old
+    /// (remaining, interval).
+    pub fn set(self: &Arc<Self>, deadline: Option<usize>, interval: usize) -> (usize, usize) {
+        let mut inner = self.inner.lock();
+        let old = (inner.remaining(), inner.interval);
+        if let Some(timer) = inner.timer.take() {
+            timer::cancel_timer(timer);
+        }
+
+        inner.deadline = deadline;
+        inner.interval = interval;
+        inner.overrun = 0;
+        inner.timer = deadline.map(|deadline| self.arm(deadline));
+        old
+    }
+
+    /// Return (remaining, interval), both zero once disarmed
+    pub fn get(&self) -> (usize, usize) {
+        let inner = self.inner.lock();
+        (inner.remaining(), inner.interval)
+    }
+
+    pub fn get_overrun(&self) -> usize {
+        self.inner.lock().overrun
+    }
+
+    fn arm(self: &Arc<Self>, deadline: usize) -> TimerId {
+        // the queue must not keep the timer alive
+        let this = Arc::downgrade(self);
+        timer::add_timer(deadline, move || {
+            if let Some(this) = this.upgrade() {
+                this.expire();
+            }
+        })
+    }
+
+    fn expire(self: &Arc<Self>) {
+        let mut inner = self.inner.lock();
+        inner.timer = None;
+        let Some(deadline) = inner.deadline else {
+            return;
+        };
+
+        let mut expirations = 1;
+        if inner.interval == 0 {
+            inner.deadline = None;
+        } else {
+            // the hart may have been busy for several periods
+            let now = timer::get_current_tick();
+            let missed = (now - deadline) / inner.interval;
+            let next = deadline + (missed + 1) * inner.interval;
+            inner.overrun = missed;
+            inner.deadline = Some(next);
+            inner.timer = Some(self.arm(next));
+            expirations += missed;
+        }
+        drop(inner);
+
+        (self.notify)(expirations);
+    }
+}
+
+impl IntervalTimerInner {
+    fn remaining(&self) -> usize {
+        self.deadline.map_or(0, |deadline| {
+            // an expired one is about to be handled, it never reads as disarmed
+            deadline.saturating_sub(timer::get_current_tick()).max(1)
+        })
+    }
+}
+// region IntervalTimer end
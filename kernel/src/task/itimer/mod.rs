@@ -0,0 +1,171 @@
+pub use cpu::*;
+pub use interval::*;
+
+use crate::{
+    config::POSIX_TIMER_MAX,
+    timer::{ClockId, TimeSpec, TimeUnit, TimeVal},
+};
+use alloc::{collections::btree_map::BTreeMap, sync::Arc};
+
+mod cpu;
+mod interval;
+
+pub const ITIMER_REAL: usize = 0;
+pub const ITIMER_VIRTUAL: usize = 1;
+pub const ITIMER_PROF: usize = 2;
+
+// region ITimerVal begin
+/// struct itimerval
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ITimerVal {
+    interval: TimeVal,
+    value: TimeVal,
+}
+
+impl ITimerVal {
+    /// From (value, interval) in ticks
+    pub fn from_ticks((value, interval): (usize, usize)) -> Self {
+        Self {
+            interval: TimeVal::from_reg(interval),
+            value: TimeVal::from_reg(value),
+        }
+    }
+
+    /// Return (value, interval) in ticks
+    pub fn to_ticks(self) -> (usize, usize) {
+        (to_ticks(self.value), to_ticks(self.interval))
+    }
+}
+// region ITimerVal end
+
+// region ITimerSpec begin
+/// struct itimerspec
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ITimerSpec {
+    interval: TimeSpec,
+    value: TimeSpec,
+}
+
+impl ITimerSpec {
+    /// From (value, interval) in ticks
+    pub fn from_ticks((value, interval): (usize, usize)) -> Self {
+        Self {
+            interval: TimeSpec::from_ticks(interval),
+            value: TimeSpec::from_ticks(value),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.interval.is_valid() && self.value.is_valid()
+    }
+
+    pub fn get_value(&self) -> TimeSpec {
+        self.value
+    }
+
+    pub fn get_interval(&self) -> TimeSpec {
+        self.interval
+    }
+}
+// region ITimerSpec end
+
+/// Ticks of a time, rounded up so that a tiny one does not read as zero
+pub fn to_ticks(time: TimeVal) -> usize {
+    let usec = time.get_time(TimeUnit::Usec);
+    let ticks = time.get_time(TimeUnit::Tick);
+    if usec != 0 {
+        ticks.max(1)
+    } else {
+        0
+    }
+}
+
+// region PosixTimer begin
+/// A timer of timer_create, its absolute times are against `clock`
+pub struct PosixTimer {
+    clock: ClockId,
+    timer: Arc<IntervalTimer>,
+}
+
+impl PosixTimer {
+    pub fn new(clock: ClockId, timer: Arc<IntervalTimer>) -> Self {
+        Self { clock, timer }
+    }
+
+    pub fn get_clock(&self) -> ClockId {
+        self.clock
+    }
+
+    pub fn get_timer(&self) -> &Arc<IntervalTimer> {
+        &self.timer
+    }
+}
+// region PosixTimer end
+
+// region ProcessTimers begin
+/// Interval timers of a process, none of them survives a fork
+pub struct ProcessTimers {
+    real: Option<Arc<IntervalTimer>>,
+    virtual_: CpuTimer,
+    prof: CpuTimer,
+    posix: BTreeMap<usize, PosixTimer>,
+}
+
+impl ProcessTimers {
+    pub fn new() -> Self {
+        Self {
+            real: None,
+            virtual_: CpuTimer::new(),
+            prof: CpuTimer::new(),
+            posix: BTreeMap::new(),
+        }
+    }
+
+    /// Drop every timer, so that none fires for a process that is gone
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// POSIX timers are deleted by exec, itimers are kept
+    pub fn clear_posix(&mut self) {
+        self.posix.clear();
+    }
+}
+
+impl ProcessTimers {
+    pub fn get_real(&self) -> Option<&Arc<IntervalTimer>> {
+        self.real.as_ref()
+    }
+
+    pub fn set_real(&mut self, timer: Arc<IntervalTimer>) {
+        self.real = Some(timer);
+    }
+
+    pub fn get_virtual_mut(&mut self) -> &mut CpuTimer {
+        &mut self.virtual_
+    }
+
+    pub fn get_prof_mut(&mut self) -> &mut CpuTimer {
+        &mut self.prof
+    }
+}
+
+impl ProcessTimers {
+    /// Keep a POSIX timer, return its id or None when every id is taken
+    pub fn insert_posix(&mut self, timer: PosixTimer) -> Option<usize> {
+        let id = (0..POSIX_TIMER_MAX).find(|id| !self.posix.contains_key(id))?;
+        self.posix.insert(id, timer);
+        Some(id)
+    }
+
+    pub fn find_posix(&self, id: usize) -> Option<&PosixTimer> {
+        self.posix.get(&id)
+    }
+
+    pub fn remove_posix(&mut self, id: usize) -> Option<PosixTimer> {
+        self.posix.remove(&id)
+    }
+}
+// region ProcessTimers end
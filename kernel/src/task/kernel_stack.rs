@@ -0,0 +1,78 @@
+#[cfg(feature = "stackful")]
+pub use stackful::*;
+#[cfg(not(feature = "stackful"))]
+pub use stackless::*;
+
+#[cfg(not(feature = "stackful"))]
+mod stackless {
+    use crate::{config::KERNEL_STACK_SP, task::PidHandle};
+
+    // region KernelStack begin
+    /// Every task traps into the single shared kernel stack
+    pub struct KernelStack;
+
+    impl KernelStack {
+        pub fn new(_pid: &PidHandle) -> Self {
+            Self
+        }
+
+        pub fn get_top(&self) -> usize {
+            KERNEL_STACK_SP
+        }
+    }
+    // region KernelStack end
+}
+
+#[cfg(feature = "stackful")]
+mod stackful {
+    use crate::{
+        config::{KERNEL_STACK_REGION, SV39_PAGE_SIZE, TASK_KERNEL_STACK_SIZE},
+        mm::{get_kernel_space, VirtAddr},
+        task::PidHandle,
+    };
+    use core::arch::asm;
+
+    // return (bottom, top) of the kernel stack of pid, a guard page is left below
+    fn kernel_stack_position(pid: usize) -> (usize, usize) {
+        let bottom =
+            KERNEL_STACK_REGION + pid * (TASK_KERNEL_STACK_SIZE + SV39_PAGE_SIZE) + SV39_PAGE_SIZE;
+        let top = bottom + TASK_KERNEL_STACK_SIZE;
+        (bottom, top)
+    }
+
+    // region KernelStack begin
+    /// Kernel stack of one task, mapped as long as its pid is alive
+    pub struct KernelStack {
+        pid: usize,
+    }
+
+    impl Drop for KernelStack {
+        fn drop(&mut self) {
+            let (bottom, _) = kernel_stack_position(self.pid);
+            get_kernel_space()
+                .inner()
+                .remove_kernel_stack(VirtAddr(bottom));
+            unsafe {
+                asm!("sfence.vma");
+            }
+        }
+    }
+
+    impl KernelStack {
+        pub fn new(pid: &PidHandle) -> Self {
+            let (bottom, top) = kernel_stack_position(pid.0);
+            get_kernel_space()
+                .inner()
+                .insert_kernel_stack(VirtAddr(bottom), VirtAddr(top));
+            unsafe {
+                asm!("sfence.vma");
+            }
+            Self { pid: pid.0 }
+        }
+
+        pub fn get_top(&self) -> usize {
+            kernel_stack_position(self.pid).1
+        }
+    }
+    // region KernelStack end
+}
@@ -1,35 +1,44 @@
-use crate::{sync::UPSafeCell, task::ProcessControlBlock};
-use alloc::{collections::vec_deque::VecDeque, sync::Arc};
-use core::cell::RefMut;
+use crate::{
+    sync::{SpinNoIrqLock, SpinNoIrqLockGuard},
+    task::ProcessControlBlock,
+};
+use alloc::{
+    collections::{btree_map::BTreeMap, vec_deque::VecDeque},
+    sync::{Arc, Weak},
+};
 use lazy_static::lazy_static;
 
 pub fn add_task(pcb: Arc<ProcessControlBlock>) {
     get_task_manager().add_to_back(pcb);
 }
 
+/// Find a live process by pid, for kill and the like
+pub fn find_process(pid: usize) -> Option<Arc<ProcessControlBlock>> {
+    PROCESS_TABLE.lock().get(&pid).and_then(Weak::upgrade)
+}
+
+/// Make a new process visible to `find_process`, a reused pid replaces the entry
+pub(in crate::task) fn register_process(pcb: &Arc<ProcessControlBlock>) {
+    PROCESS_TABLE
+        .lock()
+        .insert(pcb.get_pid(), Arc::downgrade(pcb));
+}
+
+static PROCESS_TABLE: SpinNoIrqLock<BTreeMap<usize, Weak<ProcessControlBlock>>> =
+    SpinNoIrqLock::new("ProcessTable", BTreeMap::new());
+
 pub(in crate::task) fn get_task_manager() -> &'static TaskManager {
     &TASK_MANAGER
 }
 
 #[cfg(feature = "test")]
 pub fn create_process(path: &str) {
-    use crate::{
-        fs::{self, File, Inode, OpenFlags},
-        task::get_initproc,
-    };
-    use alloc::vec::Vec;
-
-    let inode = fs::open_file(path, OpenFlags::RDONLY).unwrap();
-    let len = inode.size();
-    let mut buf = Vec::with_capacity(len);
-    unsafe {
-        buf.set_len(len);
-    }
-    let buf = buf.as_mut_slice();
-    let file = inode.to_file();
-    file.read(buf);
-    let pcb = Arc::new(ProcessControlBlock::new(buf));
+    use crate::{mm::ElfReader, task::get_initproc};
+
+    let elf = ElfReader::open(path).unwrap();
+    let pcb = Arc::new(ProcessControlBlock::new(&elf));
     pcb.set_parent(Arc::downgrade(get_initproc()));
+    register_process(&pcb);
     add_task(pcb);
 }
 
@@ -39,51 +48,38 @@ lazy_static! {
 
 // region TaskManager begin
 pub struct TaskManager {
-    inner: UPSafeCell<TaskManagerInner>,
+    inner: SpinNoIrqLock<TaskManagerInner>,
 }
 
 impl TaskManager {
     fn new() -> Self {
         Self {
-            inner: unsafe {
-                UPSafeCell::new(TaskManagerInner {
+            inner: SpinNoIrqLock::new(
+                "TaskManager",
+                TaskManagerInner {
                     ready_queue: VecDeque::new(),
-                })
-            },
+                },
+            ),
         }
     }
 
-    #[cfg(feature = "test")]
-    fn inner(&self) -> core::cell::Ref<TaskManagerInner> {
-        self.inner.shared_access()
-    }
-
-    fn inner_mut(&self) -> RefMut<TaskManagerInner> {
-        self.inner.exclusive_access()
+    fn inner(&self) -> SpinNoIrqLockGuard<TaskManagerInner> {
+        self.inner.lock()
     }
 }
 
 impl TaskManager {
     pub fn add_to_back(&self, pcb: Arc<ProcessControlBlock>) {
-        self.inner_mut().ready_queue.push_back(pcb);
+        self.inner().ready_queue.push_back(pcb);
     }
 
+    #[cfg(not(feature = "stackful"))]
     pub fn add_to_front(&self, pcb: Arc<ProcessControlBlock>) {
-        self.inner_mut().ready_queue.push_front(pcb);
+        self.inner().ready_queue.push_front(pcb);
     }
 
     pub fn fetch(&self) -> Option<Arc<ProcessControlBlock>> {
-        self.inner_mut().ready_queue.pop_front()
-    }
-
-    pub fn fetch_by_pid(&self, pid: usize) -> Option<Arc<ProcessControlBlock>> {
-        let mut inner = self.inner_mut();
-        let idx = inner
-            .ready_queue
-            .iter()
-            .position(|pcb| pcb.get_pid() == pid);
-
-        idx.map(|idx| inner.ready_queue.remove(idx).unwrap())
+        self.inner().ready_queue.pop_front()
     }
 
     #[cfg(feature = "test")]
@@ -1,15 +1,23 @@
 pub use context::*;
+pub use executor::*;
+pub use itimer::*;
+pub use kernel_stack::*;
 pub use manager::*;
 pub use pcb::*;
 pub use pid::*;
 pub use processor::*;
+pub use signal::*;
 pub use tms::*;
 
 mod context;
+mod executor;
+mod itimer;
+mod kernel_stack;
 mod manager;
 mod pcb;
 mod pid;
 mod processor;
+mod signal;
 mod tms;
 
 pub fn init() {
@@ -1,42 +1,71 @@
+#[cfg(not(feature = "stackful"))]
+use crate::task::PendingSyscall;
 use crate::{
-    config::{KERNEL_STACK_SP, ROOT_DIR, TRAP_CX_PTR},
-    fs::{self, File, Inode, Stderr, Stdin, Stdout},
+    config::{ROOT_DIR, TRAP_CX_PTR},
+    fs::{self, File, Stderr, Stdin, Stdout},
     mm::{
-        self, MapArea, MapPermission, MapType, MemorySpace, PhysPageNum, PpnOffset, UserSpace,
-        VirtAddr,
+        self, ElfReader, MapArea, MapPermission, MapType, MemorySpace, PTEFlags, PhysAddr,
+        PhysPageNum, PpnOffset, UserSpace, VirtAddr,
     },
-    sync::UPSafeCell,
-    task::{alloc_pid_handle, PidHandle, TaskContext, Tms},
-    trap::TrapContext,
+    sync::{SpinNoIrqLock, SpinNoIrqLockGuard, WaitQueue},
+    task::{
+        self, alloc_pid_handle, KernelStack, PidHandle, ProcessTimers, SignalState, TaskContext,
+        Tms, SIGPROF, SIGVTALRM,
+    },
+    trap::{FpContext, TrapContext},
 };
 use alloc::vec;
 use alloc::{
-    collections::btree_map::BTreeMap,
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
     string::{String, ToString},
     sync::{Arc, Weak},
     vec::Vec,
 };
-use core::cell::{Ref, RefMut};
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU8, Ordering},
+};
+use riscv::register::{satp, sstatus};
+
+// scheduling state seen by wakers
+const TASK_RUNNING: u8 = 0;
+const TASK_PARKED: u8 = 1;
+// woken up before it got parked
+const TASK_NOTIFIED: u8 = 2;
 
 // region ProcessControlBlock begin
 pub struct ProcessControlBlock {
     pid: PidHandle,
+    kernel_stack: KernelStack,
+    state: AtomicU8,
+    // the parent waits here for a child to exit
+    child_exit: WaitQueue,
+    // signalfd readers wait here for a signal to be raised
+    signal_raised: WaitQueue,
     #[allow(unused)]
-    inner: UPSafeCell<ProcessControlBlockInner>,
+    inner: SpinNoIrqLock<ProcessControlBlockInner>,
 }
 
 impl ProcessControlBlock {
-    pub fn new(elf_data: &[u8]) -> Self {
+    pub fn new(elf: &ElfReader) -> Self {
         let pid = alloc_pid_handle();
-        let user_space = UserSpace::from_elf(elf_data);
+        let kernel_stack = KernelStack::new(&pid);
+        let kernel_sp = kernel_stack.get_top();
+        let user_space = UserSpace::from_elf(elf, None, &[], &[])
+            .expect("ProcessControlBlock: not a loadable ELF");
         let trap_cx_ppn = user_space
-            .inner_mut()
+            .inner()
             .translate(VirtAddr(TRAP_CX_PTR).to_vpn())
             .unwrap()
             .ppn()
             .low_to_high();
-        *trap_cx_ppn.as_mut() = TrapContext::new(user_space.get_entry());
-        let task_cx = TaskContext::empty();
+        *trap_cx_ppn.as_mut() = TrapContext::new(
+            user_space.get_entry(),
+            user_space.get_sp(),
+            user_space.get_tp(),
+            kernel_sp,
+        );
+        let task_cx = TaskContext::goto_trap_return(kernel_sp);
         let cwd = ROOT_DIR.to_string();
         let mut fd_table: BTreeMap<usize, Arc<dyn File + Send + Sync>> = BTreeMap::new();
         fd_table.insert(0, Arc::new(Stdin));
@@ -45,63 +74,81 @@ impl ProcessControlBlock {
 
         Self {
             pid,
-            inner: unsafe {
-                UPSafeCell::new(ProcessControlBlockInner::new(
+            kernel_stack,
+            state: AtomicU8::new(TASK_RUNNING),
+            child_exit: WaitQueue::new(),
+            signal_raised: WaitQueue::new(),
+            inner: SpinNoIrqLock::new(
+                "ProcessControlBlock",
+                ProcessControlBlockInner::new(
                     trap_cx_ppn,
                     task_cx,
                     user_space,
                     cwd,
                     fd_table,
-                ))
-            },
+                    BTreeSet::new(),
+                    SignalState::new(),
+                ),
+            ),
         }
     }
 
     pub fn fork(self: &Arc<Self>) -> Arc<Self> {
         let pid = alloc_pid_handle();
+        let kernel_stack = KernelStack::new(&pid);
+        let kernel_sp = kernel_stack.get_top();
         let user_space = UserSpace::from_existed(self.inner().get_user_space());
         let trap_cx_ppn = user_space
-            .inner_mut()
+            .inner()
             .translate(VirtAddr(TRAP_CX_PTR).to_vpn())
             .unwrap()
             .ppn()
             .low_to_high();
-        let task_cx = TaskContext::empty();
+        let task_cx = TaskContext::goto_trap_return(kernel_sp);
         let cwd = self.inner().get_cwd().clone();
         let mut fd_table: BTreeMap<usize, Arc<dyn File + Send + Sync>> = BTreeMap::new();
         self.inner().fd_table.iter().for_each(|(&no, fd)| {
             fd_table.insert(no, fd.clone());
         });
+        let cloexec = self.inner().cloexec.clone();
+        let signals = SignalState::from_another(self.inner().get_signals_ref());
 
         let pcb = Arc::new(Self {
             pid,
-            inner: unsafe {
-                UPSafeCell::new(ProcessControlBlockInner::new(
+            kernel_stack,
+            state: AtomicU8::new(TASK_RUNNING),
+            child_exit: WaitQueue::new(),
+            signal_raised: WaitQueue::new(),
+            inner: SpinNoIrqLock::new(
+                "ProcessControlBlock",
+                ProcessControlBlockInner::new(
                     trap_cx_ppn,
                     task_cx,
                     user_space,
                     cwd,
                     fd_table,
-                ))
-            },
+                    cloexec,
+                    signals,
+                ),
+            ),
         });
-        pcb.get_trap_cx_mut().set_kernel_sp(KERNEL_STACK_SP);
+        pcb.get_trap_cx_mut().set_kernel_sp(kernel_sp);
+        // saved on the trap into clone if the parent dirtied it
+        let fp_cx = *self.inner().get_fp_cx_ref();
+        pcb.inner().fp_cx = fp_cx;
 
         // Set parent
         pcb.set_parent(Arc::downgrade(self));
 
         // Add to parent's children
-        self.inner_mut().children.push(pcb.clone());
+        self.inner().children.push(pcb.clone());
+        task::register_process(&pcb);
 
         pcb
     }
 
-    pub fn inner(&self) -> Ref<ProcessControlBlockInner> {
-        self.inner.shared_access()
-    }
-
-    pub fn inner_mut(&self) -> RefMut<ProcessControlBlockInner> {
-        self.inner.exclusive_access()
+    pub fn inner(&self) -> SpinNoIrqLockGuard<ProcessControlBlockInner> {
+        self.inner.lock()
     }
 }
 
@@ -110,21 +157,48 @@ impl ProcessControlBlock {
         self.pid.0
     }
 
-    pub fn exec(&self, elf_data: &[u8]) {
-        let user_space = UserSpace::from_elf(elf_data);
+    /// Replace the image with `elf`, run through `interp` if it names one in
+    /// PT_INTERP. On Err(ENOEXEC) or Err(EIO) the old image is left as it was.
+    pub fn exec(
+        &self,
+        elf: &ElfReader,
+        interp: Option<&ElfReader>,
+        args: &[String],
+        envs: &[String],
+    ) -> Result<(), isize> {
+        let user_space = UserSpace::from_elf(elf, interp, args, envs)?;
         let trap_cx_ppn = user_space
-            .inner_mut()
+            .inner()
             .translate(VirtAddr(TRAP_CX_PTR).to_vpn())
             .unwrap()
             .ppn()
             .low_to_high();
-        *trap_cx_ppn.as_mut() = TrapContext::new(user_space.get_entry());
+        *trap_cx_ppn.as_mut() = TrapContext::new(
+            user_space.get_entry(),
+            user_space.get_sp(),
+            user_space.get_tp(),
+            self.kernel_stack.get_top(),
+        );
 
         // update program brk, user space and trap context
-        self.inner_mut().program_brk = user_space.get_base_size();
+        self.inner().program_brk = user_space.get_base_size();
         self.drop_user_space();
-        self.inner_mut().user_space = Some(user_space);
-        self.inner_mut().trap_cx_ppn = trap_cx_ppn;
+        self.inner().user_space = Some(user_space);
+        self.inner().trap_cx_ppn = trap_cx_ppn;
+
+        // handlers, POSIX timers, FP state and FD_CLOEXEC fds belong to the old image
+        let mut inner = self.inner();
+        inner.signals.reset_handlers();
+        inner.timers.clear_posix();
+        inner.fp_cx = FpContext::empty();
+        let closed = inner.take_cloexec_fds();
+        // a pipe end takes the pipe lock when dropped, so not under ours
+        drop(inner);
+        drop(closed);
+        // the registers still hold the old image's FP state, so trap_return
+        // sets FS Off and the first use loads the empty one
+        task::get_processor().release_fp(self);
+        Ok(())
     }
 }
 
@@ -139,21 +213,42 @@ impl ProcessControlBlock {
             .get_pid()
     }
 
+    pub fn get_parent(&self) -> Option<Arc<ProcessControlBlock>> {
+        self.inner().parent.as_ref().and_then(Weak::upgrade)
+    }
+
+    pub fn get_child_exit(&self) -> &WaitQueue {
+        &self.child_exit
+    }
+
+    pub fn get_signal_raised(&self) -> &WaitQueue {
+        &self.signal_raised
+    }
+
     pub fn set_parent(&self, parent: Weak<ProcessControlBlock>) {
-        self.inner_mut().parent = Some(parent);
+        self.inner().parent = Some(parent);
     }
 
     pub fn set_exit_code(&self, exit_code: i32) {
-        self.inner_mut().exit_code = exit_code;
+        self.inner().exit_code = exit_code;
     }
 
-    pub fn get_exit_code(&self) -> i32 {
-        self.inner().exit_code
+    pub fn set_term_signal(&self, signo: usize) {
+        self.inner().term_signal = signo;
+    }
+
+    /// Status reported by waitpid, see wait(2)
+    pub fn get_wait_status(&self) -> i32 {
+        let inner = self.inner();
+        match inner.term_signal {
+            0 => inner.exit_code << 8,
+            signo => signo as i32,
+        }
     }
 
     pub fn drop_user_space(&self) {
         mm::switch_to_kernel_space();
-        self.inner_mut().user_space = None;
+        self.inner().user_space = None;
     }
 
     pub fn is_zombie(&self) -> bool {
@@ -164,26 +259,68 @@ impl ProcessControlBlock {
         self.inner().get_user_space().get_satp()
     }
 
+    /// Switch to the address space of the task, so that the kernel could touch
+    /// its user memory after a switch from another task
+    pub fn activate_user_space(&self) {
+        let satp = self.get_satp();
+        unsafe {
+            satp::write(satp);
+            asm!("sfence.vma");
+            sstatus::set_sum();
+        }
+    }
+
+    /// Physical address behind user address `va`, None if it is not mapped for
+    /// the user or the task is a zombie
+    pub fn translate(&self, va: VirtAddr) -> Option<PhysAddr> {
+        let inner = self.inner();
+        let pte = inner.user_space.as_ref()?.translate(va.to_vpn_floor())?;
+        if !pte.is_valid() || !pte.flags().contains(PTEFlags::U) {
+            return None;
+        }
+        Some(PhysAddr(pte.ppn().to_pa().0 + va.page_offset()))
+    }
+
     pub fn get_trap_cx_mut(&self) -> &'static mut TrapContext {
         self.inner().trap_cx_ppn.as_mut()
     }
 
     pub fn set_break(&self, increase: i32) -> Option<usize> {
         let base_size = self.inner().get_user_space().get_base_size();
+        let brk_limit = self.inner().get_user_space().get_brk_limit();
         let old_brk = self.inner().program_brk;
         let new_brk = (old_brk as i32 + increase) as usize;
-        if new_brk < base_size {
+        if new_brk < base_size || new_brk > brk_limit {
             return None;
         }
 
         self.inner()
             .get_user_space()
-            .inner_mut()
+            .inner()
             .change_area_end(VirtAddr(base_size), VirtAddr(new_brk));
-        self.inner_mut().program_brk = new_brk;
+        self.inner().program_brk = new_brk;
         Some(old_brk)
     }
 }
+
+impl ProcessControlBlock {
+    /// Mark the task as waiting for a waker, false if it has been woken up already
+    pub fn park(&self) -> bool {
+        if self.state.swap(TASK_PARKED, Ordering::AcqRel) == TASK_NOTIFIED {
+            self.state.store(TASK_RUNNING, Ordering::Release);
+            return false;
+        }
+        true
+    }
+
+    /// Put a parked task back to the ready queue, a running one skips its next park
+    pub fn wake_up(self: &Arc<Self>) {
+        if self.state.swap(TASK_NOTIFIED, Ordering::AcqRel) == TASK_PARKED {
+            self.state.store(TASK_RUNNING, Ordering::Release);
+            task::add_task(self.clone());
+        }
+    }
+}
 // region ProcessControlBlock end
 
 // region ProcessControlBlockInner begin
@@ -196,9 +333,15 @@ pub struct ProcessControlBlockInner {
     parent: Option<Weak<ProcessControlBlock>>,
     children: Vec<Arc<ProcessControlBlock>>,
     exit_code: i32,
+    // the signal that killed the process, 0 if it exited
+    term_signal: usize,
+    // zeroed and futex-woken on exit, see set_tid_address(2)
+    clear_child_tid: usize,
 
     cwd: String,
     fd_table: BTreeMap<usize, Arc<dyn File + Send + Sync>>,
+    // fds with FD_CLOEXEC set
+    cloexec: BTreeSet<usize>,
 
     stime_base: usize,
     utime_base: usize,
@@ -206,6 +349,15 @@ pub struct ProcessControlBlockInner {
 
     mmap_base: usize,
     mmap_pair: Vec<(usize, usize)>,
+
+    signals: SignalState,
+    timers: ProcessTimers,
+    // the FP registers of the task while another owns them
+    fp_cx: FpContext,
+
+    // the syscall the task is parked in, polled again before returning to user
+    #[cfg(not(feature = "stackful"))]
+    pending_syscall: Option<PendingSyscall>,
 }
 
 impl ProcessControlBlockInner {
@@ -215,6 +367,8 @@ impl ProcessControlBlockInner {
         user_space: UserSpace,
         cwd: String,
         fd_table: BTreeMap<usize, Arc<dyn File + Send + Sync>>,
+        cloexec: BTreeSet<usize>,
+        signals: SignalState,
     ) -> Self {
         let program_brk = user_space.get_base_size();
         Self {
@@ -225,13 +379,21 @@ impl ProcessControlBlockInner {
             parent: None,
             children: Vec::new(),
             exit_code: 0,
+            term_signal: 0,
+            clear_child_tid: 0,
             cwd,
             fd_table,
+            cloexec,
             stime_base: 0,
             utime_base: 0,
             tms: Tms::empty(),
             mmap_base: 0xffff_ffff_c020_0000,
             mmap_pair: Vec::new(),
+            signals,
+            timers: ProcessTimers::new(),
+            fp_cx: FpContext::empty(),
+            #[cfg(not(feature = "stackful"))]
+            pending_syscall: None,
         }
     }
 
@@ -255,6 +417,14 @@ impl ProcessControlBlockInner {
         &mut self.children
     }
 
+    pub fn get_clear_child_tid(&self) -> usize {
+        self.clear_child_tid
+    }
+
+    pub fn set_clear_child_tid(&mut self, clear_child_tid: usize) {
+        self.clear_child_tid = clear_child_tid;
+    }
+
     pub fn set_cwd(&mut self, cwd: String) {
         self.cwd = cwd;
     }
@@ -264,6 +434,17 @@ impl ProcessControlBlockInner {
     }
 }
 
+#[cfg(not(feature = "stackful"))]
+impl ProcessControlBlockInner {
+    pub fn set_pending_syscall(&mut self, syscall: PendingSyscall) {
+        self.pending_syscall = Some(syscall);
+    }
+
+    pub fn take_pending_syscall(&mut self) -> Option<PendingSyscall> {
+        self.pending_syscall.take()
+    }
+}
+
 impl ProcessControlBlockInner {
     pub fn get_stime_base(&self) -> usize {
         self.stime_base
@@ -288,6 +469,51 @@ impl ProcessControlBlockInner {
     pub fn get_tms_mut(&mut self) -> &mut Tms {
         &mut self.tms
     }
+
+    /// Charge user time, counted down by ITIMER_VIRTUAL and ITIMER_PROF
+    pub fn charge_utime(&mut self, inc: usize) {
+        self.tms.add_utime(inc);
+        if self.timers.get_virtual_mut().charge(inc) {
+            self.signals.raise(SIGVTALRM);
+        }
+        if self.timers.get_prof_mut().charge(inc) {
+            self.signals.raise(SIGPROF);
+        }
+    }
+
+    /// Charge system time, counted down by ITIMER_PROF
+    pub fn charge_stime(&mut self, inc: usize) {
+        self.tms.add_stime(inc);
+        if self.timers.get_prof_mut().charge(inc) {
+            self.signals.raise(SIGPROF);
+        }
+    }
+}
+
+impl ProcessControlBlockInner {
+    pub fn get_signals_ref(&self) -> &SignalState {
+        &self.signals
+    }
+
+    pub fn get_signals_mut(&mut self) -> &mut SignalState {
+        &mut self.signals
+    }
+
+    pub fn get_timers_ref(&self) -> &ProcessTimers {
+        &self.timers
+    }
+
+    pub fn get_timers_mut(&mut self) -> &mut ProcessTimers {
+        &mut self.timers
+    }
+
+    pub fn get_fp_cx_ref(&self) -> &FpContext {
+        &self.fp_cx
+    }
+
+    pub fn get_fp_cx_mut(&mut self) -> &mut FpContext {
+        &mut self.fp_cx
+    }
 }
 
 impl ProcessControlBlockInner {
@@ -306,7 +532,7 @@ impl ProcessControlBlockInner {
         self.user_space
             .as_mut()
             .unwrap()
-            .inner_mut()
+            .inner()
             .insert_area_with_data(
                 MapArea::new(
                     start_va,
@@ -322,11 +548,7 @@ impl ProcessControlBlockInner {
     }
 
     pub fn dealloc_mmap(&mut self, start: usize) {
-        self.user_space
-            .as_mut()
-            .unwrap()
-            .inner_mut()
-            .remove_area(start);
+        self.user_space.as_mut().unwrap().inner().remove_area(start);
         self.mmap_pair.retain(|&(_, start_va)| start_va != start);
     }
 }
@@ -342,6 +564,7 @@ impl ProcessControlBlockInner {
     }
 
     pub fn insert_fd(&mut self, fd: usize, file: Arc<dyn File + Send + Sync>) {
+        self.cloexec.remove(&fd);
         self.fd_table.insert(fd, file);
     }
 
@@ -350,7 +573,27 @@ impl ProcessControlBlockInner {
     }
 
     pub fn take_fd(&mut self, fd: usize) -> Option<Arc<dyn File + Send + Sync>> {
+        self.cloexec.remove(&fd);
         self.fd_table.remove(&fd)
     }
+
+    pub fn get_cloexec(&self, fd: usize) -> bool {
+        self.cloexec.contains(&fd)
+    }
+
+    pub fn set_cloexec(&mut self, fd: usize, cloexec: bool) {
+        if cloexec {
+            self.cloexec.insert(fd);
+        } else {
+            self.cloexec.remove(&fd);
+        }
+    }
+
+    fn take_cloexec_fds(&mut self) -> Vec<Arc<dyn File + Send + Sync>> {
+        core::mem::take(&mut self.cloexec)
+            .into_iter()
+            .filter_map(|fd| self.fd_table.remove(&fd))
+            .collect()
+    }
 }
 // region ProcessControlBlockInner end
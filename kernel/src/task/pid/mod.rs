@@ -1,6 +1,6 @@
 pub use handle::*;
 
-use crate::sync::UPSafeCell;
+use crate::sync::SpinNoIrqLock;
 use alloc::vec::Vec;
 use lazy_static::lazy_static;
 
@@ -20,28 +20,29 @@ lazy_static! {
 
 // region PidAllocator begin
 struct PidAllocator {
-    inner: UPSafeCell<PidAllocatorInner>,
+    inner: SpinNoIrqLock<PidAllocatorInner>,
 }
 
 impl PidAllocator {
     fn new() -> Self {
         Self {
-            inner: unsafe {
-                UPSafeCell::new(PidAllocatorInner {
+            inner: SpinNoIrqLock::new(
+                "PidAllocator",
+                PidAllocatorInner {
                     current: 1,
                     recycled: Vec::new(),
-                })
-            },
+                },
+            ),
         }
     }
 
     fn contains(&self, pid: usize) -> bool {
-        let inner = self.inner.shared_access();
+        let inner = self.inner.lock();
         pid < inner.current || !inner.recycled.contains(&pid)
     }
 
     fn alloc(&self) -> PidHandle {
-        let mut inner = self.inner.exclusive_access();
+        let mut inner = self.inner.lock();
         if let Some(pid) = inner.recycled.pop() {
             return PidHandle(pid);
         }
@@ -57,7 +58,7 @@ impl PidAllocator {
             "PidAllocator: pid {} is not allocated",
             pid
         );
-        let mut inner = self.inner.exclusive_access();
+        let mut inner = self.inner.lock();
         inner.recycled.push(pid);
     }
 }
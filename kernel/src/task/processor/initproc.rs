@@ -1,6 +1,11 @@
-use crate::task::{get_task_manager, ProcessControlBlock};
+use crate::{
+    mm::ElfReader,
+    task::{get_task_manager, register_process, ProcessControlBlock},
+    util,
+};
 use alloc::sync::Arc;
 use lazy_static::lazy_static;
+use log::warn;
 
 pub(in crate::task) fn add_initproc() {
     get_task_manager().add_to_back(INITPROC.clone());
@@ -10,10 +15,49 @@ pub(in crate::task) fn get_initproc() -> &'static Arc<ProcessControlBlock> {
     &INITPROC
 }
 
+#[cfg(feature = "embedded-initproc")]
 const INITPROC_ELF: &[u8] =
     include_bytes!("../../../../user/target/riscv64gc-unknown-none-elf/release/initproc");
 
 lazy_static! {
-    static ref INITPROC: Arc<ProcessControlBlock> =
-        Arc::new(ProcessControlBlock::new(INITPROC_ELF));
+    static ref INITPROC: Arc<ProcessControlBlock> = {
+        let elf = load_init();
+        let initproc = Arc::new(ProcessControlBlock::new(&elf));
+        register_process(&initproc);
+        initproc
+    };
+}
+
+fn load_init() -> ElfReader {
+    // init=<path> takes precedence over everything
+    if let Some(path) = util::get_cmdline().init() {
+        match ElfReader::open(path) {
+            Ok(elf) => return elf,
+            Err(_) => warn!("Initproc: init={} not found", path),
+        }
+    }
+
+    #[cfg(feature = "embedded-initproc")]
+    {
+        ElfReader::from_bytes(INITPROC_ELF)
+    }
+
+    #[cfg(not(feature = "embedded-initproc"))]
+    {
+        use log::info;
+
+        // tried in order after init=, like Linux does
+        const INIT_PATHS: &[&str] = &["/init", "/sbin/init", "/bin/sh"];
+
+        for path in INIT_PATHS {
+            if let Ok(elf) = ElfReader::open(path) {
+                info!("Initproc: load {}", path);
+                return elf;
+            }
+        }
+        panic!(
+            "No working init found, tried init= and {:?}, pass a valid init= option",
+            INIT_PATHS
+        );
+    }
 }
@@ -1,15 +1,23 @@
 pub(in crate::task) use initproc::*;
 
+#[cfg(feature = "test")]
+use crate::task::get_task_manager;
+#[cfg(feature = "stackful")]
+use crate::task::TaskContext;
 use crate::{
-    sync::UPSafeCell,
-    task::{__restore_task, __save_task, get_task_manager, ProcessControlBlock},
+    sync::{self, FutexKey, SpinNoIrqLock, SpinNoIrqLockGuard, FUTEX_BITSET_MATCH_ANY},
+    task::{send_signal, ProcessControlBlock, SIGCHLD},
     timer,
 };
-use alloc::sync::Arc;
-use core::cell::{Ref, RefMut};
+use alloc::sync::{Arc, Weak};
 use lazy_static::lazy_static;
+use riscv::register::sstatus::{self, FS};
 
 mod initproc;
+#[cfg(feature = "stackful")]
+mod stackful;
+#[cfg(not(feature = "stackful"))]
+mod stackless;
 
 pub fn get_processor() -> &'static Processor {
     &PROCESSOR
@@ -21,135 +29,71 @@ lazy_static! {
 
 // region Processor begin
 pub struct Processor {
-    inner: UPSafeCell<ProcessorInner>,
+    inner: SpinNoIrqLock<ProcessorInner>,
 }
 
 impl Processor {
     fn new() -> Self {
         Self {
-            inner: unsafe { UPSafeCell::new(ProcessorInner { current: None }) },
+            inner: SpinNoIrqLock::new(
+                "Processor",
+                ProcessorInner {
+                    current: None,
+                    #[cfg(feature = "stackful")]
+                    idle_cx: TaskContext::empty(),
+                    #[cfg(feature = "stackful")]
+                    exited: None,
+                    fp_owner: Weak::new(),
+                },
+            ),
         }
     }
 
-    fn inner(&self) -> Ref<ProcessorInner> {
-        self.inner.shared_access()
-    }
-
-    fn inner_mut(&self) -> RefMut<ProcessorInner> {
-        self.inner.exclusive_access()
+    fn inner(&self) -> SpinNoIrqLockGuard<ProcessorInner> {
+        self.inner.lock()
     }
 }
 
 impl Processor {
     fn take_current(&self) -> Option<Arc<ProcessControlBlock>> {
-        self.inner_mut().current.take()
+        self.inner().current.take()
     }
 
     pub fn current(&self) -> Arc<ProcessControlBlock> {
         self.inner().current.as_ref().map(Arc::clone).unwrap()
     }
 
-    pub fn run_tasks(&self) -> ! {
-        loop {
-            if let Some(pcb) = self.take_current() {
-                let mut inner = pcb.inner_mut();
-
-                // update tms
-                {
-                    // stime end
-                    let now = timer::get_current_tick();
-                    let inc = now - inner.get_stime_base();
-                    inner.get_tms_mut().add_stime(inc);
-                }
-
-                let task_cx = inner.get_task_cx_mut() as *mut _;
-                drop(inner);
-                unsafe {
-                    __save_task(task_cx);
-                }
-            }
-            if let Some(pcb) = get_task_manager().fetch() {
-                let inner = pcb.inner();
-                let task_cx = inner.get_task_cx_ref() as *const _;
-                drop(inner);
-                self.inner_mut().current = Some(pcb);
-                unsafe {
-                    __restore_task(task_cx);
-                }
-            }
-        }
+    /// Load the FP state of the current task into the registers, it owns them
+    /// until another task loads its own
+    pub fn load_fp(&self) {
+        let pcb = self.current();
+        // the kernel could not touch the registers with FS Off
+        unsafe { sstatus::set_fs(FS::Clean) };
+        pcb.inner().get_fp_cx_ref().restore();
+        pcb.get_trap_cx_mut().set_fs(FS::Clean);
+        self.inner().fp_owner = Arc::downgrade(&pcb);
     }
 
-    pub fn wait_for_child(&self, pid: usize) -> ! {
-        loop {
-            if let Some(pcb) = self.take_current() {
-                let mut inner = pcb.inner_mut();
-
-                // update tms
-                {
-                    // stime end
-                    let now = timer::get_current_tick();
-                    let inc = now - inner.get_stime_base();
-                    inner.get_tms_mut().add_stime(inc);
-                }
-
-                let task_cx = inner.get_task_cx_mut() as *mut _;
-                drop(inner);
-                get_task_manager().add_to_front(pcb);
-                unsafe {
-                    __save_task(task_cx);
-                }
-            }
-
-            if let Some(pcb) = get_task_manager().fetch_by_pid(pid) {
-                let inner = pcb.inner();
-                let task_cx = inner.get_task_cx_ref() as *const _;
-                drop(inner);
-                self.inner_mut().current = Some(pcb);
-                unsafe {
-                    __restore_task(task_cx);
-                }
-            }
-        }
+    /// Whether the FP registers hold the state of `pcb`
+    pub fn owns_fp(&self, pcb: &Arc<ProcessControlBlock>) -> bool {
+        core::ptr::eq(self.inner().fp_owner.as_ptr(), Arc::as_ptr(pcb))
     }
 
-    pub fn schedule(&self) -> ! {
-        loop {
-            if let Some(pcb) = self.take_current() {
-                let mut inner = pcb.inner_mut();
-
-                // update tms
-                {
-                    // stime end
-                    let now = timer::get_current_tick();
-                    let inc = now - inner.get_stime_base();
-                    inner.get_tms_mut().add_stime(inc);
-                }
-
-                let task_cx = inner.get_task_cx_mut() as *mut _;
-                drop(inner);
-                get_task_manager().add_to_back(pcb);
-                unsafe {
-                    __save_task(task_cx);
-                }
-            }
-
-            if let Some(pcb) = get_task_manager().fetch() {
-                let inner = pcb.inner();
-                let task_cx = inner.get_task_cx_ref() as *const _;
-                drop(inner);
-                self.inner_mut().current = Some(pcb);
-                unsafe {
-                    __restore_task(task_cx);
-                }
-            }
+    /// The FP registers no longer hold the state of `pcb`, it is loaded again on
+    /// first use
+    pub fn release_fp(&self, pcb: &ProcessControlBlock) {
+        let mut inner = self.inner();
+        if core::ptr::eq(inner.fp_owner.as_ptr(), pcb) {
+            inner.fp_owner = Weak::new();
         }
     }
 
     pub fn exit_current(&self, exit_code: i32) -> ! {
         let pcb = self.take_current().unwrap();
         pcb.set_exit_code(exit_code);
+        clear_child_tid(&pcb);
         pcb.drop_user_space();
+        pcb.inner().get_timers_mut().clear();
 
         // if initproc exits
         #[cfg(not(feature = "test"))]
@@ -164,26 +108,66 @@ impl Processor {
 
         // move children to initproc
         {
-            for child in pcb.inner_mut().get_children_ref().iter() {
+            for child in pcb.inner().get_children_ref().iter() {
                 child.set_parent(Arc::downgrade(get_initproc()));
                 get_initproc()
-                    .inner_mut()
+                    .inner()
                     .get_children_mut()
                     .push(child.clone());
             }
         }
-        pcb.inner_mut().get_children_mut().clear();
+        let has_children = !pcb.inner().get_children_ref().is_empty();
+        pcb.inner().get_children_mut().clear();
+
+        // notify the parent and wake it up in waitpid, initproc may have got
+        // zombies to reap
+        if let Some(parent) = pcb.get_parent() {
+            send_signal(&parent, SIGCHLD);
+            parent.get_child_exit().wake_all();
+        }
+        if has_children {
+            get_initproc().get_child_exit().wake_all();
+        }
 
-        // drop pcb manually to release resources
-        drop(pcb);
+        self.retire(pcb);
+    }
+}
 
-        self.run_tasks();
+// set_tid_address or CLONE_CHILD_CLEARTID, the word is cleared and woken up
+// like Linux does while the user space is still there
+fn clear_child_tid(pcb: &ProcessControlBlock) {
+    let tid_ptr = pcb.inner().get_clear_child_tid();
+    if tid_ptr == 0 {
+        return;
+    }
+    if let Ok(key) = FutexKey::new(pcb, tid_ptr, false) {
+        unsafe {
+            (tid_ptr as *mut u32).write_volatile(0);
+        }
+        sync::futex_wake(key, 1, FUTEX_BITSET_MATCH_ANY);
     }
 }
+
+// stime end
+fn account_stime(pcb: &ProcessControlBlock) {
+    let mut inner = pcb.inner();
+    let now = timer::get_current_tick();
+    let inc = now - inner.get_stime_base();
+    inner.charge_stime(inc);
+}
 // region Processor end
 
 // region ProcessorInner begin
 struct ProcessorInner {
     current: Option<Arc<ProcessControlBlock>>,
+    // the idle loop runs on the boot stack between two tasks
+    #[cfg(feature = "stackful")]
+    idle_cx: TaskContext,
+    // an exited task could not free the kernel stack it is running on
+    #[cfg(feature = "stackful")]
+    exited: Option<Arc<ProcessControlBlock>>,
+    // whose FP state is in the registers, being weak it keeps the address from
+    // being reused by a new task
+    fp_owner: Weak<ProcessControlBlock>,
 }
 // region ProcessorInner end
@@ -0,0 +1,108 @@
+use super::{account_stime, Processor};
+use crate::{
+    sync::{lockdep, WaitQueue},
+    task::{__switch, get_task_manager, ProcessControlBlock, TaskContext},
+    timer, trap,
+};
+use alloc::sync::Arc;
+use core::{arch::asm, task::Waker};
+use riscv::register::satp;
+
+// Every task owns a kernel stack and resumes right where it called __switch.
+// The diverging calls keep the stackless semantics by returning to user once
+// the task is resumed, so a rewound syscall still restarts.
+impl Processor {
+    /// The idle loop on the boot stack, tasks switch back here to give up the hart
+    pub fn run_tasks(&self) -> ! {
+        loop {
+            timer::run_expired_timers();
+            if let Some(pcb) = get_task_manager().fetch() {
+                self.run(pcb);
+
+                // the kernel stack of an exited task is free to go now
+                let exited = self.inner().exited.take();
+                drop(exited);
+            } else {
+                timer::idle();
+            }
+        }
+    }
+
+    fn run(&self, pcb: Arc<ProcessControlBlock>) {
+        // a task resumed in the middle of a syscall may touch its user memory
+        let satp = pcb.get_satp();
+        unsafe {
+            satp::write(satp);
+            asm!("sfence.vma");
+        }
+
+        let mut inner = pcb.inner();
+        inner.set_stime_base(timer::get_current_tick());
+        let task_cx = inner.get_task_cx_ref() as *const TaskContext;
+        drop(inner);
+
+        lockdep::switch_context(0, context_id(&pcb));
+        self.inner().current = Some(pcb);
+        timer::start_quantum();
+        let idle_cx = &mut self.inner().idle_cx as *mut TaskContext;
+        unsafe {
+            __switch(idle_cx, task_cx);
+        }
+    }
+
+    // save the current task and run the idle loop, returns once it is picked again
+    fn switch_to_idle(&self, pcb: &Arc<ProcessControlBlock>) {
+        account_stime(pcb);
+
+        let task_cx = pcb.inner().get_task_cx_mut() as *mut TaskContext;
+        let idle_cx = &self.inner().idle_cx as *const TaskContext;
+        lockdep::switch_context(context_id(pcb), 0);
+        unsafe {
+            __switch(task_cx, idle_cx);
+        }
+    }
+
+    pub fn yield_current(&self) {
+        let pcb = self.take_current().unwrap();
+        get_task_manager().add_to_back(pcb.clone());
+        self.switch_to_idle(&pcb);
+    }
+
+    /// Block the current task in `wait_queue`, returns once woken up
+    pub fn sleep_current(&self, wait_queue: &WaitQueue) {
+        wait_queue.register(&Waker::from(self.current()));
+        self.park_current();
+    }
+
+    /// Leave the current task to its wakers, returns once one of them fires
+    pub fn park_current(&self) {
+        let pcb = self.take_current().unwrap();
+        // woken up while being polled
+        if !pcb.park() {
+            self.inner().current = Some(pcb);
+            return;
+        }
+        self.switch_to_idle(&pcb);
+    }
+
+    pub fn schedule(&self) -> ! {
+        self.yield_current();
+        trap::trap_return();
+    }
+
+    pub(super) fn retire(&self, pcb: Arc<ProcessControlBlock>) -> ! {
+        let task_cx = pcb.inner().get_task_cx_mut() as *mut TaskContext;
+        let idle_cx = &self.inner().idle_cx as *const TaskContext;
+        lockdep::switch_context(context_id(&pcb), 0);
+        // the idle loop drops it, off this kernel stack
+        self.inner().exited = Some(pcb);
+        unsafe {
+            __switch(task_cx, idle_cx);
+        }
+        unreachable!("Processor: an exited task is resumed");
+    }
+}
+
+fn context_id(pcb: &Arc<ProcessControlBlock>) -> usize {
+    Arc::as_ptr(pcb) as usize
+}
@@ -0,0 +1,91 @@
+use super::{account_stime, Processor};
+use crate::{
+    sync::WaitQueue,
+    task::{__restore_task, __save_task, get_task_manager, ProcessControlBlock},
+    timer,
+};
+use alloc::sync::Arc;
+use core::task::Waker;
+
+// Every task shares one kernel stack, a task switched out abandons it and
+// always resumes from trap_return
+impl Processor {
+    pub fn run_tasks(&self) -> ! {
+        loop {
+            timer::run_expired_timers();
+            if let Some(pcb) = self.take_current() {
+                account_stime(&pcb);
+
+                let task_cx = pcb.inner().get_task_cx_mut() as *mut _;
+                unsafe {
+                    __save_task(task_cx);
+                }
+            }
+            if let Some(pcb) = get_task_manager().fetch() {
+                let inner = pcb.inner();
+                let task_cx = inner.get_task_cx_ref() as *const _;
+                drop(inner);
+                self.inner().current = Some(pcb);
+                timer::start_quantum();
+                unsafe {
+                    __restore_task(task_cx);
+                }
+            }
+            timer::idle();
+        }
+    }
+
+    pub fn schedule(&self) -> ! {
+        loop {
+            timer::run_expired_timers();
+            if let Some(pcb) = self.take_current() {
+                account_stime(&pcb);
+
+                let task_cx = pcb.inner().get_task_cx_mut() as *mut _;
+                get_task_manager().add_to_back(pcb);
+                unsafe {
+                    __save_task(task_cx);
+                }
+            }
+
+            if let Some(pcb) = get_task_manager().fetch() {
+                let inner = pcb.inner();
+                let task_cx = inner.get_task_cx_ref() as *const _;
+                drop(inner);
+                self.inner().current = Some(pcb);
+                timer::start_quantum();
+                unsafe {
+                    __restore_task(task_cx);
+                }
+            }
+            timer::idle();
+        }
+    }
+
+    /// Park the current task in `wait_queue`, its syscall restarts once woken up
+    pub fn block_current(&self, wait_queue: &WaitQueue) -> ! {
+        let pcb = self.current();
+        pcb.get_trap_cx_mut().move_to_prev_ins();
+        wait_queue.register(&Waker::from(pcb));
+        self.park_current();
+    }
+
+    /// Leave the current task to its wakers, it goes on from trap_return
+    pub fn park_current(&self) -> ! {
+        let pcb = self.take_current().unwrap();
+        account_stime(&pcb);
+
+        // woken up while being polled
+        if !pcb.park() {
+            get_task_manager().add_to_front(pcb);
+        }
+        self.run_tasks();
+    }
+
+    pub(super) fn retire(&self, pcb: Arc<ProcessControlBlock>) -> ! {
+        // drop pcb manually to release resources
+        drop(pcb);
+
+        self.run_tasks();
+    }
+}
@@ -0,0 +1,61 @@
+use crate::task::{
+    SignalSet, SIGCHLD, SIGCONT, SIGSTOP, SIGTSTP, SIGTTIN, SIGTTOU, SIGURG, SIGWINCH,
+};
+
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+pub const SA_NODEFER: usize = 0x4000_0000;
+pub const SA_RESETHAND: usize = 0x8000_0000;
+
+// region SigAction begin
+/// struct sigaction of the kernel ABI, riscv has no sa_restorer
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SigAction {
+    handler: usize,
+    flags: usize,
+    mask: SignalSet,
+}
+
+impl SigAction {
+    pub const fn default() -> Self {
+        Self {
+            handler: SIG_DFL,
+            flags: 0,
+            mask: SignalSet::empty(),
+        }
+    }
+
+    pub fn get_handler(&self) -> usize {
+        self.handler
+    }
+
+    pub fn get_flags(&self) -> usize {
+        self.flags
+    }
+
+    pub fn get_mask(&self) -> SignalSet {
+        self.mask
+    }
+}
+// region SigAction end
+
+// region DefaultAction begin
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DefaultAction {
+    Terminate,
+    Ignore,
+}
+
+impl DefaultAction {
+    pub fn of(signo: usize) -> Self {
+        match signo {
+            SIGCHLD | SIGCONT | SIGURG | SIGWINCH => DefaultAction::Ignore,
+            // no job control, stopping is not supported
+            SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => DefaultAction::Ignore,
+            _ => DefaultAction::Terminate,
+        }
+    }
+}
+// region DefaultAction end
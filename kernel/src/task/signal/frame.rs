@@ -0,0 +1,100 @@
+use crate::{
+    task::SignalSet,
+    trap::{FpContext, TrapContext},
+};
+use riscv::register::sstatus::FS;
+
+// region SignalFrame begin
+/// struct rt_sigframe pushed on the user stack, the layout of Linux on riscv
+#[repr(C)]
+pub struct SignalFrame {
+    info: SigInfo,
+    ucontext: UContext,
+}
+
+impl SignalFrame {
+    pub fn new(signo: usize, cx: &TrapContext, fp_cx: &FpContext, mask: SignalSet) -> Self {
+        let mut regs = [0; 32];
+        regs[0] = cx.get_sepc();
+        for (no, reg) in regs.iter_mut().enumerate().skip(1) {
+            *reg = cx.get_x(no);
+        }
+
+        Self {
+            info: SigInfo {
+                signo: signo as i32,
+                errno: 0,
+                code: 0,
+                _fields: [0; 116],
+            },
+            ucontext: UContext {
+                flags: 0,
+                link: 0,
+                stack: [0; 3],
+                mask,
+                _unused: [0; 120],
+                mcontext: MContext {
+                    regs,
+                    fp_state: *fp_cx,
+                    _fp_reserved: [0; 264],
+                },
+            },
+        }
+    }
+
+    pub fn get_info_ptr(&self) -> usize {
+        &self.info as *const SigInfo as usize
+    }
+
+    pub fn get_ucontext_ptr(&self) -> usize {
+        &self.ucontext as *const UContext as usize
+    }
+
+    /// Put back the registers saved in the frame, the handler may have changed them
+    pub fn restore(&self, cx: &mut TrapContext, fp_cx: &mut FpContext) -> SignalSet {
+        let regs = &self.ucontext.mcontext.regs;
+        cx.set_sepc(regs[0]);
+        for (no, &reg) in regs.iter().enumerate().skip(1) {
+            cx.set_x(no, reg);
+        }
+        // loaded from the saved copy on the next FP instruction
+        *fp_cx = self.ucontext.mcontext.fp_state;
+        cx.set_fs(FS::Off);
+        self.ucontext.mask
+    }
+}
+// region SignalFrame end
+
+// region SigInfo begin
+#[repr(C)]
+struct SigInfo {
+    signo: i32,
+    errno: i32,
+    code: i32,
+    _fields: [u8; 116],
+}
+// region SigInfo end
+
+// region UContext begin
+#[repr(C)]
+struct UContext {
+    flags: usize,
+    link: usize,
+    // stack_t, no alternate signal stack
+    stack: [usize; 3],
+    mask: SignalSet,
+    // sigset_t is 1024 bits in glibc
+    _unused: [u8; 120],
+    mcontext: MContext,
+}
+
+/// struct sigcontext, pc then x1 - x31, then the D extension part of the
+/// union __riscv_fp_state
+#[repr(C, align(16))]
+struct MContext {
+    regs: [usize; 32],
+    fp_state: FpContext,
+    // the rest of the Q extension state
+    _fp_reserved: [u8; 264],
+}
+// region UContext end
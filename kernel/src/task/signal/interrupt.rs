@@ -0,0 +1,38 @@
+use crate::task;
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Run `future` until it is done, or until a signal is to be handled
+pub fn interruptible<F: Future>(future: F) -> Interruptible<F> {
+    Interruptible { future }
+}
+
+// region Interruptible begin
+pub struct Interrupted;
+
+pub struct Interruptible<F> {
+    future: F,
+}
+
+impl<F: Future> Future for Interruptible<F> {
+    type Output = Result<F::Output, Interrupted>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // never moved out of the pinned self
+        let future = unsafe { self.map_unchecked_mut(|this| &mut this.future) };
+        if let Poll::Ready(output) = future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        // send_signal wakes the task up to get here
+        let current_task = task::get_processor().current();
+        if current_task.inner().get_signals_ref().has_deliverable() {
+            return Poll::Ready(Err(Interrupted));
+        }
+        Poll::Pending
+    }
+}
+// region Interruptible end
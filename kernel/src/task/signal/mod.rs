@@ -0,0 +1,143 @@
+/* Signals
+ *
+ * A signal is marked pending in the SignalState of the process and handled
+ * on the way back to user, at trap_return. A caught signal pushes a
+ * SignalFrame on the user stack and enters the handler, which returns to the
+ * sigreturn trampoline mapped in every user space, then rt_sigreturn puts the
+ * saved registers back.
+ */
+
+pub use action::*;
+pub use interrupt::*;
+pub use set::*;
+pub use state::*;
+
+use crate::{
+    config::SIGRETURN_TRAMPOLINE,
+    task::{self, ProcessControlBlock},
+};
+use alloc::sync::Arc;
+use core::mem::size_of;
+use frame::SignalFrame;
+
+mod action;
+mod frame;
+mod interrupt;
+mod set;
+mod state;
+
+pub const NSIG: usize = 64;
+
+pub const SIGKILL: usize = 9;
+pub const SIGPIPE: usize = 13;
+pub const SIGALRM: usize = 14;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+pub const SIGTTIN: usize = 21;
+pub const SIGTTOU: usize = 22;
+pub const SIGURG: usize = 23;
+pub const SIGVTALRM: usize = 26;
+pub const SIGPROF: usize = 27;
+pub const SIGWINCH: usize = 28;
+
+pub fn is_valid_signal(signo: usize) -> bool {
+    (1..=NSIG).contains(&signo)
+}
+
+/// Mark `signo` pending for `pcb`, it is handled once the task returns to user.
+/// A task waiting in an interruptible syscall is woken up to get there, and
+/// signalfd readers are woken up to take it.
+pub fn send_signal(pcb: &Arc<ProcessControlBlock>, signo: usize) {
+    let mut inner = pcb.inner();
+    let signals = inner.get_signals_mut();
+    signals.raise(signo);
+    let deliverable = signals.has_deliverable();
+    drop(inner);
+
+    pcb.get_signal_raised().wake_all();
+    if deliverable {
+        pcb.wake_up();
+    }
+}
+
+/// Handle the pending signals of the current task before it returns to user,
+/// a caught one enters its handler
+pub fn handle_signals() {
+    let pcb = task::get_processor().current();
+    loop {
+        let mut inner = pcb.inner();
+        let signals = inner.get_signals_mut();
+        let Some((signo, action)) = signals.take_deliverable() else {
+            // nothing to handle, a temporary mask is done with
+            if let Some(mask) = signals.take_saved_blocked() {
+                signals.set_blocked(mask);
+            }
+            return;
+        };
+        drop(inner);
+
+        match action.get_handler() {
+            SIG_IGN => continue,
+            SIG_DFL => match DefaultAction::of(signo) {
+                DefaultAction::Ignore => continue,
+                DefaultAction::Terminate => {
+                    pcb.set_term_signal(signo);
+                    drop(pcb);
+                    task::get_processor().exit_current(0);
+                }
+            },
+            _ => {
+                enter_handler(&pcb, signo, &action);
+                return;
+            }
+        }
+    }
+}
+
+fn enter_handler(pcb: &ProcessControlBlock, signo: usize, action: &SigAction) {
+    // switched in from another address space, the frame goes on the user stack
+    pcb.activate_user_space();
+
+    let cx = pcb.get_trap_cx_mut();
+    let mut inner = pcb.inner();
+    let fp_cx = *inner.get_fp_cx_ref();
+    let signals = inner.get_signals_mut();
+    let mask = signals.get_blocked();
+    // sigreturn goes back to the mask from before a temporary one
+    let old_mask = signals.take_saved_blocked().unwrap_or(mask);
+
+    let frame_ptr = (cx.get_x(2) - size_of::<SignalFrame>()) & !0xf;
+    let frame = unsafe { &mut *(frame_ptr as *mut SignalFrame) };
+    *frame = SignalFrame::new(signo, cx, &fp_cx, old_mask);
+
+    let mut blocked = mask.union(action.get_mask());
+    if action.get_flags() & SA_NODEFER == 0 {
+        blocked.add(signo);
+    }
+    signals.set_blocked(blocked);
+    if action.get_flags() & SA_RESETHAND != 0 {
+        signals.reset_action(signo);
+    }
+    drop(inner);
+
+    // handler(signo, siginfo, ucontext), returns to the trampoline
+    cx.set_x(1, SIGRETURN_TRAMPOLINE);
+    cx.set_sp(frame_ptr);
+    cx.set_x(10, signo);
+    cx.set_x(11, frame.get_info_ptr());
+    cx.set_x(12, frame.get_ucontext_ptr());
+    cx.set_sepc(action.get_handler());
+}
+
+/// Leave a signal handler, return the a0 it interrupted
+pub fn return_from_handler() -> usize {
+    let pcb = task::get_processor().current();
+    let cx = pcb.get_trap_cx_mut();
+    let frame = unsafe { &*(cx.get_x(2) as *const SignalFrame) };
+    let mut inner = pcb.inner();
+    let mask = frame.restore(cx, inner.get_fp_cx_mut());
+    inner.get_signals_mut().set_blocked(mask);
+    cx.get_x(10)
+}
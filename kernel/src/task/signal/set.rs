@@ -0,0 +1,44 @@
+// region SignalSet begin
+/// sigset_t, signal `n` is bit `n - 1`
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct SignalSet(u64);
+
+impl SignalSet {
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn add(&mut self, signo: usize) {
+        self.0 |= Self::bit(signo);
+    }
+
+    pub fn remove(&mut self, signo: usize) {
+        self.0 &= !Self::bit(signo);
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// The lowest signal in the set, standard signals go before real-time ones
+    pub fn first(&self) -> Option<usize> {
+        match self.0 {
+            0 => None,
+            bits => Some(bits.trailing_zeros() as usize + 1),
+        }
+    }
+
+    fn bit(signo: usize) -> u64 {
+        1 << (signo - 1)
+    }
+}
+// region SignalSet end
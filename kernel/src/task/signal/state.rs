@@ -0,0 +1,125 @@
+use crate::task::{DefaultAction, SigAction, SignalSet, NSIG, SIGKILL, SIGSTOP, SIG_DFL, SIG_IGN};
+
+// region SignalState begin
+/// Signal dispositions, the blocked mask and pending signals of a process
+pub struct SignalState {
+    pending: SignalSet,
+    blocked: SignalSet,
+    // the mask to restore on the way back to user, see set_temporary_blocked
+    saved_blocked: Option<SignalSet>,
+    actions: [SigAction; NSIG],
+}
+
+impl SignalState {
+    pub fn new() -> Self {
+        Self {
+            pending: SignalSet::empty(),
+            blocked: SignalSet::empty(),
+            saved_blocked: None,
+            actions: [SigAction::default(); NSIG],
+        }
+    }
+
+    /// A forked child inherits dispositions and the mask, but nothing pending
+    pub fn from_another(another: &Self) -> Self {
+        Self {
+            pending: SignalSet::empty(),
+            blocked: another.blocked,
+            saved_blocked: None,
+            actions: another.actions,
+        }
+    }
+
+    /// Handlers are gone with the old image, ignored signals stay ignored
+    pub fn reset_handlers(&mut self) {
+        for action in self.actions.iter_mut() {
+            if action.get_handler() != SIG_IGN {
+                *action = SigAction::default();
+            }
+        }
+    }
+}
+
+impl SignalState {
+    pub fn raise(&mut self, signo: usize) {
+        self.pending.add(signo);
+    }
+
+    /// Whether a pending signal would be acted on once the task returns to user
+    pub fn has_deliverable(&self) -> bool {
+        let mut deliverable = self.pending.difference(self.blocked);
+        while let Some(signo) = deliverable.first() {
+            if !self.is_ignored(signo) {
+                return true;
+            }
+            deliverable.remove(signo);
+        }
+        false
+    }
+
+    fn is_ignored(&self, signo: usize) -> bool {
+        match self.actions[signo - 1].get_handler() {
+            SIG_IGN => true,
+            SIG_DFL => DefaultAction::of(signo) == DefaultAction::Ignore,
+            _ => false,
+        }
+    }
+
+    /// Take the next pending signal that is not blocked
+    pub fn take_deliverable(&mut self) -> Option<(usize, SigAction)> {
+        let signo = self.pending.difference(self.blocked).first()?;
+        self.pending.remove(signo);
+        Some((signo, self.actions[signo - 1]))
+    }
+
+    pub fn has_pending(&self, mask: SignalSet) -> bool {
+        self.pending.intersection(mask).first().is_some()
+    }
+
+    /// Take the lowest pending signal in `mask`, blocked or not, for signalfd
+    pub fn take_pending(&mut self, mask: SignalSet) -> Option<usize> {
+        let signo = self.pending.intersection(mask).first()?;
+        self.pending.remove(signo);
+        Some(signo)
+    }
+
+    pub fn get_action(&self, signo: usize) -> SigAction {
+        self.actions[signo - 1]
+    }
+
+    pub fn set_action(&mut self, signo: usize, action: SigAction) {
+        self.actions[signo - 1] = action;
+        // a pending signal set to be ignored is discarded
+        if action.get_handler() == SIG_IGN {
+            self.pending.remove(signo);
+        }
+    }
+
+    pub fn reset_action(&mut self, signo: usize) {
+        self.actions[signo - 1] = SigAction::default();
+    }
+
+    pub fn get_blocked(&self) -> SignalSet {
+        self.blocked
+    }
+
+    /// SIGKILL and SIGSTOP could never be blocked
+    pub fn set_blocked(&mut self, blocked: SignalSet) {
+        let mut blocked = blocked;
+        blocked.remove(SIGKILL);
+        blocked.remove(SIGSTOP);
+        self.blocked = blocked;
+    }
+
+    /// Block `blocked` until the task returns to user, for ppoll and pselect6.
+    /// A handler entered on the way runs with it, and sigreturn restores the old.
+    pub fn set_temporary_blocked(&mut self, blocked: SignalSet) {
+        self.saved_blocked.get_or_insert(self.blocked);
+        self.set_blocked(blocked);
+    }
+
+    pub fn take_saved_blocked(&mut self) -> Option<SignalSet> {
+        self.saved_blocked.take()
+    }
+}
+// region SignalState end
@@ -0,0 +1,97 @@
+use crate::{board, drivers::GoldfishRtc, sync::SpinNoIrqLock, timer::TimeSpec};
+use lazy_static::lazy_static;
+
+pub fn init() {
+    WALL_CLOCK.init();
+}
+
+pub fn get_wall_time() -> TimeSpec {
+    WALL_CLOCK.get_time()
+}
+
+pub fn set_wall_time(time: TimeSpec) -> Result<(), ()> {
+    WALL_CLOCK.set_time(time)
+}
+
+pub fn get_clock_time(clock: ClockId) -> TimeSpec {
+    match clock {
+        ClockId::Realtime => get_wall_time(),
+        ClockId::Monotonic | ClockId::Boottime => get_uptime(),
+    }
+}
+
+fn get_uptime() -> TimeSpec {
+    TimeSpec::from_ticks(super::get_current_tick())
+}
+
+lazy_static! {
+    static ref WALL_CLOCK: WallClock = WallClock::new(board::virt_rtc().map(GoldfishRtc::new));
+}
+
+// region ClockId begin
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ClockId {
+    Realtime,
+    Monotonic,
+    Boottime,
+}
+
+impl ClockId {
+    pub fn from_raw(id: usize) -> Option<Self> {
+        const CLOCK_REALTIME: usize = 0;
+        const CLOCK_MONOTONIC: usize = 1;
+        const CLOCK_MONOTONIC_RAW: usize = 4;
+        const CLOCK_REALTIME_COARSE: usize = 5;
+        const CLOCK_MONOTONIC_COARSE: usize = 6;
+        const CLOCK_BOOTTIME: usize = 7;
+
+        match id {
+            CLOCK_REALTIME | CLOCK_REALTIME_COARSE => Some(ClockId::Realtime),
+            CLOCK_MONOTONIC | CLOCK_MONOTONIC_RAW | CLOCK_MONOTONIC_COARSE => {
+                Some(ClockId::Monotonic)
+            }
+            CLOCK_BOOTTIME => Some(ClockId::Boottime),
+            _ => None,
+        }
+    }
+}
+// region ClockId end
+
+// region WallClock begin
+/// Wall-clock time kept as an offset from the boot time
+struct WallClock {
+    rtc: Option<GoldfishRtc>,
+    // nanoseconds between the unix epoch and boot
+    offset: SpinNoIrqLock<usize>,
+}
+
+impl WallClock {
+    fn new(rtc: Option<GoldfishRtc>) -> Self {
+        Self {
+            rtc,
+            offset: SpinNoIrqLock::new("WallClock", 0),
+        }
+    }
+
+    fn init(&self) {
+        // without a RTC the wall clock starts from the unix epoch
+        let now = self.rtc.as_ref().map_or(0, |rtc| rtc.read_time());
+        *self.offset.lock() = now.saturating_sub(get_uptime().get_nsec());
+    }
+
+    fn get_time(&self) -> TimeSpec {
+        TimeSpec::from_nsec(*self.offset.lock() + get_uptime().get_nsec())
+    }
+
+    fn set_time(&self, time: TimeSpec) -> Result<(), ()> {
+        let uptime = get_uptime().get_nsec();
+        let time = time.get_nsec();
+        // the wall clock could not go before boot
+        if time < uptime {
+            return Err(());
+        }
+        *self.offset.lock() = time - uptime;
+        Ok(())
+    }
+}
+// region WallClock end
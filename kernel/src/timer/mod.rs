@@ -1,10 +1,27 @@
+pub use clock::*;
+pub use queue::*;
+pub use sleep::*;
+pub use time_spec::*;
 pub use time_val::*;
 
-use crate::sbi;
+use crate::{board, sbi};
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 
+mod clock;
+mod queue;
+mod sleep;
+mod time_spec;
 mod time_val;
 
-const TIGGER_TIME: usize = 100_000; // 100 ms
+const TIGGER_TIME: usize = 100_000; // 100 ms, the scheduler quantum
+
+pub fn init() {
+    clock::init();
+    SSTC.store(board::has_sstc(), Ordering::Relaxed);
+}
 
 pub fn get_current_tick() -> usize {
     sbi::sbi_get_time()
@@ -15,13 +32,57 @@ pub fn get_current_time() -> TimeVal {
     TimeVal::from_reg(time)
 }
 
-pub fn set_timer(timer: TimeVal) {
-    let timer = timer.get_time(TimeUnit::Tick);
-    sbi::sbi_set_timer(timer);
+fn set_timer(tick: usize) {
+    if SSTC.load(Ordering::Relaxed) {
+        // stimecmp, written directly without going through M mode
+        unsafe {
+            asm!("csrw 0x14d, {}", in(reg) tick);
+        }
+    } else {
+        sbi::sbi_set_timer(tick);
+    }
+}
+
+/// Preempt the running task once its quantum is used up
+pub fn enable_preemption() {
+    PREEMPTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Give the task about to run a new quantum
+pub fn start_quantum() {
+    let quantum_end = if PREEMPTIVE.load(Ordering::Relaxed) {
+        (get_current_time() + TimeVal::new(0, TIGGER_TIME)).get_time(TimeUnit::Tick)
+    } else {
+        usize::MAX
+    };
+    QUANTUM_END.store(quantum_end, Ordering::Relaxed);
+    set_next_trigger();
 }
 
+pub fn quantum_expired() -> bool {
+    get_current_tick() >= QUANTUM_END.load(Ordering::Relaxed)
+}
+
+/// Arm the timer at the earliest of the next deadline and the end of the quantum
 pub fn set_next_trigger() {
-    let current_time = get_current_time();
-    let next_time = TimeVal::new(0, TIGGER_TIME);
-    set_timer(current_time + next_time);
+    let next = next_deadline()
+        .unwrap_or(usize::MAX)
+        .min(QUANTUM_END.load(Ordering::Relaxed));
+    set_timer(next);
 }
+
+/// Wait for an interrupt with nothing to run, only timer deadlines are armed
+/// so an idle hart takes no ticks
+pub fn idle() {
+    QUANTUM_END.store(usize::MAX, Ordering::Relaxed);
+    set_next_trigger();
+    // sstatus.SIE is off in the kernel, a pending interrupt still ends wfi
+    unsafe {
+        asm!("wfi");
+    }
+}
+
+static SSTC: AtomicBool = AtomicBool::new(false);
+static PREEMPTIVE: AtomicBool = AtomicBool::new(false);
+// in ticks
+static QUANTUM_END: AtomicUsize = AtomicUsize::new(usize::MAX);
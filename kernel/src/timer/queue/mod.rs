@@ -0,0 +1,90 @@
+use crate::{sync::SpinNoIrqLock, timer};
+use alloc::{boxed::Box, collections::BinaryHeap, vec::Vec};
+use core::{
+    cmp::Ordering,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+/// Call `callback` once the tick reaches `deadline`, from the timer interrupt
+/// or the scheduler loop
+pub fn add_timer<F>(deadline: usize, callback: F) -> TimerId
+where
+    F: FnOnce() + Send + 'static,
+{
+    let id = TimerId(NEXT_TIMER_ID.fetch_add(1, AtomicOrdering::Relaxed));
+    TIMER_QUEUE.lock().push(Timer {
+        deadline,
+        id,
+        callback: Box::new(callback),
+    });
+    // the new deadline may come before the armed one
+    timer::set_next_trigger();
+    id
+}
+
+/// Drop a timer that has not fired yet, return false if it is gone
+pub fn cancel_timer(id: TimerId) -> bool {
+    let mut queue = TIMER_QUEUE.lock();
+    let len = queue.len();
+    queue.retain(|timer| timer.id != id);
+    queue.len() != len
+}
+
+/// Run the callbacks of every expired timer
+pub fn run_expired_timers() {
+    let now = timer::get_current_tick();
+    let mut expired = Vec::new();
+    {
+        let mut queue = TIMER_QUEUE.lock();
+        while queue.peek().is_some_and(|timer| timer.deadline <= now) {
+            expired.push(queue.pop().unwrap());
+        }
+    }
+    // a callback may add timers again
+    for timer in expired {
+        (timer.callback)();
+    }
+}
+
+pub(super) fn next_deadline() -> Option<usize> {
+    TIMER_QUEUE.lock().peek().map(|timer| timer.deadline)
+}
+
+static TIMER_QUEUE: SpinNoIrqLock<BinaryHeap<Timer>> =
+    SpinNoIrqLock::new("TimerQueue", BinaryHeap::new());
+static NEXT_TIMER_ID: AtomicUsize = AtomicUsize::new(0);
+
+// region TimerId begin
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimerId(usize);
+// region TimerId end
+
+// region Timer begin
+struct Timer {
+    // in ticks
+    deadline: usize,
+    id: TimerId,
+    callback: Box<dyn FnOnce() + Send>,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    // BinaryHeap is a max-heap, the earliest deadline goes first
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deadline, other.id.0).cmp(&(self.deadline, self.id.0))
+    }
+}
+// region Timer end
@@ -0,0 +1,47 @@
+use crate::timer::{self, TimerId};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Sleep until the tick count reaches `deadline`
+pub fn sleep_until(deadline: usize) -> Sleep {
+    Sleep {
+        deadline,
+        timer: None,
+    }
+}
+
+// region Sleep begin
+pub struct Sleep {
+    // in ticks
+    deadline: usize,
+    timer: Option<TimerId>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if timer::get_current_tick() >= self.deadline {
+            self.timer = None;
+            return Poll::Ready(());
+        }
+        // woken up early by someone else, the timer is still armed
+        if self.timer.is_none() {
+            let waker = cx.waker().clone();
+            self.timer = Some(timer::add_timer(self.deadline, move || waker.wake()));
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer::cancel_timer(timer);
+        }
+    }
+}
+// region Sleep end
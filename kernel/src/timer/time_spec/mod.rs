@@ -0,0 +1,70 @@
+use crate::{
+    board,
+    timer::{TimeUnit, TimeVal},
+};
+
+const NANO_PER_SEC: usize = 1_000_000_000;
+const NANO_PER_MICRO: usize = 1_000;
+
+// region TimeSpec begin
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeSpec {
+    sec: usize,
+    nsec: usize,
+}
+
+impl TimeSpec {
+    pub const fn from_nsec(nsec: usize) -> Self {
+        TimeSpec {
+            sec: nsec / NANO_PER_SEC,
+            nsec: nsec % NANO_PER_SEC,
+        }
+    }
+
+    /// From a count of timer ticks, keeping nanosecond precision
+    pub fn from_ticks(ticks: usize) -> Self {
+        let clock_freq = board::clock_freq();
+        TimeSpec {
+            sec: ticks / clock_freq,
+            nsec: ticks % clock_freq * NANO_PER_SEC / clock_freq,
+        }
+    }
+
+    /// Timer ticks, rounded up so that a non-zero time never reads as zero
+    pub fn to_ticks(self) -> usize {
+        let clock_freq = board::clock_freq();
+        let sub_ticks = (self.nsec * clock_freq).div_ceil(NANO_PER_SEC);
+        self.sec
+            .saturating_mul(clock_freq)
+            .saturating_add(sub_ticks)
+    }
+
+    pub fn sec(&self) -> usize {
+        self.sec
+    }
+
+    /// A negative tv_sec reads as a huge one
+    pub fn is_valid(&self) -> bool {
+        self.nsec < NANO_PER_SEC && (self.sec as isize) >= 0
+    }
+
+    pub fn get_nsec(&self) -> usize {
+        self.sec * NANO_PER_SEC + self.nsec
+    }
+
+    pub fn saturating_sub(self, rhs: TimeSpec) -> TimeSpec {
+        TimeSpec::from_nsec(self.get_nsec().saturating_sub(rhs.get_nsec()))
+    }
+
+    pub fn to_time_val(self) -> TimeVal {
+        TimeVal::new(self.sec, self.nsec / NANO_PER_MICRO)
+    }
+}
+
+impl From<TimeVal> for TimeSpec {
+    fn from(time_val: TimeVal) -> Self {
+        TimeSpec::from_nsec(time_val.get_time(TimeUnit::Usec) * NANO_PER_MICRO)
+    }
+}
+// region TimeSpec end
@@ -1,6 +1,6 @@
 pub use unit::*;
 
-use crate::board::CLOCK_FREQ;
+use crate::board;
 
 mod unit;
 
@@ -32,9 +32,10 @@ impl TimeVal {
         TimeVal { sec, usec }
     }
 
-    pub const fn from_reg(time: usize) -> Self {
-        let sec = time / CLOCK_FREQ;
-        let usec = time % CLOCK_FREQ * MICRO_PER_SEC / CLOCK_FREQ;
+    pub fn from_reg(time: usize) -> Self {
+        let clock_freq = board::clock_freq();
+        let sec = time / clock_freq;
+        let usec = time % clock_freq * MICRO_PER_SEC / clock_freq;
         TimeVal { sec, usec }
     }
 
@@ -45,7 +46,10 @@ impl TimeVal {
             TimeUnit::Sec => self.sec,
             TimeUnit::Msec => self.sec * MILLIS_PER_SEC + self.usec / MICRO_PER_SEC,
             TimeUnit::Usec => self.sec * MICRO_PER_SEC + self.usec,
-            TimeUnit::Tick => self.sec * CLOCK_FREQ + self.usec * CLOCK_FREQ / MICRO_PER_SEC,
+            TimeUnit::Tick => {
+                let clock_freq = board::clock_freq();
+                self.sec * clock_freq + self.usec * clock_freq / MICRO_PER_SEC
+            }
         }
     }
 }
@@ -1,31 +1,36 @@
-use crate::config::{KERNEL_STACK_SP, USER_STACK_SP};
-use riscv::register::sstatus::{self, Sstatus, SPP};
+use riscv::register::sstatus::{self, FS, SPP};
+
+// sstatus.FS, bits 13 - 14
+const SSTATUS_FS_SHIFT: usize = 13;
+const SSTATUS_FS_MASK: usize = 0b11 << SSTATUS_FS_SHIFT;
 
 // region TrapContext begin
 #[repr(C)]
 pub struct TrapContext {
     // registers
-    x: [usize; 32],   // +0 ~ + 31
-    sstatus: Sstatus, // + 32
-    sepc: usize,      // +33
+    x: [usize; 32], // +0 ~ + 31
+    sstatus: usize, // + 32
+    sepc: usize,    // +33
 
     // variables
     kernel_sp: usize, // +34
 }
 
 impl TrapContext {
-    pub fn new(entry: usize) -> Self {
+    pub fn new(entry: usize, sp: usize, tp: usize, kernel_sp: usize) -> Self {
         let mut sstatus = sstatus::read();
         sstatus.set_spp(SPP::User);
         sstatus.clear_sum();
 
         let mut cx = Self {
             x: [0; 32],
-            sstatus,
+            // FS is Off, the first FP instruction loads the state of the task
+            sstatus: sstatus.bits() & !SSTATUS_FS_MASK,
             sepc: entry,
-            kernel_sp: KERNEL_STACK_SP,
+            kernel_sp,
         };
-        cx.set_sp(USER_STACK_SP);
+        cx.set_sp(sp);
+        cx.set_tp(tp);
         cx
     }
 }
@@ -35,14 +40,26 @@ impl TrapContext {
         self.x[no]
     }
 
+    pub fn set_x(&mut self, no: usize, value: usize) {
+        self.x[no] = value;
+    }
+
     pub fn get_sepc(&self) -> usize {
         self.sepc
     }
 
+    pub fn set_sepc(&mut self, sepc: usize) {
+        self.sepc = sepc;
+    }
+
     pub fn set_sp(&mut self, sp: usize) {
         self.x[2] = sp;
     }
 
+    pub fn set_tp(&mut self, tp: usize) {
+        self.x[4] = tp;
+    }
+
     pub fn set_a0(&mut self, a0: usize) {
         self.x[10] = a0;
     }
@@ -51,10 +68,31 @@ impl TrapContext {
         self.sepc += 4;
     }
 
+    #[cfg(not(feature = "stackful"))]
     pub fn move_to_prev_ins(&mut self) {
         self.sepc -= 4;
     }
 
+    /// FP state of the user, sstatus.FS is saved and restored along with the rest
+    pub fn get_fs(&self) -> FS {
+        match (self.sstatus & SSTATUS_FS_MASK) >> SSTATUS_FS_SHIFT {
+            0 => FS::Off,
+            1 => FS::Initial,
+            2 => FS::Clean,
+            _ => FS::Dirty,
+        }
+    }
+
+    pub fn set_fs(&mut self, fs: FS) {
+        let bits = match fs {
+            FS::Off => 0,
+            FS::Initial => 1,
+            FS::Clean => 2,
+            FS::Dirty => 3,
+        };
+        self.sstatus = (self.sstatus & !SSTATUS_FS_MASK) | (bits << SSTATUS_FS_SHIFT);
+    }
+
     pub fn set_kernel_sp(&mut self, sp: usize) {
         self.kernel_sp = sp;
     }
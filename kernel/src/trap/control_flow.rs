@@ -8,10 +8,14 @@
  * goto trap_handler
  *
  * trap_handler() - Handle Trap
+ * save the FP registers if dirty
  * syscall / exception / interrupt
  * goto trap_return
  *
  * trap_return() - Return to User
+ * poll the parked syscall, if any
+ * handle pending signals
+ * turn FP off if another task owns the registers
  * goto __restore_snap
  *
  * __restore_snap() - Restore Trap Context
@@ -19,14 +23,15 @@
 
 use crate::{
     config::TRAP_CX_PTR,
-    syscall, task, timer,
+    sync, syscall, task, timer,
     trap::{set_kernel_trap_entry, set_user_trap_entry},
 };
 use core::arch::asm;
 use log::{error, trace};
 use riscv::register::{
     scause::{self, Exception, Interrupt, Trap},
-    sstatus, stval,
+    sstatus::{self, FS},
+    stval,
 };
 
 #[naked]
@@ -46,7 +51,7 @@ pub(in crate::trap) unsafe extern "C" fn __snap_trap() -> ! {
         "sd x1, 1*8(sp)",
         // save sp later
         "sd x3, 3*8(sp)",
-        // skip tp
+        "sd x4, 4*8(sp)",
         "sd x5, 5*8(sp)",
         "sd x6, 6*8(sp)",
         "sd x7, 7*8(sp)",
@@ -110,12 +115,12 @@ pub fn trap_handler() -> ! {
     // update tms
     {
         let current_task = task::get_processor().current();
-        let mut inner = current_task.inner_mut();
+        let mut inner = current_task.inner();
 
         // utime end
         let now = timer::get_current_tick();
         let inc = now - inner.get_utime_base();
-        inner.get_tms_mut().add_utime(inc);
+        inner.charge_utime(inc);
 
         // stime start
         inner.set_stime_base(now);
@@ -124,6 +129,13 @@ pub fn trap_handler() -> ! {
     }
 
     let cx = task::get_processor().current().get_trap_cx_mut();
+    // the registers stay with the task, only the saved copy goes stale
+    if cx.get_fs() == FS::Dirty {
+        let current_task = task::get_processor().current();
+        current_task.inner().get_fp_cx_mut().save();
+        cx.set_fs(FS::Clean);
+    }
+
     let stval = stval::read();
     let scause = scause::read();
     let sepc = cx.get_sepc();
@@ -156,8 +168,18 @@ pub fn trap_handler() -> ! {
             trap_return();
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            timer::run_expired_timers();
+            if timer::quantum_expired() {
+                task::get_processor().schedule();
+            }
+            // an earlier deadline than the quantum, the task goes on
             timer::set_next_trigger();
-            task::get_processor().schedule();
+            trap_return();
+        }
+        // the first FP instruction since the task lost the registers
+        Trap::Exception(Exception::IllegalInstruction) if cx.get_fs() == FS::Off => {
+            task::get_processor().load_fp();
+            trap_return();
         }
         Trap::Exception(Exception::IllegalInstruction) => {
             error!(
@@ -201,6 +223,18 @@ pub fn trap_handler() -> ! {
 
 #[no_mangle]
 pub fn trap_return() -> ! {
+    // a parked syscall is done before the task returns to user
+    #[cfg(not(feature = "stackful"))]
+    task::resume_syscall();
+    task::handle_signals();
+    // lazy FP switch, the state is loaded on first use
+    {
+        let current_task = task::get_processor().current();
+        if !task::get_processor().owns_fp(&current_task) {
+            current_task.get_trap_cx_mut().set_fs(FS::Off);
+        }
+    }
+    sync::lockdep::assert_no_locks_held("trap_return");
     unsafe {
         // disable supervisor user memory access
         sstatus::clear_sum();
@@ -210,7 +244,7 @@ pub fn trap_return() -> ! {
     // update tms
     {
         let current_task = task::get_processor().current();
-        let mut inner = current_task.inner_mut();
+        let mut inner = current_task.inner();
 
         // utime start
         let now = timer::get_current_tick();
@@ -218,7 +252,7 @@ pub fn trap_return() -> ! {
 
         // stime end
         let inc = now - inner.get_stime_base();
-        inner.get_tms_mut().add_stime(inc);
+        inner.charge_stime(inc);
 
         drop(inner);
     }
@@ -265,7 +299,7 @@ unsafe extern "C" fn __restore_snap() -> ! {
         "ld x1, 1*8(sp)",
         // restore sp later
         "ld x3, 3*8(sp)",
-        // skip tp
+        "ld x4, 4*8(sp)",
         "ld x5, 5*8(sp)",
         "ld x6, 6*8(sp)",
         "ld x7, 7*8(sp)",
@@ -0,0 +1,122 @@
+use core::arch::asm;
+
+// region FpContext begin
+/// f0 - f31 and fcsr of a task, laid out as struct __riscv_d_ext_state
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FpContext {
+    f: [u64; 32], // +0 ~ +31
+    fcsr: u32,    // +32
+}
+
+impl FpContext {
+    pub const fn empty() -> Self {
+        Self {
+            f: [0; 32],
+            fcsr: 0,
+        }
+    }
+
+    /// Save the FP registers here, sstatus.FS must not be Off
+    pub fn save(&mut self) {
+        unsafe { __save_fp(self) }
+    }
+
+    /// Load the FP registers from here, sstatus.FS must not be Off
+    pub fn restore(&self) {
+        unsafe { __restore_fp(self) }
+    }
+}
+// region FpContext end
+
+#[naked]
+unsafe extern "C" fn __save_fp(fp_cx: *mut FpContext) {
+    // a0 -> *mut FpContext
+    asm!(
+        // save f0 - f31
+        "fsd f0, 0 * 8(a0)",
+        "fsd f1, 1 * 8(a0)",
+        "fsd f2, 2 * 8(a0)",
+        "fsd f3, 3 * 8(a0)",
+        "fsd f4, 4 * 8(a0)",
+        "fsd f5, 5 * 8(a0)",
+        "fsd f6, 6 * 8(a0)",
+        "fsd f7, 7 * 8(a0)",
+        "fsd f8, 8 * 8(a0)",
+        "fsd f9, 9 * 8(a0)",
+        "fsd f10, 10 * 8(a0)",
+        "fsd f11, 11 * 8(a0)",
+        "fsd f12, 12 * 8(a0)",
+        "fsd f13, 13 * 8(a0)",
+        "fsd f14, 14 * 8(a0)",
+        "fsd f15, 15 * 8(a0)",
+        "fsd f16, 16 * 8(a0)",
+        "fsd f17, 17 * 8(a0)",
+        "fsd f18, 18 * 8(a0)",
+        "fsd f19, 19 * 8(a0)",
+        "fsd f20, 20 * 8(a0)",
+        "fsd f21, 21 * 8(a0)",
+        "fsd f22, 22 * 8(a0)",
+        "fsd f23, 23 * 8(a0)",
+        "fsd f24, 24 * 8(a0)",
+        "fsd f25, 25 * 8(a0)",
+        "fsd f26, 26 * 8(a0)",
+        "fsd f27, 27 * 8(a0)",
+        "fsd f28, 28 * 8(a0)",
+        "fsd f29, 29 * 8(a0)",
+        "fsd f30, 30 * 8(a0)",
+        "fsd f31, 31 * 8(a0)",
+        // save fcsr
+        "frcsr t0",
+        "sw t0, 32 * 8(a0)",
+        // done
+        "ret",
+        options(noreturn)
+    )
+}
+
+#[naked]
+unsafe extern "C" fn __restore_fp(fp_cx: *const FpContext) {
+    // a0 -> *const FpContext
+    asm!(
+        // restore f0 - f31
+        "fld f0, 0 * 8(a0)",
+        "fld f1, 1 * 8(a0)",
+        "fld f2, 2 * 8(a0)",
+        "fld f3, 3 * 8(a0)",
+        "fld f4, 4 * 8(a0)",
+        "fld f5, 5 * 8(a0)",
+        "fld f6, 6 * 8(a0)",
+        "fld f7, 7 * 8(a0)",
+        "fld f8, 8 * 8(a0)",
+        "fld f9, 9 * 8(a0)",
+        "fld f10, 10 * 8(a0)",
+        "fld f11, 11 * 8(a0)",
+        "fld f12, 12 * 8(a0)",
+        "fld f13, 13 * 8(a0)",
+        "fld f14, 14 * 8(a0)",
+        "fld f15, 15 * 8(a0)",
+        "fld f16, 16 * 8(a0)",
+        "fld f17, 17 * 8(a0)",
+        "fld f18, 18 * 8(a0)",
+        "fld f19, 19 * 8(a0)",
+        "fld f20, 20 * 8(a0)",
+        "fld f21, 21 * 8(a0)",
+        "fld f22, 22 * 8(a0)",
+        "fld f23, 23 * 8(a0)",
+        "fld f24, 24 * 8(a0)",
+        "fld f25, 25 * 8(a0)",
+        "fld f26, 26 * 8(a0)",
+        "fld f27, 27 * 8(a0)",
+        "fld f28, 28 * 8(a0)",
+        "fld f29, 29 * 8(a0)",
+        "fld f30, 30 * 8(a0)",
+        "fld f31, 31 * 8(a0)",
+        // restore fcsr
+        "lw t0, 32 * 8(a0)",
+        "fscsr t0",
+        // done
+        "ret",
+        options(noreturn)
+    )
+}
@@ -3,9 +3,11 @@ use riscv::register::{sie, stvec, utvec::TrapMode};
 
 pub use context::*;
 pub use control_flow::*;
+pub use fp_context::*;
 
 mod context;
 mod control_flow;
+mod fp_context;
 
 pub fn init_trap() {
     set_kernel_trap_entry();
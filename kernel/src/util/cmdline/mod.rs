@@ -0,0 +1,101 @@
+use alloc::{string::String, vec::Vec};
+use log::LevelFilter;
+use spin::Once;
+
+/// Use the device tree bootargs, or the build-time `BOOTARGS` when there is none
+pub fn init_cmdline(bootargs: Option<&str>) {
+    let bootargs = bootargs
+        .filter(|bootargs| !bootargs.trim().is_empty())
+        .or(option_env!("BOOTARGS"))
+        .unwrap_or_default();
+    CMDLINE.call_once(|| Cmdline::parse(bootargs));
+}
+
+pub fn get_cmdline() -> &'static Cmdline {
+    CMDLINE.get().expect("Command line is not parsed")
+}
+
+static CMDLINE: Once<Cmdline> = Once::new();
+
+// region Cmdline begin
+/// Kernel command line, `key=value` pairs and bare flags separated by spaces
+pub struct Cmdline {
+    raw: String,
+    args: Vec<(String, Option<String>)>,
+}
+
+impl Cmdline {
+    fn parse(raw: &str) -> Self {
+        let args = raw
+            .split_whitespace()
+            .map(|arg| match arg.split_once('=') {
+                Some((key, value)) => (String::from(key), Some(String::from(value))),
+                None => (String::from(arg), None),
+            })
+            .collect();
+        Self {
+            raw: String::from(raw),
+            args,
+        }
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// The last value wins, as on Linux
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, value)| value.as_deref())
+    }
+}
+
+impl Cmdline {
+    /// `log=error|warn|info|debug|trace|off`
+    pub fn log_level(&self) -> Option<LevelFilter> {
+        self.get("log")?.parse().ok()
+    }
+
+    /// `init=<path>`, the first user program
+    pub fn init(&self) -> Option<&str> {
+        self.get("init")
+    }
+
+    /// `root=/dev/vdX`, index of the virtio block device to mount as root
+    pub fn root_device(&self) -> Option<usize> {
+        let name = self.get("root")?.strip_prefix("/dev/vd")?;
+        match name.as_bytes() {
+            &[c] if c.is_ascii_lowercase() => Some((c - b'a') as usize),
+            _ => None,
+        }
+    }
+
+    /// `sched=rr|fifo`
+    pub fn sched(&self) -> Option<SchedPolicy> {
+        match self.get("sched")? {
+            "rr" => Some(SchedPolicy::RoundRobin),
+            "fifo" => Some(SchedPolicy::Fifo),
+            _ => None,
+        }
+    }
+
+    /// `test=<path>`, a manifest listing one test program per line
+    #[cfg(feature = "test")]
+    pub fn test_manifest(&self) -> Option<&str> {
+        self.get("test")
+    }
+}
+// region Cmdline end
+
+// region SchedPolicy begin
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// preempt tasks on timer interrupts
+    RoundRobin,
+    /// run tasks until they yield
+    Fifo,
+}
+// region SchedPolicy end
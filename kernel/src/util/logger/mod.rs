@@ -13,7 +13,8 @@ pub fn init_log() {
         Some("INFO") => LevelFilter::Info,
         Some("DEBUG") => LevelFilter::Debug,
         Some("TRACE") => LevelFilter::Trace,
-        _ => LevelFilter::Info,
+        Some(_) => LevelFilter::Info,
+        None => LevelFilter::Off,
     };
     log::set_max_level(max_level);
 
@@ -21,12 +22,18 @@ pub fn init_log() {
     debug!("Logger: set level {}", max_level);
 }
 
+/// Override the build-time `LOG` level, e.g. with `log=` from the command line
+pub fn set_log_level(max_level: LevelFilter) {
+    log::set_max_level(max_level);
+    debug!("Logger: set level {}", max_level);
+}
+
 // region Logger begin
 struct Logger;
 
 impl Log for Logger {
-    fn enabled(&self, _metadata: &log::Metadata) -> bool {
-        option_env!("LOG").is_some()
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
     }
 
     fn log(&self, record: &log::Record) {
@@ -1,3 +1,5 @@
+pub use cmdline::*;
 pub use logger::*;
 
+mod cmdline;
 mod logger;
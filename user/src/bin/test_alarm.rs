@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use user_lib::{alarm, get_time, println, signal, yield_, TimeVal, SIGALRM};
+
+extern crate user_lib;
+
+static FIRED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_alarm(_signo: usize) {
+    FIRED.store(true, Ordering::SeqCst);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_alarm");
+    assert_eq!(signal(SIGALRM, on_alarm), 0);
+
+    // a new alarm replaces the old one, and reports what was left of it
+    assert_eq!(alarm(5), 0);
+    assert_eq!(alarm(1), 5);
+
+    let time = get_time();
+    while !FIRED.load(Ordering::SeqCst) {
+        assert!(
+            get_time() - time < TimeVal::new(3, 0),
+            "SIGALRM not delivered"
+        );
+        yield_();
+    }
+    assert_eq!(alarm(0), 0);
+    println!("[User] test_alarm: done");
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{
+    close, epoll_create, epoll_ctl, epoll_wait, fork, pipe, println, read_fd, waitpid, write,
+    EpollEvent, EPOLLET, EPOLLIN, EPOLLONESHOT, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD,
+};
+
+extern crate user_lib;
+
+const EEXIST: isize = 17;
+const DATA: u64 = 0x1234_5678_9abc;
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_epoll");
+    let mut fds = [0i32; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (read_end, write_end) = (fds[0] as usize, fds[1] as usize);
+
+    let epoll_fd = epoll_create();
+    assert!(epoll_fd >= 0);
+    let epoll_fd = epoll_fd as usize;
+    let event = EpollEvent::new(EPOLLIN, DATA);
+    assert_eq!(epoll_ctl(epoll_fd, EPOLL_CTL_ADD, read_end, event), 0);
+    assert_eq!(epoll_ctl(epoll_fd, EPOLL_CTL_ADD, read_end, event), -EEXIST);
+
+    let mut events = [EpollEvent::new(0, 0); 4];
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 0);
+
+    // blocks until the child writes
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(write(write_end, b"x"), 1);
+        return 0;
+    }
+    assert_eq!(epoll_wait(epoll_fd, &mut events, -1), 1);
+    assert_eq!(events[0].events, EPOLLIN);
+    assert_eq!(events[0].data, DATA);
+    let mut exit_code = 0;
+    waitpid(pid as usize, &mut exit_code);
+    assert_eq!(exit_code, 0);
+
+    // level-triggered, reported again while unread
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 1);
+
+    // edge-triggered, reported once per write
+    let event = EpollEvent::new(EPOLLIN | EPOLLET, DATA);
+    assert_eq!(epoll_ctl(epoll_fd, EPOLL_CTL_MOD, read_end, event), 0);
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 1);
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 0);
+    assert_eq!(write(write_end, b"y"), 1);
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 1);
+
+    // one-shot, disabled until rearmed
+    let event = EpollEvent::new(EPOLLIN | EPOLLONESHOT, DATA);
+    assert_eq!(epoll_ctl(epoll_fd, EPOLL_CTL_MOD, read_end, event), 0);
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 1);
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 0);
+
+    let mut buf = [0u8; 2];
+    assert_eq!(read_fd(read_end, &mut buf), 2);
+    assert_eq!(epoll_ctl(epoll_fd, EPOLL_CTL_DEL, read_end, event), 0);
+    assert_eq!(write(write_end, b"z"), 1);
+    assert_eq!(epoll_wait(epoll_fd, &mut events, 0), 0);
+
+    close(epoll_fd);
+    close(read_end);
+    close(write_end);
+    println!("[User] test_epoll: done");
+    0
+}
@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{
+    close, eventfd, get_time, getpid, kill, println, read_fd, sigmask, signalfd, sigprocmask,
+    timerfd_create, timerfd_settime, write, TimeVal, CLOCK_MONOTONIC, EFD_SEMAPHORE, O_NONBLOCK,
+    SIGUSR1, SIG_BLOCK, SIG_UNBLOCK,
+};
+
+extern crate user_lib;
+
+const EAGAIN: isize = 11;
+
+fn read_u64(fd: usize) -> Result<u64, isize> {
+    let mut buf = [0u8; 8];
+    match read_fd(fd, &mut buf) {
+        8 => Ok(u64::from_ne_bytes(buf)),
+        len => Err(-len),
+    }
+}
+
+fn test_eventfd() {
+    // writes add up, a read takes the whole counter
+    let fd = eventfd(3, O_NONBLOCK) as usize;
+    assert_eq!(write(fd, &4u64.to_ne_bytes()), 8);
+    assert_eq!(read_u64(fd), Ok(7));
+    assert_eq!(read_u64(fd), Err(EAGAIN));
+    close(fd);
+
+    // a semaphore is taken one at a time
+    let fd = eventfd(2, EFD_SEMAPHORE | O_NONBLOCK) as usize;
+    assert_eq!(read_u64(fd), Ok(1));
+    assert_eq!(read_u64(fd), Ok(1));
+    assert_eq!(read_u64(fd), Err(EAGAIN));
+    close(fd);
+    println!("[User] test_eventfd: eventfd passed");
+}
+
+fn test_timerfd() {
+    let fd = timerfd_create(CLOCK_MONOTONIC) as usize;
+    let time = get_time();
+    assert_eq!(timerfd_settime(fd, 50, 0), 0);
+    // blocks until the timer fires
+    assert_eq!(read_u64(fd), Ok(1));
+    assert!(get_time() - time >= TimeVal::new(0, 40_000));
+    close(fd);
+    println!("[User] test_eventfd: timerfd passed");
+}
+
+fn test_signalfd() {
+    // blocked, so the signal waits for the signalfd instead of killing us
+    let mask = sigmask(SIGUSR1);
+    sigprocmask(SIG_BLOCK, mask);
+    let fd = signalfd(mask, 0) as usize;
+    assert_eq!(kill(getpid() as usize, SIGUSR1), 0);
+
+    // struct signalfd_siginfo starts with ssi_signo
+    let mut info = [0u8; 128];
+    assert_eq!(read_fd(fd, &mut info), 128);
+    let signo = u32::from_ne_bytes([info[0], info[1], info[2], info[3]]);
+    assert_eq!(signo as usize, SIGUSR1);
+    close(fd);
+    sigprocmask(SIG_UNBLOCK, mask);
+    println!("[User] test_eventfd: signalfd passed");
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_eventfd");
+    test_eventfd();
+    test_timerfd();
+    test_signalfd();
+    println!("[User] test_eventfd: done");
+    0
+}
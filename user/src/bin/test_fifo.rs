@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{
+    close, fork, mkdir, mkfifo, mount, open, println, read_fd, unlink, waitpid, write, O_NONBLOCK,
+    O_RDONLY, O_WRONLY,
+};
+
+extern crate user_lib;
+
+// FAT has no special files, so the FIFO lives on a tmpfs
+const DIR: &str = "/tmp\0";
+const FIFO: &str = "/tmp/test_fifo\0";
+const ENXIO: isize = 6;
+const EBUSY: isize = 16;
+const MESSAGE: &[u8] = b"through a named pipe";
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_fifo");
+    // the directory and the mount are left from an earlier run
+    mkdir(DIR);
+    let mounted = mount("tmpfs\0", DIR, "tmpfs\0");
+    assert!(mounted == 0 || mounted == -EBUSY);
+    assert_eq!(mkfifo(FIFO), 0);
+
+    // nobody would read what is written
+    assert_eq!(open(FIFO, O_WRONLY | O_NONBLOCK), -ENXIO);
+
+    let pid = fork();
+    if pid == 0 {
+        // waits for the reader to open
+        let fd = open(FIFO, O_WRONLY);
+        assert!(fd >= 0);
+        assert_eq!(write(fd as usize, MESSAGE), MESSAGE.len() as isize);
+        close(fd as usize);
+        return 0;
+    }
+
+    let fd = open(FIFO, O_RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 64];
+    let mut read = 0;
+    loop {
+        let len = read_fd(fd as usize, &mut buf[read..]);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        read += len as usize;
+    }
+    assert_eq!(&buf[..read], MESSAGE);
+    close(fd as usize);
+
+    let mut exit_code = 0;
+    waitpid(pid as usize, &mut exit_code);
+    assert_eq!(exit_code, 0);
+    assert_eq!(unlink(FIFO), 0);
+    println!("[User] test_fifo: done");
+    0
+}
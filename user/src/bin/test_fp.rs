@@ -0,0 +1,147 @@
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+use user_lib::{fork, getpid, println, waitpid};
+
+extern crate user_lib;
+
+const ROUNDS: usize = 50;
+const SYSCALL_YIELD: usize = 124;
+
+// load all FP registers, yield to the other process, then store them back
+fn yield_with_fp(src: &[f64; 32], dst: &mut [f64; 32]) {
+    unsafe {
+        asm!(
+            "fld f0, 0({src})",
+            "fld f1, 8({src})",
+            "fld f2, 16({src})",
+            "fld f3, 24({src})",
+            "fld f4, 32({src})",
+            "fld f5, 40({src})",
+            "fld f6, 48({src})",
+            "fld f7, 56({src})",
+            "fld f8, 64({src})",
+            "fld f9, 72({src})",
+            "fld f10, 80({src})",
+            "fld f11, 88({src})",
+            "fld f12, 96({src})",
+            "fld f13, 104({src})",
+            "fld f14, 112({src})",
+            "fld f15, 120({src})",
+            "fld f16, 128({src})",
+            "fld f17, 136({src})",
+            "fld f18, 144({src})",
+            "fld f19, 152({src})",
+            "fld f20, 160({src})",
+            "fld f21, 168({src})",
+            "fld f22, 176({src})",
+            "fld f23, 184({src})",
+            "fld f24, 192({src})",
+            "fld f25, 200({src})",
+            "fld f26, 208({src})",
+            "fld f27, 216({src})",
+            "fld f28, 224({src})",
+            "fld f29, 232({src})",
+            "fld f30, 240({src})",
+            "fld f31, 248({src})",
+            "ecall",
+            "fsd f0, 0({dst})",
+            "fsd f1, 8({dst})",
+            "fsd f2, 16({dst})",
+            "fsd f3, 24({dst})",
+            "fsd f4, 32({dst})",
+            "fsd f5, 40({dst})",
+            "fsd f6, 48({dst})",
+            "fsd f7, 56({dst})",
+            "fsd f8, 64({dst})",
+            "fsd f9, 72({dst})",
+            "fsd f10, 80({dst})",
+            "fsd f11, 88({dst})",
+            "fsd f12, 96({dst})",
+            "fsd f13, 104({dst})",
+            "fsd f14, 112({dst})",
+            "fsd f15, 120({dst})",
+            "fsd f16, 128({dst})",
+            "fsd f17, 136({dst})",
+            "fsd f18, 144({dst})",
+            "fsd f19, 152({dst})",
+            "fsd f20, 160({dst})",
+            "fsd f21, 168({dst})",
+            "fsd f22, 176({dst})",
+            "fsd f23, 184({dst})",
+            "fsd f24, 192({dst})",
+            "fsd f25, 200({dst})",
+            "fsd f26, 208({dst})",
+            "fsd f27, 216({dst})",
+            "fsd f28, 224({dst})",
+            "fsd f29, 232({dst})",
+            "fsd f30, 240({dst})",
+            "fsd f31, 248({dst})",
+            src = in(reg) src.as_ptr(),
+            dst = in(reg) dst.as_mut_ptr(),
+            in("a7") SYSCALL_YIELD,
+            out("a0") _,
+            out("f0") _,
+            out("f1") _,
+            out("f2") _,
+            out("f3") _,
+            out("f4") _,
+            out("f5") _,
+            out("f6") _,
+            out("f7") _,
+            out("f8") _,
+            out("f9") _,
+            out("f10") _,
+            out("f11") _,
+            out("f12") _,
+            out("f13") _,
+            out("f14") _,
+            out("f15") _,
+            out("f16") _,
+            out("f17") _,
+            out("f18") _,
+            out("f19") _,
+            out("f20") _,
+            out("f21") _,
+            out("f22") _,
+            out("f23") _,
+            out("f24") _,
+            out("f25") _,
+            out("f26") _,
+            out("f27") _,
+            out("f28") _,
+            out("f29") _,
+            out("f30") _,
+            out("f31") _,
+        );
+    }
+}
+
+fn run(seed: f64) {
+    for round in 0..ROUNDS {
+        let mut src = [0f64; 32];
+        for (i, value) in src.iter_mut().enumerate() {
+            *value = seed + (round * 32 + i) as f64 * 0.5;
+        }
+        let mut dst = [0f64; 32];
+        yield_with_fp(&src, &mut dst);
+        assert!(src == dst, "FP registers clobbered in round {}", round);
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_fp");
+    let pid = fork();
+    // both sides keep switching while their registers hold different values
+    run(getpid() as f64 * 10000.0);
+    if pid == 0 {
+        return 0;
+    }
+    let mut exit_code = 0;
+    waitpid(pid as usize, &mut exit_code);
+    assert_eq!(exit_code, 0);
+    println!("[User] test_fp: done");
+    0
+}
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use user_lib::{
+    clone, exit, futex, futex_wait, futex_wake, get_time, getpid, println, set_tid_address,
+    waitpid, TimeVal, FUTEX_PRIVATE_FLAG, FUTEX_WAIT_BITSET,
+};
+
+extern crate user_lib;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+const ETIMEDOUT: isize = 110;
+
+const SIGCHLD: usize = 17;
+const CLONE_VM: usize = 0x0000_0100;
+const CLONE_THREAD: usize = 0x0001_0000;
+const CLONE_CHILD_CLEARTID: usize = 0x0020_0000;
+
+static WORD: AtomicU32 = AtomicU32::new(0);
+
+fn test_wait() {
+    // the word changed before the wait
+    assert_eq!(futex_wait(&WORD, 1, None), -EAGAIN);
+
+    let time = get_time();
+    assert_eq!(futex_wait(&WORD, 0, Some(50)), -ETIMEDOUT);
+    assert!(get_time() - time >= TimeVal::new(0, 40_000));
+
+    assert_eq!(futex_wake(&WORD, 1), 0);
+    // a bitset matching no waker
+    assert_eq!(
+        futex(&WORD, FUTEX_WAIT_BITSET | FUTEX_PRIVATE_FLAG, 0, 0),
+        -EINVAL
+    );
+    println!("[User] test_futex: wait passed");
+}
+
+fn test_clone() {
+    // a child never shares the address space, so threads are refused
+    assert_eq!(clone(CLONE_VM | SIGCHLD, 0, None), -EINVAL);
+    assert_eq!(clone(CLONE_THREAD | SIGCHLD, 0, None), -EINVAL);
+
+    // join the children as processes, a word is cleared in the copy of the child
+    let pid = clone(CLONE_CHILD_CLEARTID | SIGCHLD, 0, Some(&WORD));
+    if pid == 0 {
+        WORD.store(getpid() as u32, Ordering::SeqCst);
+        exit(7);
+    }
+    assert!(pid > 0);
+    let mut wstatus = 0;
+    assert_eq!(waitpid(pid as usize, &mut wstatus), pid);
+    assert_eq!(wstatus >> 8, 7);
+
+    let pid = clone(SIGCHLD, 0, None);
+    if pid == 0 {
+        assert_eq!(set_tid_address(&WORD), getpid());
+        WORD.store(getpid() as u32, Ordering::SeqCst);
+        exit(7);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut wstatus), pid);
+    assert_eq!(wstatus >> 8, 7);
+    assert_eq!(WORD.load(Ordering::SeqCst), 0);
+    println!("[User] test_futex: clone passed");
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_futex");
+    test_wait();
+    test_clone();
+    println!("[User] test_futex: done");
+    0
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{
+    close, fcntl, fork, pipe, println, read_fd, waitpid, write, F_GETPIPE_SZ, F_SETPIPE_SZ,
+};
+
+extern crate user_lib;
+
+// more than the buffer holds, so both sides have to block in turn
+const TOTAL: usize = 3 * 65536 + 123;
+
+fn byte_at(pos: usize) -> u8 {
+    (pos % 251) as u8
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_pipe");
+    let mut fds = [0i32; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (read_end, write_end) = (fds[0] as usize, fds[1] as usize);
+
+    assert_eq!(fcntl(write_end, F_GETPIPE_SZ, 0), 65536);
+    // rounded up to whole pages
+    assert_eq!(fcntl(write_end, F_SETPIPE_SZ, 5000), 8192);
+    assert_eq!(fcntl(read_end, F_GETPIPE_SZ, 0), 8192);
+
+    let pid = fork();
+    if pid == 0 {
+        close(read_end);
+        let mut buf = [0u8; 1000];
+        let mut written = 0;
+        while written < TOTAL {
+            let len = buf.len().min(TOTAL - written);
+            for (i, byte) in buf[..len].iter_mut().enumerate() {
+                *byte = byte_at(written + i);
+            }
+            assert_eq!(write(write_end, &buf[..len]), len as isize);
+            written += len;
+        }
+        close(write_end);
+        return 0;
+    }
+
+    // the read end sees the end of file once the child's write end is closed
+    close(write_end);
+    let mut buf = [0u8; 3000];
+    let mut read = 0;
+    loop {
+        let len = read_fd(read_end, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        for (i, byte) in buf[..len as usize].iter().enumerate() {
+            assert_eq!(*byte, byte_at(read + i));
+        }
+        read += len as usize;
+    }
+    assert_eq!(read, TOTAL);
+    close(read_end);
+
+    let mut exit_code = 0;
+    waitpid(pid as usize, &mut exit_code);
+    assert_eq!(exit_code, 0);
+    println!("[User] test_pipe: done");
+    0
+}
@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{
+    close, fork, get_time, pipe, poll, println, read_fd, select, waitpid, write, PollFd, TimeVal,
+    POLLHUP, POLLIN, POLLOUT,
+};
+
+extern crate user_lib;
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_poll");
+    let mut fds = [0i32; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (read_end, write_end) = (fds[0] as usize, fds[1] as usize);
+
+    // an empty pipe is only writable
+    let mut poll_fds = [
+        PollFd::new(read_end, POLLIN),
+        PollFd::new(write_end, POLLOUT),
+    ];
+    assert_eq!(poll(&mut poll_fds, Some(0)), 1);
+    assert_eq!(poll_fds[0].revents, 0);
+    assert_eq!(poll_fds[1].revents, POLLOUT);
+
+    // nothing shows up before the timeout
+    let time = get_time();
+    let mut poll_fds = [PollFd::new(read_end, POLLIN)];
+    assert_eq!(poll(&mut poll_fds, Some(50)), 0);
+    assert!(get_time() - time >= TimeVal::new(0, 40_000));
+
+    let pid = fork();
+    if pid == 0 {
+        close(read_end);
+        assert_eq!(write(write_end, b"x"), 1);
+        close(write_end);
+        return 0;
+    }
+    close(write_end);
+
+    // blocks until the child writes
+    let mut poll_fds = [PollFd::new(read_end, POLLIN)];
+    assert_eq!(poll(&mut poll_fds, None), 1);
+    assert!(poll_fds[0].revents & POLLIN != 0);
+
+    let mut read_set = 1u64 << read_end;
+    assert_eq!(select(read_end + 1, Some(&mut read_set), None, Some(0)), 1);
+    assert_eq!(read_set, 1 << read_end);
+
+    let mut buf = [0u8; 1];
+    assert_eq!(read_fd(read_end, &mut buf), 1);
+    let mut exit_code = 0;
+    waitpid(pid as usize, &mut exit_code);
+    assert_eq!(exit_code, 0);
+
+    // the hangup is reported without asking for it
+    let mut poll_fds = [PollFd::new(read_end, 0)];
+    assert_eq!(poll(&mut poll_fds, Some(0)), 1);
+    assert_eq!(poll_fds[0].revents, POLLHUP);
+    close(read_end);
+    println!("[User] test_poll: done");
+    0
+}
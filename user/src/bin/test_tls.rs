@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+use user_lib::{clone, exit, println, waitpid, yield_};
+
+extern crate user_lib;
+
+const SIGCHLD: usize = 17;
+const CLONE_SETTLS: usize = 0x0008_0000;
+
+// stands in for the TLS block of a new thread
+static mut BLOCK: [u64; 4] = [0; 4];
+
+fn get_tp() -> usize {
+    let tp: usize;
+    unsafe {
+        asm!("mv {}, tp", out(reg) tp);
+    }
+    tp
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("[User] test_tls");
+    let parent_tp = get_tp();
+    let tls = core::ptr::addr_of!(BLOCK) as usize;
+
+    let pid = clone(CLONE_SETTLS | SIGCHLD, tls, None);
+    if pid == 0 {
+        let tp = get_tp();
+        // tp is kept across traps like any other register
+        yield_();
+        exit(if tp == tls && get_tp() == tls { 0 } else { 1 });
+    }
+    assert!(pid > 0);
+    let mut wstatus = 0;
+    assert_eq!(waitpid(pid as usize, &mut wstatus), pid);
+    assert_eq!(wstatus >> 8, 0);
+    // only the child is given the new tp
+    assert_eq!(get_tp(), parent_tp);
+    println!("[User] test_tls: done");
+    0
+}
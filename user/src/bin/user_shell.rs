@@ -8,7 +8,9 @@ use user_lib::{
 extern crate user_lib;
 
 const SHELL_NAME: &str = "Miku Shell";
+const COMMAND_NOT_EXECUTABLE: i32 = 126;
 const COMMAND_NOT_FOUND: i32 = 127;
+const ENOEXEC: isize = 8;
 
 const LF: u8 = 0x0au8;
 const CR: u8 = 0x0du8;
@@ -49,14 +51,19 @@ fn main() -> i32 {
                             let path = input.as_str();
                             let pid = fork();
                             if pid == 0 {
-                                if exec(path) == -1 {
+                                if exec(path) == -ENOEXEC {
+                                    println!("{}: {}: exec format error", SHELL_NAME, path);
+                                    exit(COMMAND_NOT_EXECUTABLE);
+                                } else {
                                     println!("{}: {}: command not found", SHELL_NAME, path);
                                     exit(COMMAND_NOT_FOUND);
                                 }
                             } else {
                                 let mut exit_code = 0;
                                 let zombie_pid = waitpid(pid as usize, &mut exit_code);
-                                if exit_code != COMMAND_NOT_FOUND {
+                                if exit_code != COMMAND_NOT_FOUND
+                                    && exit_code != COMMAND_NOT_EXECUTABLE
+                                {
                                     println!(
                                         "{}: process '{}' (PID={}) exited with code {}",
                                         SHELL_NAME, path, zombie_pid, exit_code
@@ -1,8 +1,27 @@
-use super::syscall;
+use super::{syscall, syscall6};
 
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
 const SYSCALL_CHDIR: usize = 49;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_MKNODAT: usize = 33;
+const SYSCALL_MKDIRAT: usize = 34;
+const SYSCALL_UNLINK: usize = 35;
+const SYSCALL_MOUNT: usize = 40;
+const SYSCALL_EVENTFD2: usize = 19;
+const SYSCALL_EPOLL_CREATE1: usize = 20;
+const SYSCALL_EPOLL_CTL: usize = 21;
+const SYSCALL_EPOLL_PWAIT: usize = 22;
+const SYSCALL_PSELECT6: usize = 72;
+const SYSCALL_PPOLL: usize = 73;
+const SYSCALL_SIGNALFD4: usize = 74;
+const SYSCALL_TIMERFD_CREATE: usize = 85;
+const SYSCALL_TIMERFD_SETTIME: usize = 86;
+// the current directory for the *at syscalls
+const AT_FDCWD: isize = -100;
 
 pub fn sys_read(fd: usize, buf: &mut [u8]) -> isize {
     syscall(SYSCALL_READ, [fd, buf.as_mut_ptr() as usize, buf.len()])
@@ -15,3 +34,148 @@ pub fn sys_write(fd: usize, buf: &[u8]) -> isize {
 pub fn sys_chdir(path: &str) -> isize {
     syscall(SYSCALL_CHDIR, [path.as_ptr() as usize, 0, 0])
 }
+
+pub fn sys_close(fd: usize) -> isize {
+    syscall(SYSCALL_CLOSE, [fd, 0, 0])
+}
+
+pub fn sys_pipe2(fds: &mut [i32; 2], flags: usize) -> isize {
+    syscall(SYSCALL_PIPE, [fds.as_mut_ptr() as usize, flags, 0])
+}
+
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    syscall(SYSCALL_FCNTL, [fd, cmd, arg])
+}
+
+pub fn sys_open(path: &str, flags: usize) -> isize {
+    syscall(
+        SYSCALL_OPEN,
+        [AT_FDCWD as usize, path.as_ptr() as usize, flags],
+    )
+}
+
+pub fn sys_mknod(path: &str, mode: usize) -> isize {
+    syscall6(
+        SYSCALL_MKNODAT,
+        [AT_FDCWD as usize, path.as_ptr() as usize, mode, 0, 0, 0],
+    )
+}
+
+pub fn sys_unlink(path: &str) -> isize {
+    syscall(
+        SYSCALL_UNLINK,
+        [AT_FDCWD as usize, path.as_ptr() as usize, 0],
+    )
+}
+
+pub fn sys_mkdir(path: &str, mode: usize) -> isize {
+    syscall(
+        SYSCALL_MKDIRAT,
+        [AT_FDCWD as usize, path.as_ptr() as usize, mode],
+    )
+}
+
+pub fn sys_mount(source: &str, target: &str, fs_type: &str) -> isize {
+    syscall6(
+        SYSCALL_MOUNT,
+        [
+            source.as_ptr() as usize,
+            target.as_ptr() as usize,
+            fs_type.as_ptr() as usize,
+            0,
+            0,
+            0,
+        ],
+    )
+}
+
+// `timeout` is a struct timespec, { sec, nsec }, null waits forever
+pub fn sys_ppoll(fds: *mut u8, nfds: usize, timeout: *const [usize; 2]) -> isize {
+    syscall6(
+        SYSCALL_PPOLL,
+        [fds as usize, nfds, timeout as usize, 0, 0, 0],
+    )
+}
+
+pub fn sys_pselect6(
+    nfds: usize,
+    read_fds: *mut u64,
+    write_fds: *mut u64,
+    timeout: *const [usize; 2],
+) -> isize {
+    syscall6(
+        SYSCALL_PSELECT6,
+        [
+            nfds,
+            read_fds as usize,
+            write_fds as usize,
+            0,
+            timeout as usize,
+            0,
+        ],
+    )
+}
+
+pub fn sys_epoll_create1(flags: usize) -> isize {
+    syscall(SYSCALL_EPOLL_CREATE1, [flags, 0, 0])
+}
+
+pub fn sys_epoll_ctl(epoll_fd: usize, op: usize, fd: usize, event: *const u8) -> isize {
+    syscall6(SYSCALL_EPOLL_CTL, [epoll_fd, op, fd, event as usize, 0, 0])
+}
+
+pub fn sys_epoll_pwait(epoll_fd: usize, events: *mut u8, max_events: usize, timeout: i32) -> isize {
+    syscall6(
+        SYSCALL_EPOLL_PWAIT,
+        [
+            epoll_fd,
+            events as usize,
+            max_events,
+            timeout as usize,
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_eventfd2(init_value: usize, flags: usize) -> isize {
+    syscall(SYSCALL_EVENTFD2, [init_value, flags, 0])
+}
+
+pub fn sys_timerfd_create(clock_id: usize, flags: usize) -> isize {
+    syscall(SYSCALL_TIMERFD_CREATE, [clock_id, flags, 0])
+}
+
+// struct itimerspec, { it_interval, it_value } of struct timespec
+pub fn sys_timerfd_settime(
+    fd: usize,
+    flags: usize,
+    new: &[usize; 4],
+    old: &mut [usize; 4],
+) -> isize {
+    syscall6(
+        SYSCALL_TIMERFD_SETTIME,
+        [
+            fd,
+            flags,
+            new.as_ptr() as usize,
+            old.as_mut_ptr() as usize,
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_signalfd4(fd: isize, mask: &u64, flags: usize) -> isize {
+    syscall6(
+        SYSCALL_SIGNALFD4,
+        [
+            fd as usize,
+            mask as *const u64 as usize,
+            core::mem::size_of::<u64>(),
+            flags,
+            0,
+            0,
+        ],
+    )
+}
@@ -20,3 +20,22 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
     }
     ret
 }
+
+// for the syscalls taking more than three arguments
+#[inline(always)]
+fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    let ret: isize;
+    unsafe {
+        asm!(
+            "ecall",
+            in("a7") id,
+            inlateout("a0") args[0] => ret,
+            in("a1") args[1],
+            in("a2") args[2],
+            in("a3") args[3],
+            in("a4") args[4],
+            in("a5") args[5],
+        );
+    }
+    ret
+}
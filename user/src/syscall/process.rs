@@ -1,4 +1,4 @@
-use super::syscall;
+use super::{syscall, syscall6};
 
 const SYSCALL_EXIT: usize = 93;
 const SYSCALL_YIELD: usize = 124;
@@ -6,8 +6,14 @@ const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_SBRK: usize = 214;
 const SYSCALL_GETPID: usize = 172;
 const SYSCALL_FORK: usize = 220;
+const SYSCALL_SET_TID_ADDRESS: usize = 96;
+const SYSCALL_FUTEX: usize = 98;
 const SYSCALL_EXEC: usize = 221;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_RT_SIGACTION: usize = 134;
+const SYSCALL_RT_SIGPROCMASK: usize = 135;
 
 pub fn sys_exit(code: i32) -> ! {
     syscall(SYSCALL_EXIT, [code as usize, 0, 0]);
@@ -19,6 +25,35 @@ pub fn sys_yield() -> isize {
 }
 
 use crate::timer::TimeVal;
+
+// { it_interval, it_value }
+pub fn sys_setitimer(which: usize, new: &[TimeVal; 2], old: &mut [TimeVal; 2]) -> isize {
+    syscall(
+        SYSCALL_SETITIMER,
+        [which, new.as_ptr() as usize, old.as_mut_ptr() as usize],
+    )
+}
+
+// struct sigaction of the kernel ABI, { handler, flags, mask }
+pub fn sys_sigaction(signo: usize, act: &[usize; 3], old: &mut [usize; 3]) -> isize {
+    syscall(
+        SYSCALL_RT_SIGACTION,
+        [signo, act.as_ptr() as usize, old.as_mut_ptr() as usize],
+    )
+}
+
+pub fn sys_kill(pid: isize, signo: usize) -> isize {
+    syscall(SYSCALL_KILL, [pid as usize, signo, 0])
+}
+
+// a sigset_t has signal `n` at bit `n - 1`
+pub fn sys_sigprocmask(how: usize, set: &u64, old: &mut u64) -> isize {
+    syscall(
+        SYSCALL_RT_SIGPROCMASK,
+        [how, set as *const u64 as usize, old as *mut u64 as usize],
+    )
+}
+
 pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
     syscall(SYSCALL_GET_TIME, [ts as usize, _tz, 0])
 }
@@ -36,7 +71,38 @@ pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [SIGCHLD, 0, 0])
 }
 
-pub fn sys_exec(path: &str, argv: &[&str]) -> isize {
+// clone is the syscall behind fork, the child keeps the stack with a null `sp`
+pub fn sys_clone(flags: usize, sp: usize, tls: usize, child_tid: *mut u32) -> isize {
+    syscall6(SYSCALL_FORK, [flags, sp, 0, tls, child_tid as usize, 0])
+}
+
+pub fn sys_set_tid_address(tid: *mut u32) -> isize {
+    syscall(SYSCALL_SET_TID_ADDRESS, [tid as usize, 0, 0])
+}
+
+// `timeout` is a relative struct timespec for FUTEX_WAIT, null waits forever
+pub fn sys_futex(
+    addr: *const u32,
+    op: usize,
+    value: u32,
+    timeout: *const [usize; 2],
+    value3: u32,
+) -> isize {
+    syscall6(
+        SYSCALL_FUTEX,
+        [
+            addr as usize,
+            op,
+            value as usize,
+            timeout as usize,
+            0,
+            value3 as usize,
+        ],
+    )
+}
+
+/// `argv` is NULL terminated, the environment is left empty
+pub fn sys_exec(path: &str, argv: &[*const u8]) -> isize {
     syscall(
         SYSCALL_EXEC,
         [path.as_ptr() as usize, argv.as_ptr() as usize, 0],
@@ -1,4 +1,5 @@
 use crate::syscall;
+use alloc::vec::Vec;
 
 pub fn exit(code: i32) -> isize {
     syscall::sys_exit(code)
@@ -8,7 +9,7 @@ pub fn yield_() -> isize {
     syscall::sys_yield()
 }
 
-use crate::timer::TimeVal;
+use crate::timer::{TimeUnit, TimeVal};
 
 pub fn get_time() -> TimeVal {
     let mut ts = TimeVal::empty();
@@ -16,11 +17,53 @@ pub fn get_time() -> TimeVal {
     ts
 }
 
+/// Deliver SIGALRM in `seconds`, return the seconds left of the previous alarm
+pub fn alarm(seconds: usize) -> usize {
+    const ITIMER_REAL: usize = 0;
+    let new = [TimeVal::empty(), TimeVal::new(seconds, 0)];
+    let mut old = [TimeVal::empty(); 2];
+    syscall::sys_setitimer(ITIMER_REAL, &new, &mut old);
+    // rounded up, like alarm(2)
+    let left = old[1].get_time(TimeUnit::Usec);
+    left.div_ceil(1_000_000)
+}
+
+pub const SIGUSR1: usize = 10;
+pub const SIGALRM: usize = 14;
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+
+pub fn kill(pid: usize, signo: usize) -> isize {
+    syscall::sys_kill(pid as isize, signo)
+}
+
+/// Change the blocked signals, return the old mask
+pub fn sigprocmask(how: usize, mask: u64) -> u64 {
+    let mut old = 0;
+    syscall::sys_sigprocmask(how, &mask, &mut old);
+    old
+}
+
+pub fn sigmask(signo: usize) -> u64 {
+    1 << (signo - 1)
+}
+
+/// Catch `signo` with `handler`, return the errno negated on failure
+pub fn signal(signo: usize, handler: extern "C" fn(usize)) -> isize {
+    let act = [handler as usize, 0, 0];
+    let mut old = [0; 3];
+    syscall::sys_sigaction(signo, &act, &mut old)
+}
+
 pub fn read(buf: &mut [u8]) -> isize {
     let fd = 0;
     syscall::sys_read(fd, buf)
 }
 
+pub fn read_fd(fd: usize, buf: &mut [u8]) -> isize {
+    syscall::sys_read(fd, buf)
+}
+
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     syscall::sys_write(fd, buf)
 }
@@ -38,12 +81,25 @@ pub fn fork() -> isize {
     syscall::sys_fork()
 }
 
+/// `tls` is used with CLONE_SETTLS, `child_tid` with CLONE_CHILD_CLEARTID
+pub fn clone(flags: usize, tls: usize, child_tid: Option<&core::sync::atomic::AtomicU32>) -> isize {
+    let child_tid = child_tid.map_or(core::ptr::null_mut(), |tid| tid.as_ptr());
+    syscall::sys_clone(flags, 0, tls, child_tid)
+}
+
+pub fn set_tid_address(tid: &core::sync::atomic::AtomicU32) -> isize {
+    syscall::sys_set_tid_address(tid.as_ptr())
+}
+
 pub fn exec(path: &str) -> isize {
-    syscall::sys_exec(path, &[])
+    syscall::sys_exec(path, &[path.as_ptr(), core::ptr::null()])
 }
 
+/// Like exec, every arg ends with \0
 pub fn exec_with_argv(path: &str, argv: &[&str]) -> isize {
-    syscall::sys_exec(path, argv)
+    let mut argv: Vec<*const u8> = argv.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(core::ptr::null());
+    syscall::sys_exec(path, &argv)
 }
 
 pub fn wait(wstatus: &mut i32) -> isize {
@@ -57,3 +113,212 @@ pub fn waitpid(pid: usize, wstatus: &mut i32) -> isize {
 pub fn chdir(path: &str) -> isize {
     syscall::sys_chdir(path)
 }
+
+pub fn close(fd: usize) -> isize {
+    syscall::sys_close(fd)
+}
+
+/// `fds` gets the read end and then the write end
+pub fn pipe(fds: &mut [i32; 2]) -> isize {
+    syscall::sys_pipe2(fds, 0)
+}
+
+pub const F_SETPIPE_SZ: usize = 1031;
+pub const F_GETPIPE_SZ: usize = 1032;
+
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    syscall::sys_fcntl(fd, cmd, arg)
+}
+
+pub const O_RDONLY: usize = 0;
+pub const O_WRONLY: usize = 1;
+pub const O_NONBLOCK: usize = 1 << 11;
+
+/// Like exec, `path` ends with \0
+pub fn open(path: &str, flags: usize) -> isize {
+    syscall::sys_open(path, flags)
+}
+
+/// Create a named pipe at `path`, which ends with \0
+pub fn mkfifo(path: &str) -> isize {
+    const S_IFIFO: usize = 0o010000;
+    syscall::sys_mknod(path, S_IFIFO | 0o644)
+}
+
+pub fn unlink(path: &str) -> isize {
+    syscall::sys_unlink(path)
+}
+
+pub fn mkdir(path: &str) -> isize {
+    syscall::sys_mkdir(path, 0o755)
+}
+
+/// Every string ends with \0, only tmpfs is really mounted
+pub fn mount(source: &str, target: &str, fs_type: &str) -> isize {
+    syscall::sys_mount(source, target, fs_type)
+}
+
+pub const POLLIN: i16 = 0x1;
+pub const POLLOUT: i16 = 0x4;
+pub const POLLHUP: i16 = 0x10;
+
+// region PollFd begin
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+impl PollFd {
+    pub fn new(fd: usize, events: i16) -> Self {
+        Self {
+            fd: fd as i32,
+            events,
+            revents: 0,
+        }
+    }
+}
+// region PollFd end
+
+// None waits forever
+fn to_timespec(timeout_ms: Option<usize>) -> Option<[usize; 2]> {
+    timeout_ms.map(|ms| [ms / 1000, ms % 1000 * 1_000_000])
+}
+
+pub const FUTEX_WAIT: usize = 0;
+pub const FUTEX_WAKE: usize = 1;
+pub const FUTEX_WAIT_BITSET: usize = 9;
+pub const FUTEX_PRIVATE_FLAG: usize = 128;
+
+/// Sleep while `word` holds `value`, the errno negated once woken up otherwise
+pub fn futex_wait(
+    word: &core::sync::atomic::AtomicU32,
+    value: u32,
+    timeout_ms: Option<usize>,
+) -> isize {
+    let timeout = to_timespec(timeout_ms);
+    let timeout_ptr = timeout
+        .as_ref()
+        .map_or(core::ptr::null(), |ts| ts as *const _);
+    syscall::sys_futex(
+        word.as_ptr(),
+        FUTEX_WAIT | FUTEX_PRIVATE_FLAG,
+        value,
+        timeout_ptr,
+        0,
+    )
+}
+
+/// Return how many waiters are woken up
+pub fn futex_wake(word: &core::sync::atomic::AtomicU32, count: u32) -> isize {
+    syscall::sys_futex(
+        word.as_ptr(),
+        FUTEX_WAKE | FUTEX_PRIVATE_FLAG,
+        count,
+        core::ptr::null(),
+        0,
+    )
+}
+
+/// `op` with raw arguments, for the cases the wrappers above do not cover
+pub fn futex(word: &core::sync::atomic::AtomicU32, op: usize, value: u32, value3: u32) -> isize {
+    syscall::sys_futex(word.as_ptr(), op, value, core::ptr::null(), value3)
+}
+
+/// Return the number of ready fds, 0 on timeout
+pub fn poll(fds: &mut [PollFd], timeout_ms: Option<usize>) -> isize {
+    let timeout = to_timespec(timeout_ms);
+    let timeout_ptr = timeout
+        .as_ref()
+        .map_or(core::ptr::null(), |ts| ts as *const _);
+    syscall::sys_ppoll(fds.as_mut_ptr() as *mut u8, fds.len(), timeout_ptr)
+}
+
+/// The fd sets only cover fds below 64, the ready ones are left set
+pub fn select(
+    nfds: usize,
+    read_fds: Option<&mut u64>,
+    write_fds: Option<&mut u64>,
+    timeout_ms: Option<usize>,
+) -> isize {
+    let timeout = to_timespec(timeout_ms);
+    let timeout_ptr = timeout
+        .as_ref()
+        .map_or(core::ptr::null(), |ts| ts as *const _);
+    let read_ptr = read_fds.map_or(core::ptr::null_mut(), |set| set as *mut _);
+    let write_ptr = write_fds.map_or(core::ptr::null_mut(), |set| set as *mut _);
+    syscall::sys_pselect6(nfds, read_ptr, write_ptr, timeout_ptr)
+}
+
+pub const EPOLL_CTL_ADD: usize = 1;
+pub const EPOLL_CTL_DEL: usize = 2;
+pub const EPOLL_CTL_MOD: usize = 3;
+pub const EPOLLIN: u32 = 0x1;
+pub const EPOLLONESHOT: u32 = 1 << 30;
+pub const EPOLLET: u32 = 1 << 31;
+
+// region EpollEvent begin
+/// struct epoll_event, only packed on x86_64
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EpollEvent {
+    pub events: u32,
+    pub data: u64,
+}
+
+impl EpollEvent {
+    pub fn new(events: u32, data: u64) -> Self {
+        Self { events, data }
+    }
+}
+// region EpollEvent end
+
+pub fn epoll_create() -> isize {
+    syscall::sys_epoll_create1(0)
+}
+
+/// `event` is ignored by EPOLL_CTL_DEL
+pub fn epoll_ctl(epoll_fd: usize, op: usize, fd: usize, event: EpollEvent) -> isize {
+    syscall::sys_epoll_ctl(epoll_fd, op, fd, &event as *const _ as *const u8)
+}
+
+/// Return the number of events filled in, a negative timeout waits forever
+pub fn epoll_wait(epoll_fd: usize, events: &mut [EpollEvent], timeout_ms: i32) -> isize {
+    syscall::sys_epoll_pwait(
+        epoll_fd,
+        events.as_mut_ptr() as *mut u8,
+        events.len(),
+        timeout_ms,
+    )
+}
+
+pub const EFD_SEMAPHORE: usize = 1;
+
+pub fn eventfd(init_value: usize, flags: usize) -> isize {
+    syscall::sys_eventfd2(init_value, flags)
+}
+
+pub const CLOCK_MONOTONIC: usize = 1;
+
+pub fn timerfd_create(clock_id: usize) -> isize {
+    syscall::sys_timerfd_create(clock_id, 0)
+}
+
+/// Arm a relative timer, a zero interval fires once
+pub fn timerfd_settime(fd: usize, value_ms: usize, interval_ms: usize) -> isize {
+    let new = [
+        interval_ms / 1000,
+        interval_ms % 1000 * 1_000_000,
+        value_ms / 1000,
+        value_ms % 1000 * 1_000_000,
+    ];
+    let mut old = [0; 4];
+    syscall::sys_timerfd_settime(fd, 0, &new, &mut old)
+}
+
+/// A new signalfd taking the signals in `mask`
+pub fn signalfd(mask: u64, flags: usize) -> isize {
+    syscall::sys_signalfd4(-1, &mask, flags)
+}